@@ -0,0 +1,99 @@
+//! Local send throughput
+//!
+//! Messages are sent through `Address` from the actor's own thread,
+//! handlers reply with immediate results. Each benchmark is sampled
+//! several times, minimum and mean time per message are reported.
+//! Run with `cargo bench --bench local_send`.
+extern crate actix;
+extern crate futures;
+
+use std::time::{Duration, Instant};
+use futures::Future;
+use actix::prelude::*;
+
+const MESSAGES: usize = 100_000;
+const SAMPLES: usize = 10;
+
+struct Ping(u64);
+struct Stop;
+
+struct Counter(u64);
+
+impl Actor for Counter {}
+
+impl MessageResponse<Ping> for Counter {
+    type Item = u64;
+    type Error = ();
+}
+
+impl MessageHandler<Ping> for Counter {
+    fn handle(&mut self, msg: Ping, _: &mut Context<Self>) -> MessageFuture<Self, Ping> {
+        self.0 += msg.0;
+        self.0.to_result()
+    }
+}
+
+impl MessageResponse<Stop> for Counter {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<Stop> for Counter {
+    fn handle(&mut self, _: Stop, _: &mut Context<Self>) -> MessageFuture<Self, Stop> {
+        Arbiter::system().send(actix::SystemExit(0));
+        ().to_result()
+    }
+}
+
+// Send messages without response
+fn send(addr: &Address<Counter>) {
+    for i in 0..MESSAGES {
+        addr.send(Ping(i as u64));
+    }
+}
+
+// Send messages without response, handler time is measured
+fn send_timed(addr: &Address<Counter>) {
+    System::enable_timings();
+    send(addr)
+}
+
+// Send messages and wait for responses
+fn call(addr: &Address<Counter>) {
+    for i in 0..MESSAGES {
+        Arbiter::handle().spawn(
+            addr.call_fut(Ping(i as u64)).then(|_| Ok(())));
+    }
+}
+
+fn sample(f: fn(&Address<Counter>)) -> Duration {
+    let sys = System::new("bench".to_owned());
+    let addr: Address<_> = Counter(0).start();
+
+    let start = Instant::now();
+    f(&addr);
+    addr.send(Stop);
+    sys.run();
+    start.elapsed()
+}
+
+fn nanos(dur: Duration) -> u64 {
+    dur.as_secs() * 1_000_000_000 + u64::from(dur.subsec_nanos())
+}
+
+fn bench(name: &str, f: fn(&Address<Counter>)) {
+    // warm up
+    sample(f);
+
+    let samples: Vec<_> = (0..SAMPLES).map(|_| nanos(sample(f))).collect();
+    let min = samples.iter().min().unwrap();
+    let mean = samples.iter().sum::<u64>() / SAMPLES as u64;
+    println!("{}: min {} ns/msg, mean {} ns/msg ({} samples of {} messages)",
+             name, min / MESSAGES as u64, mean / MESSAGES as u64, SAMPLES, MESSAGES);
+}
+
+fn main() {
+    bench("local send", send);
+    bench("local send, timings", send_timed);
+    bench("local call", call);
+}
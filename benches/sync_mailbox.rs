@@ -0,0 +1,123 @@
+//! Cross-thread mailbox throughput
+//!
+//! Producer threads send messages to a single actor through `SyncAddress`.
+//! Baseline delivers the same messages to the same actor through
+//! futures' unbounded channel, which per-message sync mailbox was a copy of.
+//! Each benchmark is sampled several times, minimum and mean time per
+//! message are reported along with the change against the baseline.
+//! Run with `cargo bench --bench sync_mailbox`.
+extern crate actix;
+extern crate futures;
+
+use std::thread;
+use std::time::{Duration, Instant};
+use futures::sync::mpsc;
+use actix::prelude::*;
+
+const MESSAGES: usize = 1_000_000;
+const SAMPLES: usize = 5;
+
+struct Ping;
+
+struct Counter {
+    count: usize,
+    total: usize,
+}
+
+impl Actor for Counter {}
+
+impl StreamHandler<Ping> for Counter {}
+
+impl MessageResponse<Ping> for Counter {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<Ping> for Counter {
+    fn handle(&mut self, _: Ping, _: &mut Context<Self>) -> MessageFuture<Self, Ping> {
+        self.count += 1;
+        if self.count == self.total {
+            Arbiter::system().send(actix::SystemExit(0));
+        }
+        ().to_result()
+    }
+}
+
+// Send messages through actor's sync mailbox
+fn mailbox(producers: usize) -> Duration {
+    let sys = System::new("bench".to_owned());
+
+    let per_producer = MESSAGES / producers;
+    let addr: SyncAddress<_> = Counter{count: 0, total: per_producer * producers}.start();
+
+    let start = Instant::now();
+    let handles: Vec<_> = (0..producers).map(|_| {
+        let addr = addr.clone();
+        thread::spawn(move || {
+            for _ in 0..per_producer {
+                addr.send(Ping);
+            }
+        })
+    }).collect();
+
+    sys.run();
+    let elapsed = start.elapsed();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    elapsed
+}
+
+// Send messages through futures' channel registered as actor's stream
+fn baseline(producers: usize) -> Duration {
+    let sys = System::new("bench".to_owned());
+
+    let per_producer = MESSAGES / producers;
+    let (tx, rx) = mpsc::unbounded();
+    let _: () = Counter{count: 0, total: per_producer * producers}.start_with(rx);
+
+    let start = Instant::now();
+    let handles: Vec<_> = (0..producers).map(|_| {
+        let tx = tx.clone();
+        thread::spawn(move || {
+            for _ in 0..per_producer {
+                let _ = tx.unbounded_send(Ping);
+            }
+        })
+    }).collect();
+
+    sys.run();
+    let elapsed = start.elapsed();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    elapsed
+}
+
+fn nanos(dur: Duration) -> u64 {
+    dur.as_secs() * 1_000_000_000 + u64::from(dur.subsec_nanos())
+}
+
+// Returns minimum time per message
+fn bench(name: &str, producers: usize, f: fn(usize) -> Duration) -> u64 {
+    // warm up
+    f(producers);
+
+    let samples: Vec<_> = (0..SAMPLES).map(|_| nanos(f(producers))).collect();
+    let min = samples.iter().min().unwrap() / MESSAGES as u64;
+    let mean = samples.iter().sum::<u64>() / SAMPLES as u64 / MESSAGES as u64;
+    println!("{}, {} producer(s): min {} ns/msg, mean {} ns/msg ({} samples of {} messages)",
+             name, producers, min, mean, SAMPLES, MESSAGES);
+    min
+}
+
+fn main() {
+    for producers in &[1, 2, 4, 8] {
+        let base = bench("baseline", *producers, baseline);
+        let new = bench("sync mailbox", *producers, mailbox);
+        println!("{} producer(s): {:+.1}% against baseline\n",
+                 producers, (new as f64 - base as f64) * 100.0 / base as f64);
+    }
+}
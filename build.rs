@@ -1,6 +1,38 @@
 extern crate skeptic;
 
+use std::env;
+use std::process::Command;
+
 fn main() {
+    let minor = rustc_minor_version().unwrap_or(0);
+
+    // `std::mem::MaybeUninit` is stable since rust 1.36,
+    // older compilers do not store envelopes inline.
+    println!("cargo:rustc-check-cfg=cfg(actix_maybe_uninit)");
+    if minor >= 36 {
+        println!("cargo:rustc-cfg=actix_maybe_uninit");
+    }
+
+    // `std::any::type_name()` is stable since rust 1.38,
+    // older compilers use placeholder type names.
+    println!("cargo:rustc-check-cfg=cfg(actix_type_name)");
+    if minor >= 38 {
+        println!("cargo:rustc-cfg=actix_type_name");
+    }
+
     // generates doc tests for `README.md`.
     skeptic::generate_doc_tests(&["README.md"]);
 }
+
+fn rustc_minor_version() -> Option<u32> {
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_owned());
+    let output = match Command::new(rustc).arg("--version").output() {
+        Ok(output) => output,
+        Err(_) => return None,
+    };
+    // "rustc 1.20.0 (f3d6973f4 2017-08-27)"
+    let version = String::from_utf8_lossy(&output.stdout).into_owned();
+    version.split_whitespace().nth(1)
+        .and_then(|version| version.split('.').nth(1))
+        .and_then(|minor| minor.parse().ok())
+}
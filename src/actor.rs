@@ -76,6 +76,7 @@ impl<T> ActixActor for T where T: Actor {}
 /// `M` is message which can be handled by actor
 /// `E` optional error type, if message handler is used for handling messages
 ///  from Future or Stream, then `E` type has to be set to correspondent `Error` type.
+///  Stream errors are handled by `StreamHandler::error` method.
 #[allow(unused_variables)]
 pub trait MessageHandler<M, E=()> where Self: Actor + MessageResponse<M>
 {
@@ -99,7 +100,8 @@ pub trait MessageResponse<M> where Self: Actor {
 /// Stream handler
 ///
 /// `StreamHandler` is an extension of a `MessageHandler` with several stream specific
-/// methods.
+/// methods. Stream errors are handled by `StreamHandler::error` method,
+/// return value controls further stream processing.
 #[allow(unused_variables)]
 pub trait StreamHandler<M, E=()>: MessageResponse<M>
     where Self: Actor
@@ -107,7 +109,59 @@ pub trait StreamHandler<M, E=()>: MessageResponse<M>
     /// Method is called when stream get polled first time.
     fn started(&mut self, ctx: &mut Context<Self>) {}
 
+    /// Method is called when stream emits error. By default stream processing stops.
+    fn error(&mut self, err: E, ctx: &mut Context<Self>) -> StreamControl {
+        StreamControl::StopStream
+    }
+
     /// Method is called when stream finishes.
     fn finished(&mut self, ctx: &mut Context<Self>) {}
 
+    /// Method is called when stream finishes, `reason` indicates why stream ended.
+    /// By default it calls `finished` method.
+    fn stream_finished(&mut self, reason: StreamEnd, ctx: &mut Context<Self>) {
+        self.finished(ctx)
+    }
+
+}
+
+/// Stream error handling result
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum StreamControl {
+    /// Continue stream processing
+    Continue,
+    /// Stop stream processing, `StreamHandler::stream_finished` get called
+    StopStream,
+    /// Stop stream processing and stop actor
+    StopActor,
+}
+
+/// Reason of stream completion
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum StreamEnd {
+    /// Stream has no more items
+    Completed,
+    /// Stream processing stopped after error
+    Failed,
+}
+
+/// Sink handler
+///
+/// `SinkHandler` allows actor to observe state of the sink registered in
+/// actor's context. `E` is sink's error type. Sink events get reported
+/// only after `Context::observe_sink()` call, errors of unobserved sinks
+/// are logged.
+#[allow(unused_variables)]
+pub trait SinkHandler<E>
+    where Self: Actor
+{
+    /// Method is called when sink fails. Sink is closed after error and
+    /// all pending items get dropped.
+    fn error(&mut self, err: E, ctx: &mut Context<Self>) {}
+
+    /// Method is called when all buffered items get sent and flushed.
+    fn drained(&mut self, ctx: &mut Context<Self>) {}
+
+    /// Method is called when sink get closed with `Sink::close()` method.
+    fn closed(&mut self, ctx: &mut Context<Self>) {}
 }
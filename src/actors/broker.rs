@@ -0,0 +1,261 @@
+//! Publish/subscribe broker
+//!
+//! `Broker<M>` actor delivers published messages of type `M` to all interested
+//! subscribers. Subscriber can subscribe to all messages of type `M` or only to
+//! messages published with specific topic. Each subscriber receives clone of
+//! the published message. Closed subscribers get removed automatically.
+//!
+//! `Subscribe` message requires `Send` subscriber, i.e. `SyncAddress`
+//! subscriber, and could be sent to any broker. Subscribers of local
+//! `Address` could be registered only in arbiter's broker with
+//! `SubscribeLocal` message.
+//!
+//! Broker could be used per arbiter, `Broker::from_registry()` returns address
+//! of the broker registered in current arbiter's registry, or system-wide,
+//! `Broker::from_system_registry()` returns address of the broker shared
+//! across all arbiters.
+//!
+//! # Examples
+//!
+//! ```rust
+//! extern crate actix;
+//!
+//! use actix::prelude::*;
+//! use actix::actors::broker::{Broker, Publish, Subscribe};
+//!
+//! #[derive(Clone)]
+//! struct Event(usize);
+//!
+//! struct Listener;
+//!
+//! impl Actor for Listener {}
+//!
+//! impl MessageResponse<Event> for Listener {
+//!     type Item = ();
+//!     type Error = ();
+//! }
+//!
+//! impl MessageHandler<Event> for Listener {
+//!     fn handle(&mut self, msg: Event, _: &mut Context<Self>) -> MessageFuture<Self, Event> {
+//!         println!("Event: {}", msg.0);
+//!         Arbiter::system().send(actix::SystemExit(0));
+//!         ().to_result()
+//!     }
+//! }
+//!
+//! fn main() {
+//!    let sys = System::new("test".to_owned());
+//!
+//!    let broker = Broker::<Event>::from_registry();
+//!
+//!    let addr: SyncAddress<_> = Listener.start();
+//!    broker.send(Subscribe::topic("events", addr.subscriber()));
+//!    broker.send(Publish::topic("events", Event(1)));
+//!
+//!    sys.run();
+//! }
+//! ```
+use prelude::*;
+
+/// Subscription identifier, it is required for unsubscribe operation.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub struct SubscriptionId(usize);
+
+/// Subscribe to messages of type `M`.
+///
+/// Result of this message is subscription identifier.
+pub struct Subscribe<M: 'static> {
+    topic: Option<String>,
+    subscriber: Box<Subscriber<M> + Send>,
+}
+
+impl<M: 'static> Subscribe<M> {
+
+    /// Subscribe to all messages of type `M`
+    pub fn all(subscriber: Box<Subscriber<M> + Send>) -> Subscribe<M> {
+        Subscribe{topic: None, subscriber: subscriber}
+    }
+
+    /// Subscribe to messages published with specific topic
+    pub fn topic<T: Into<String>>(topic: T, subscriber: Box<Subscriber<M> + Send>)
+                                  -> Subscribe<M>
+    {
+        Subscribe{topic: Some(topic.into()), subscriber: subscriber}
+    }
+}
+
+/// Subscribe local subscriber to messages of type `M`.
+///
+/// Message is not `Send`, so it could be sent only to arbiter's broker,
+/// see `Broker::from_registry()`. Result of this message is subscription
+/// identifier.
+pub struct SubscribeLocal<M: 'static> {
+    topic: Option<String>,
+    subscriber: Box<Subscriber<M>>,
+}
+
+impl<M: 'static> SubscribeLocal<M> {
+
+    /// Subscribe to all messages of type `M`
+    pub fn all(subscriber: Box<Subscriber<M>>) -> SubscribeLocal<M> {
+        SubscribeLocal{topic: None, subscriber: subscriber}
+    }
+
+    /// Subscribe to messages published with specific topic
+    pub fn topic<T: Into<String>>(topic: T, subscriber: Box<Subscriber<M>>)
+                                  -> SubscribeLocal<M>
+    {
+        SubscribeLocal{topic: Some(topic.into()), subscriber: subscriber}
+    }
+}
+
+/// Remove subscription
+pub struct Unsubscribe(pub SubscriptionId);
+
+/// Publish message to subscribers
+pub struct Publish<M> {
+    topic: Option<String>,
+    msg: M,
+}
+
+impl<M> Publish<M> {
+
+    /// Publish message without topic, only subscribers to all messages
+    /// of type `M` receive it.
+    pub fn new(msg: M) -> Publish<M> {
+        Publish{topic: None, msg: msg}
+    }
+
+    /// Publish message with topic. Message is delivered to topic subscribers
+    /// and to subscribers to all messages of type `M`.
+    pub fn topic<T: Into<String>>(topic: T, msg: M) -> Publish<M> {
+        Publish{topic: Some(topic.into()), msg: msg}
+    }
+}
+
+struct Subscription<M: 'static> {
+    id: SubscriptionId,
+    topic: Option<String>,
+    subscriber: Box<Subscriber<M>>,
+}
+
+/// Publish/subscribe broker for messages of type `M`
+pub struct Broker<M: 'static> {
+    next_id: usize,
+    subscriptions: Vec<Subscription<M>>,
+}
+
+impl<M: 'static> Default for Broker<M> {
+    fn default() -> Self {
+        Broker{next_id: 0, subscriptions: Vec::new()}
+    }
+}
+
+impl<M> Broker<M> where M: Clone + Send + 'static {
+
+    fn subscribe(&mut self, topic: Option<String>, subscriber: Box<Subscriber<M>>)
+                 -> SubscriptionId
+    {
+        // remove dead subscribers
+        self.subscriptions.retain(|s| !s.subscriber.is_closed());
+
+        let id = SubscriptionId(self.next_id);
+        self.next_id += 1;
+        self.subscriptions.push(
+            Subscription{id: id, topic: topic, subscriber: subscriber});
+        id
+    }
+
+    /// Get address of the arbiter's broker. If broker is not running yet,
+    /// new broker get started in current arbiter.
+    pub fn from_registry() -> Address<Broker<M>> {
+        Arbiter::registry().get::<Broker<M>>()
+    }
+
+    /// Get address of the system wide broker. If broker is not running yet,
+    /// new broker get started in current arbiter.
+    pub fn from_system_registry() -> SyncAddress<Broker<M>> {
+        let registry = Arbiter::system_registry();
+        if let Some(addr) = registry.query::<Broker<M>>() {
+            return addr
+        }
+
+        let addr: SyncAddress<_> = Broker::<M>::default().start();
+        match registry.register(addr) {
+            Ok(()) => (),
+            // broker has been registered from other arbiter
+            Err(_) => debug!("System broker is registered already"),
+        }
+        registry.query::<Broker<M>>().expect("Broker has been registered")
+    }
+}
+
+impl<M: 'static> Actor for Broker<M> {}
+
+impl<M> MessageResponse<Subscribe<M>> for Broker<M> where M: Clone + Send + 'static {
+    type Item = SubscriptionId;
+    type Error = ();
+}
+
+impl<M> MessageHandler<Subscribe<M>> for Broker<M> where M: Clone + Send + 'static {
+
+    fn handle(&mut self, msg: Subscribe<M>, _: &mut Context<Self>)
+              -> MessageFuture<Self, Subscribe<M>>
+    {
+        self.subscribe(msg.topic, msg.subscriber).to_result()
+    }
+}
+
+impl<M> MessageResponse<SubscribeLocal<M>> for Broker<M> where M: Clone + Send + 'static {
+    type Item = SubscriptionId;
+    type Error = ();
+}
+
+impl<M> MessageHandler<SubscribeLocal<M>> for Broker<M> where M: Clone + Send + 'static {
+
+    fn handle(&mut self, msg: SubscribeLocal<M>, _: &mut Context<Self>)
+              -> MessageFuture<Self, SubscribeLocal<M>>
+    {
+        self.subscribe(msg.topic, msg.subscriber).to_result()
+    }
+}
+
+impl<M> MessageResponse<Unsubscribe> for Broker<M> where M: Clone + Send + 'static {
+    type Item = ();
+    type Error = ();
+}
+
+impl<M> MessageHandler<Unsubscribe> for Broker<M> where M: Clone + Send + 'static {
+
+    fn handle(&mut self, msg: Unsubscribe, _: &mut Context<Self>)
+              -> MessageFuture<Self, Unsubscribe>
+    {
+        self.subscriptions.retain(|s| s.id != msg.0);
+        ().to_result()
+    }
+}
+
+impl<M> MessageResponse<Publish<M>> for Broker<M> where M: Clone + Send + 'static {
+    type Item = ();
+    type Error = ();
+}
+
+impl<M> MessageHandler<Publish<M>> for Broker<M> where M: Clone + Send + 'static {
+
+    fn handle(&mut self, msg: Publish<M>, _: &mut Context<Self>)
+              -> MessageFuture<Self, Publish<M>>
+    {
+        let Publish{topic, msg} = msg;
+        self.subscriptions.retain(|s| {
+            // remove dead subscribers
+            if s.subscriber.is_closed() {
+                return false
+            }
+            if s.topic.is_none() || s.topic == topic {
+                s.subscriber.send(msg.clone());
+            }
+            !s.subscriber.is_closed()
+        });
+        ().to_result()
+    }
+}
@@ -0,0 +1,380 @@
+//! Tcp connector service
+//!
+//! `Connector` actor resolves `host:port` address and establishes tcp connection.
+//! Name resolution is done with [`Resolver`](trait.Resolver.html) object,
+//! by default `Connector` uses system resolver, each lookup runs in a separate
+//! thread so event loop is not blocked. If connection can not
+//! be established, connector waits for some time and then tries again.
+//! `Connector` registers itself in system registry, so only one instance of
+//! connector can run. Returned `TcpStream` is bound to event loop of the
+//! `Connector`'s arbiter.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! extern crate actix;
+//!
+//! use actix::prelude::*;
+//! use actix::actors::connector;
+//!
+//! struct Client;
+//!
+//! impl Actor for Client {
+//!
+//!     fn started(&mut self, ctx: &mut Context<Self>) {
+//!         connector::Connector::from_registry()
+//!             .call_fut(connector::Connect::host("localhost:8080"))
+//!             .actfuture()
+//!             .map(|res, _: &mut Client, _: &mut Context<Client>| {
+//!                 match res {
+//!                     Ok(_stream) => println!("Connected"),
+//!                     Err(err) => println!("Can not connect: {}", err),
+//!                 }
+//!                 Arbiter::system().send(actix::SystemExit(0));
+//!             })
+//!             .map_err(|_, _: &mut Client, _: &mut Context<Client>| ())
+//!             .spawn(ctx);
+//!     }
+//! }
+//!
+//! fn main() {
+//!    // initialize system
+//!    let sys = System::new("test".to_owned());
+//!
+//!    // Start client
+//!    let _: () = Client.start();
+//!
+//!    sys.run();
+//! }
+//! ```
+use std::{fmt, io, thread};
+use std::error::Error;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::time::Duration;
+use std::collections::VecDeque;
+
+use futures::{future, Async, Future, Poll};
+use futures::sync::oneshot;
+use tokio_core::net::{TcpStream, TcpStreamNew};
+
+use prelude::*;
+use clock::Delay;
+
+/// Connect to specified host.
+///
+/// Host address has to be in `host:port` form.
+pub struct Connect {
+    host: String,
+    timeout: Option<Duration>,
+}
+
+impl Connect {
+    /// Create `Connect` message for specified host
+    pub fn host<T: Into<String>>(host: T) -> Connect {
+        Connect{host: host.into(), timeout: None}
+    }
+
+    /// Set connect timeout for this request.
+    ///
+    /// By default connector's timeout is used.
+    pub fn timeout(mut self, timeout: Duration) -> Connect {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+/// Connector errors
+#[derive(Debug)]
+pub enum ConnectorError {
+    /// Failed to resolve the hostname
+    Resolver(String),
+    /// Address is invalid
+    InvalidInput,
+    /// No dns records
+    NoRecords,
+    /// Connecting took too long
+    Timeout,
+    /// Connection io error
+    IoError(io::Error),
+}
+
+impl fmt::Display for ConnectorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConnectorError::Resolver(ref msg) =>
+                write!(f, "Failed resolving hostname: {}", msg),
+            ConnectorError::IoError(ref err) => write!(f, "{}", err),
+            _ => write!(f, "{}", self.description()),
+        }
+    }
+}
+
+impl Error for ConnectorError {
+    fn description(&self) -> &str {
+        match *self {
+            ConnectorError::Resolver(_) => "Failed resolving hostname",
+            ConnectorError::InvalidInput => "Invalid input",
+            ConnectorError::NoRecords => "No dns records found for the input",
+            ConnectorError::Timeout => "Timeout out while establishing connection",
+            ConnectorError::IoError(ref err) => err.description(),
+        }
+    }
+}
+
+impl From<io::Error> for ConnectorError {
+    fn from(err: io::Error) -> ConnectorError {
+        ConnectorError::IoError(err)
+    }
+}
+
+/// Resolver future
+pub type ResolverFuture = Box<Future<Item=Vec<SocketAddr>, Error=ConnectorError>>;
+
+/// Host name resolver
+///
+/// Resolver is used by `Connector` actor for host name resolution.
+pub trait Resolver: 'static {
+
+    /// Resolve host name and port to list of socket addresses.
+    fn resolve(&mut self, host: &str, port: u16) -> ResolverFuture;
+}
+
+/// Default resolver, it uses system resolver.
+///
+/// System resolver is blocking, so each lookup runs in a new thread
+/// and result is sent back to the event loop.
+pub struct DefaultResolver;
+
+impl Resolver for DefaultResolver {
+
+    fn resolve(&mut self, host: &str, port: u16) -> ResolverFuture {
+        let (tx, rx) = oneshot::channel();
+        let host = host.to_owned();
+
+        let res = thread::Builder::new()
+            .name("actix-resolver".to_owned())
+            .spawn(move || {
+                let _ = tx.send(
+                    (host.as_str(), port).to_socket_addrs()
+                        .map(|addrs| addrs.collect())
+                        .map_err(|err| ConnectorError::Resolver(format!("{}", err))));
+            });
+        if let Err(err) = res {
+            return Box::new(future::err(err.into()))
+        }
+
+        Box::new(rx.then(|res| match res {
+            Ok(res) => res,
+            Err(_) => Err(ConnectorError::Resolver("Resolver thread is gone".to_owned())),
+        }))
+    }
+}
+
+/// Tcp connector actor
+pub struct Connector {
+    resolver: Box<Resolver>,
+    timeout: Duration,
+    retries: usize,
+    backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl Default for Connector {
+    fn default() -> Connector {
+        Connector::new(DefaultResolver)
+    }
+}
+
+impl Connector {
+
+    /// Create new connector with specific resolver.
+    pub fn new<R: Resolver>(resolver: R) -> Connector {
+        Connector {
+            resolver: Box::new(resolver),
+            timeout: Duration::from_secs(1),
+            retries: 3,
+            backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+
+    /// Set connect timeout for each address. By default timeout is 1 second.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set number of reconnect attempts. By default connector re-tries 3 times.
+    pub fn retries(mut self, retries: usize) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Set reconnect backoff. Initial backoff doubles after each failed attempt
+    /// but never exceeds `max` value.
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Query system registry for connector actor. If connector is not registered
+    /// starts default connector.
+    pub fn from_registry() -> SyncAddress<Connector> {
+        if let Some(addr) = Arbiter::system_registry().query::<Connector>() {
+            addr
+        } else {
+            Connector::run()
+        }
+    }
+}
+
+impl Actor for Connector {
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        // allow only one instance of the actor
+        if Arbiter::system_registry().register(ctx.address()).is_err() {
+            ctx.stop();
+        }
+    }
+}
+
+impl MessageResponse<Connect> for Connector {
+    type Item = TcpStream;
+    type Error = ConnectorError;
+}
+
+impl MessageHandler<Connect> for Connector {
+
+    fn handle(&mut self, msg: Connect, _: &mut Context<Self>) -> MessageFuture<Self, Connect>
+    {
+        let (host, port) = match parse_host(&msg.host) {
+            Ok(val) => val,
+            Err(err) => return err.to_error(),
+        };
+        let timeout = msg.timeout.unwrap_or(self.timeout);
+        let state = ConnectState::Resolving(self.resolver.resolve(&host, port));
+
+        ConnectFut {
+            host: host,
+            port: port,
+            timeout: timeout,
+            state: state,
+            addrs: VecDeque::new(),
+            attempt: 0,
+            backoff: self.backoff,
+            error: None,
+        }.into()
+    }
+}
+
+fn parse_host(host: &str) -> Result<(String, u16), ConnectorError> {
+    let mut parts = host.rsplitn(2, ':');
+    let port = parts.next().and_then(|port| port.parse::<u16>().ok());
+    let host = parts.next().map(|host| host.trim_left_matches('[').trim_right_matches(']'));
+
+    match (host, port) {
+        (Some(host), Some(port)) if !host.is_empty() => Ok((host.to_owned(), port)),
+        _ => Err(ConnectorError::InvalidInput),
+    }
+}
+
+enum ConnectState {
+    Resolving(ResolverFuture),
+    Connecting(TcpStreamNew, Delay),
+    Backoff(Delay),
+    Done,
+}
+
+struct ConnectFut {
+    host: String,
+    port: u16,
+    timeout: Duration,
+    state: ConnectState,
+    addrs: VecDeque<SocketAddr>,
+    attempt: usize,
+    backoff: Duration,
+    error: Option<ConnectorError>,
+}
+
+impl ConnectFut {
+
+    /// Start connecting to next address, on failure schedule next attempt
+    fn next(&mut self, act: &mut Connector) -> Result<ConnectState, ConnectorError> {
+        if let Some(addr) = self.addrs.pop_front() {
+            let timeout = Delay::new(self.timeout)?;
+            return Ok(ConnectState::Connecting(
+                TcpStream::connect(&addr, Arbiter::handle()), timeout))
+        }
+
+        let err = self.error.take().unwrap_or(ConnectorError::NoRecords);
+        if self.attempt >= act.retries {
+            return Err(err)
+        }
+        warn!("Can not connect to {}:{}: {}, reconnecting in {:?}",
+              self.host, self.port, err, self.backoff);
+
+        self.attempt += 1;
+        let timeout = Delay::new(self.backoff)?;
+        self.backoff = ::std::cmp::min(self.backoff * 2, act.max_backoff);
+        Ok(ConnectState::Backoff(timeout))
+    }
+}
+
+impl ActorFuture for ConnectFut {
+    type Item = TcpStream;
+    type Error = ConnectorError;
+    type Actor = Connector;
+
+    fn poll(&mut self, act: &mut Connector, _: &mut Context<Connector>)
+            -> Poll<Self::Item, Self::Error>
+    {
+        loop {
+            let state = match ::std::mem::replace(&mut self.state, ConnectState::Done) {
+                ConnectState::Resolving(mut fut) => match fut.poll() {
+                    Ok(Async::Ready(addrs)) => {
+                        self.addrs = addrs.into_iter().collect();
+                        self.next(act)?
+                    }
+                    Ok(Async::NotReady) => {
+                        self.state = ConnectState::Resolving(fut);
+                        return Ok(Async::NotReady)
+                    }
+                    Err(err) => {
+                        self.error = Some(err);
+                        self.next(act)?
+                    }
+                },
+                ConnectState::Connecting(mut fut, mut timeout) => match fut.poll() {
+                    Ok(Async::Ready(stream)) => return Ok(Async::Ready(stream)),
+                    Ok(Async::NotReady) => match timeout.poll() {
+                        Ok(Async::NotReady) => {
+                            self.state = ConnectState::Connecting(fut, timeout);
+                            return Ok(Async::NotReady)
+                        }
+                        Ok(Async::Ready(_)) => {
+                            self.error = Some(ConnectorError::Timeout);
+                            self.next(act)?
+                        }
+                        Err(err) => return Err(err.into()),
+                    },
+                    Err(err) => {
+                        self.error = Some(err.into());
+                        self.next(act)?
+                    }
+                },
+                ConnectState::Backoff(mut timeout) => match timeout.poll() {
+                    Ok(Async::NotReady) => {
+                        self.state = ConnectState::Backoff(timeout);
+                        return Ok(Async::NotReady)
+                    }
+                    Ok(Async::Ready(_)) =>
+                        ConnectState::Resolving(act.resolver.resolve(&self.host, self.port)),
+                    Err(err) => return Err(err.into()),
+                },
+                ConnectState::Done => panic!("cannot poll ConnectFut twice"),
+            };
+            self.state = state;
+        }
+    }
+}
@@ -0,0 +1,181 @@
+//! Dead letters
+//!
+//! Message that can not be delivered to its target actor is dropped, but
+//! descriptor of the message is sent to the system-wide `DeadLetters` actor.
+//! Message is undeliverable if target actor's context is stopped,
+//! or if supervisor stops while it holds message for the supervised actor.
+//!
+//! Dead letters are collected only if `DeadLetters` actor is running,
+//! `DeadLetters::from_registry()` starts it on first use. `DeadLetters` actor
+//! logs every dead letter and forwards it to subscribers.
+//!
+//! # Examples
+//!
+//! ```rust
+//! extern crate actix;
+//!
+//! use actix::prelude::*;
+//! use actix::actors::dead_letters::{DeadLetter, DeadLetters, Subscribe};
+//!
+//! struct Alert;
+//!
+//! impl Actor for Alert {}
+//!
+//! impl MessageResponse<DeadLetter> for Alert {
+//!     type Item = ();
+//!     type Error = ();
+//! }
+//!
+//! impl MessageHandler<DeadLetter> for Alert {
+//!     fn handle(&mut self, msg: DeadLetter, _: &mut Context<Self>)
+//!               -> MessageFuture<Self, DeadLetter>
+//!     {
+//!         println!("{} is not delivered to {}: {:?}",
+//!                  msg.message_type, msg.target_type, msg.reason);
+//!         ().to_result()
+//!     }
+//! }
+//!
+//! fn main() {
+//!    let sys = System::new("test".to_owned());
+//!
+//!    let addr: SyncAddress<_> = Alert.start();
+//!    DeadLetters::from_registry().send(Subscribe(addr.subscriber()));
+//!
+//!    Arbiter::system().send(actix::SystemExit(0));
+//!    sys.run();
+//! }
+//! ```
+use std::any::TypeId;
+
+use prelude::*;
+use identity::ActorId;
+use utils;
+
+/// Reason why message is not delivered
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DeadLetterReason {
+    /// Target actor's context is stopped
+    Stopped,
+    /// Supervisor is stopped before message is passed to supervised actor
+    SupervisorStopped,
+}
+
+/// Descriptor of the undeliverable message
+#[derive(Clone, Debug)]
+pub struct DeadLetter {
+    /// Identifier of the target actor
+    pub target_id: ActorId,
+    /// Type name of the target actor, placeholder on rust older than 1.38
+    pub target_type: &'static str,
+    /// Type name of the message, placeholder on rust older than 1.38
+    pub message_type: &'static str,
+    /// Reason why message is not delivered
+    pub reason: DeadLetterReason,
+}
+
+/// Subscribe to dead letters
+pub struct Subscribe(pub Box<Subscriber<DeadLetter> + Send>);
+
+/// System-wide dead letters actor
+#[derive(Default)]
+pub struct DeadLetters {
+    subscribers: Vec<Box<Subscriber<DeadLetter> + Send>>,
+}
+
+impl DeadLetters {
+
+    /// Get address of the dead letters actor. If actor is not running yet,
+    /// new actor get started in current arbiter.
+    pub fn from_registry() -> SyncAddress<DeadLetters> {
+        let registry = Arbiter::system_registry();
+        if let Some(addr) = registry.query::<DeadLetters>() {
+            return addr
+        }
+
+        let addr: SyncAddress<_> = DeadLetters::default().start();
+        match registry.register(addr) {
+            Ok(()) => (),
+            // actor has been registered from other arbiter, stop started actor
+            Err(addr) => {
+                debug!("Dead letters actor is registered already");
+                addr.send(Stop);
+            }
+        }
+        registry.query::<DeadLetters>().expect("DeadLetters has been registered")
+    }
+}
+
+impl Actor for DeadLetters {}
+
+/// Stop dead letters actor that lost registration
+struct Stop;
+
+impl MessageResponse<Stop> for DeadLetters {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<Stop> for DeadLetters {
+
+    fn handle(&mut self, _: Stop, ctx: &mut Context<Self>) -> MessageFuture<Self, Stop> {
+        ctx.stop();
+        ().to_result()
+    }
+}
+
+impl MessageResponse<DeadLetter> for DeadLetters {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<DeadLetter> for DeadLetters {
+
+    fn handle(&mut self, msg: DeadLetter, _: &mut Context<Self>)
+              -> MessageFuture<Self, DeadLetter>
+    {
+        warn!("Message {} is not delivered to {}({}): {:?}",
+              msg.message_type, msg.target_type, msg.target_id, msg.reason);
+
+        self.subscribers.retain(|s| !s.is_closed());
+        for subscriber in &self.subscribers {
+            subscriber.send(msg.clone());
+        }
+        ().to_result()
+    }
+}
+
+impl MessageResponse<Subscribe> for DeadLetters {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<Subscribe> for DeadLetters {
+
+    fn handle(&mut self, msg: Subscribe, _: &mut Context<Self>) -> MessageFuture<Self, Subscribe> {
+        self.subscribers.push(msg.0);
+        ().to_result()
+    }
+}
+
+/// Report undeliverable message to the dead letters actor
+pub(crate) fn report<A: Actor>(target_id: ActorId, message_id: TypeId,
+                               message_type: &'static str, reason: DeadLetterReason)
+{
+    // undeliverable dead letters are dropped silently
+    if message_id == TypeId::of::<DeadLetter>() {
+        return
+    }
+
+    let msg = DeadLetter {
+        target_id: target_id,
+        target_type: utils::type_name::<A>(),
+        message_type: message_type,
+        reason: reason,
+    };
+    match Arbiter::system_registry().query::<DeadLetters>() {
+        Some(addr) => addr.send(msg),
+        None => debug!("Message {} is not delivered to {}({}): {:?}",
+                       msg.message_type, msg.target_type, msg.target_id, msg.reason),
+    }
+}
@@ -1,4 +1,10 @@
 //! Helper actors
 
+pub mod broker;
+pub mod connector;
+pub mod dead_letters;
+#[cfg(feature="prometheus")]
+pub mod prometheus;
+pub mod router;
 #[cfg(feature="signal")]
 pub mod signal;
@@ -0,0 +1,336 @@
+//! Prometheus metrics endpoint
+//!
+//! `PrometheusExporter` actor serves actor metrics, collected by
+//! [`System::metrics()`](../../struct.System.html#method.metrics),
+//! in Prometheus text exposition format. Exporter accepts http connections
+//! and responds to `GET /metrics` requests, connection is closed after response.
+//! Connection that does not send complete request within 5 seconds is closed.
+//!
+//! Each actor's series are labeled with `actor_type`, `arbiter` and `actor_id`
+//! labels (and `name` for named actors), `actix_system_*` series are
+//! aggregated across all actors of the system.
+//!
+//! This module is available with `prometheus` feature.
+//!
+//! # Examples
+//!
+//! ```rust
+//! extern crate actix;
+//!
+//! use actix::prelude::*;
+//! use actix::actors::prometheus::PrometheusExporter;
+//!
+//! fn main() {
+//!    let sys = System::new("test".to_owned());
+//!
+//!    // start exporter on random port
+//!    let (addr, _): (_, Address<_>) =
+//!        PrometheusExporter::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+//!    println!("Metrics are available at http://{}/metrics", addr);
+//!
+//!    Arbiter::system().send(actix::SystemExit(0));
+//!    sys.run();
+//! }
+//! ```
+use std::io;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use bytes::BytesMut;
+use futures::Sink;
+use tokio_core::net::{TcpListener, TcpStream};
+use tokio_io::codec::{Decoder, Encoder};
+use tokio_io::io::WriteHalf;
+
+use prelude::*;
+use address::ActorAddress;
+use clock::Delay;
+use metrics::{ActorMetrics, Histogram, SystemMetrics};
+
+/// Maximum size of request head
+const MAX_HEAD_SIZE: usize = 8 * 1024;
+
+/// Time to receive request head in seconds
+const REQUEST_TIMEOUT: u64 = 5;
+
+/// Http endpoint for actor metrics
+pub struct PrometheusExporter;
+
+impl PrometheusExporter {
+
+    /// Bind to `addr` and start exporter actor in current arbiter.
+    /// Timing metrics of the system are enabled.
+    ///
+    /// Returns address exporter is listening on, it is useful if `addr` has zero port.
+    pub fn bind<Addr>(addr: &SocketAddr) -> io::Result<(SocketAddr, Addr)>
+        where PrometheusExporter: ActorAddress<PrometheusExporter, Addr>
+    {
+        let lst = TcpListener::bind(addr, Arbiter::handle())?;
+        let addr = lst.local_addr()?;
+        info!("Starting prometheus exporter on {}", addr);
+        System::enable_timings();
+        Ok((addr, PrometheusExporter.start_with(lst.incoming())))
+    }
+}
+
+impl Actor for PrometheusExporter {}
+
+#[doc(hidden)]
+impl StreamHandler<(TcpStream, SocketAddr), io::Error> for PrometheusExporter {
+
+    fn error(&mut self, err: io::Error, _: &mut Context<Self>) -> StreamControl {
+        error!("Error during accepting prometheus connection: {}", err);
+        StreamControl::Continue
+    }
+}
+
+impl MessageResponse<(TcpStream, SocketAddr)> for PrometheusExporter {
+    type Item = ();
+    type Error = ();
+}
+
+#[doc(hidden)]
+impl MessageHandler<(TcpStream, SocketAddr), io::Error> for PrometheusExporter {
+
+    fn handle(&mut self, msg: (TcpStream, SocketAddr), _: &mut Context<Self>)
+              -> MessageFuture<Self, (TcpStream, SocketAddr)>
+    {
+        debug!("New prometheus connection from {}", msg.1);
+        let (r, w) = msg.0.actix_framed(RequestCodec, ResponseCodec);
+        let _: () = Connection::create_with(r, move |_| Connection{writer: Some(w)});
+        ().to_result()
+    }
+}
+
+/// Parsed http request head
+struct Request {
+    method: String,
+    path: String,
+}
+
+/// Http response
+struct Response {
+    status: &'static str,
+    body: String,
+}
+
+struct RequestCodec;
+
+impl Decoder for RequestCodec {
+    type Item = Request;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Request>, io::Error> {
+        let end = match src.windows(4).position(|w| w == b"\r\n\r\n") {
+            Some(pos) => pos + 4,
+            None => if src.len() > MAX_HEAD_SIZE {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "Request is too large"))
+            } else {
+                return Ok(None)
+            },
+        };
+        let head = src.split_to(end);
+        let head = String::from_utf8_lossy(&head);
+        let mut parts = head.lines().next().unwrap_or("").split(' ');
+
+        match (parts.next(), parts.next()) {
+            (Some(method), Some(path)) => Ok(Some(Request{
+                method: method.to_owned(), path: path.to_owned()})),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidData, "Malformed request line")),
+        }
+    }
+}
+
+struct ResponseCodec;
+
+impl Encoder for ResponseCodec {
+    type Item = Response;
+    type Error = io::Error;
+
+    fn encode(&mut self, msg: Response, dst: &mut BytesMut) -> Result<(), io::Error> {
+        let head = format!(
+            "HTTP/1.1 {}\r\n\
+             Content-Type: text/plain; version=0.0.4\r\n\
+             Content-Length: {}\r\n\
+             Connection: close\r\n\r\n", msg.status, msg.body.len());
+        dst.extend_from_slice(head.as_bytes());
+        dst.extend_from_slice(msg.body.as_bytes());
+        Ok(())
+    }
+}
+
+/// Http connection, responds to first request and closes connection
+struct Connection {
+    writer: Option<ActixFramedWrite<WriteHalf<TcpStream>, ResponseCodec>>,
+}
+
+impl Actor for Connection {
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        // idle connections must not keep actor alive forever
+        match Delay::new(Duration::from_secs(REQUEST_TIMEOUT)) {
+            Ok(delay) => {
+                delay.actfuture()
+                    .then(|res, act: &mut Connection, ctx: &mut Context<Connection>| {
+                        if let Err(err) = res {
+                            error!("Prometheus request timeout failed: {}", err);
+                        }
+                        // response is in progress if writer is taken
+                        if act.writer.is_some() {
+                            debug!("Prometheus request timed out");
+                            ctx.stop();
+                        }
+                        fut::ok(())
+                    })
+                    .spawn(ctx);
+            }
+            Err(err) => {
+                error!("Can not start prometheus request timeout: {}", err);
+                ctx.stop();
+            }
+        }
+    }
+}
+
+impl StreamHandler<Request, io::Error> for Connection {
+
+    fn error(&mut self, err: io::Error, _: &mut Context<Self>) -> StreamControl {
+        debug!("Error during reading prometheus request: {}", err);
+        StreamControl::StopActor
+    }
+}
+
+impl MessageResponse<Request> for Connection {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<Request, io::Error> for Connection {
+
+    fn handle(&mut self, req: Request, ctx: &mut Context<Self>) -> MessageFuture<Self, Request> {
+        let resp = if req.method != "GET" {
+            Response{status: "405 Method Not Allowed", body: String::new()}
+        } else if req.path.split('?').next() != Some("/metrics") {
+            Response{status: "404 Not Found", body: String::new()}
+        } else {
+            Response{status: "200 OK", body: render(&System::metrics())}
+        };
+
+        if let Some(writer) = self.writer.take() {
+            writer.send(resp)
+                .actfuture()
+                .then(|res, _: &mut Connection, ctx: &mut Context<Connection>| {
+                    if let Err(err) = res {
+                        debug!("Error during writing prometheus response: {}", err);
+                    }
+                    ctx.stop();
+                    fut::ok(())
+                })
+                .spawn(ctx);
+        }
+        ().to_result()
+    }
+}
+
+/// Render metrics snapshot in prometheus text exposition format
+pub fn render(metrics: &SystemMetrics) -> String {
+    let mut out = String::new();
+
+    family(&mut out, "actix_actor_messages_received_total", "counter",
+           "Number of messages passed to message handlers");
+    for m in &metrics.actors {
+        sample(&mut out, "actix_actor_messages_received_total", &labels(m, ""), m.received);
+    }
+    family(&mut out, "actix_actor_messages_handled_total", "counter",
+           "Number of messages with completed response");
+    for m in &metrics.actors {
+        sample(&mut out, "actix_actor_messages_handled_total", &labels(m, ""), m.handled);
+    }
+    family(&mut out, "actix_actor_mailbox_messages", "gauge",
+           "Number of messages in actor's mailbox");
+    for m in &metrics.actors {
+        sample(&mut out, "actix_actor_mailbox_messages",
+               &labels(m, ",mailbox=\"unsync\""), m.mailbox);
+        sample(&mut out, "actix_actor_mailbox_messages",
+               &labels(m, ",mailbox=\"sync\""), m.sync_mailbox);
+    }
+    family(&mut out, "actix_actor_spawned_futures_total", "counter",
+           "Number of spawned response futures");
+    for m in &metrics.actors {
+        sample(&mut out, "actix_actor_spawned_futures_total", &labels(m, ""), m.spawned);
+    }
+    family(&mut out, "actix_actor_restarts_total", "counter",
+           "Number of restarts of supervised actor");
+    for m in &metrics.actors {
+        sample(&mut out, "actix_actor_restarts_total", &labels(m, ""), m.restarts);
+    }
+    family(&mut out, "actix_actor_handler_seconds", "histogram",
+           "Time spent in message handler");
+    for m in &metrics.actors {
+        histogram(&mut out, "actix_actor_handler_seconds", &labels(m, ""), &m.handler_time);
+    }
+    family(&mut out, "actix_actor_response_seconds", "histogram",
+           "Time from handler return till response future completion");
+    for m in &metrics.actors {
+        histogram(&mut out, "actix_actor_response_seconds", &labels(m, ""), &m.future_time);
+    }
+
+    // system wide series
+    family(&mut out, "actix_system_actors", "gauge", "Number of running actors");
+    sample(&mut out, "actix_system_actors", "", metrics.actors.len());
+    family(&mut out, "actix_system_messages_received_total", "counter",
+           "Number of messages passed to message handlers");
+    sample(&mut out, "actix_system_messages_received_total", "",
+           metrics.actors.iter().map(|m| m.received).sum());
+    family(&mut out, "actix_system_mailbox_messages", "gauge",
+           "Number of messages in mailboxes of all actors");
+    sample(&mut out, "actix_system_mailbox_messages", "",
+           metrics.actors.iter().map(|m| m.mailbox + m.sync_mailbox).sum());
+    family(&mut out, "actix_system_restarts_total", "counter",
+           "Number of restarts of supervised actors");
+    sample(&mut out, "actix_system_restarts_total", "",
+           metrics.actors.iter().map(|m| m.restarts).sum());
+
+    out
+}
+
+fn family(out: &mut String, name: &str, tp: &str, help: &str) {
+    let _ = write!(out, "# HELP {} {}\n# TYPE {} {}\n", name, help, name, tp);
+}
+
+fn sample(out: &mut String, name: &str, labels: &str, value: usize) {
+    if labels.is_empty() {
+        let _ = writeln!(out, "{} {}", name, value);
+    } else {
+        let _ = writeln!(out, "{}{{{}}} {}", name, labels, value);
+    }
+}
+
+fn histogram(out: &mut String, name: &str, labels: &str, hist: &Histogram) {
+    for &(bound, count) in &hist.buckets {
+        let _ = writeln!(out, "{}_bucket{{{},le=\"{}\"}} {}",
+                         name, labels, seconds(bound), count);
+    }
+    let _ = writeln!(out, "{}_bucket{{{},le=\"+Inf\"}} {}", name, labels, hist.count);
+    let _ = writeln!(out, "{}_sum{{{}}} {}", name, labels, seconds(hist.sum));
+    let _ = writeln!(out, "{}_count{{{}}} {}", name, labels, hist.count);
+}
+
+fn labels(m: &ActorMetrics, extra: &str) -> String {
+    let mut labels = format!("actor_type=\"{}\",arbiter=\"{}\",actor_id=\"{}\"",
+                             escape(m.actor_type), escape(&m.arbiter), m.id);
+    if let Some(ref name) = m.name {
+        let _ = write!(labels, ",name=\"{}\"", escape(name));
+    }
+    labels.push_str(extra);
+    labels
+}
+
+fn seconds(dur: Duration) -> f64 {
+    dur.as_secs() as f64 + f64::from(dur.subsec_nanos()) / 1_000_000_000.0
+}
+
+fn escape(val: &str) -> String {
+    val.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
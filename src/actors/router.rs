@@ -0,0 +1,349 @@
+//! Message routers
+//!
+//! `Router<A>` actor forwards every message that actor `A` can handle
+//! to one of the routees according to routing [`Strategy`](enum.Strategy.html).
+//! Router is a regular actor, so it could be started with `start()` and
+//! all messages could be sent to router's address.
+//!
+//! Routees pool can be modified at runtime with [`Pool`](struct.Pool.html)
+//! handle. Stopped routees get removed from the pool automatically.
+//!
+//! # Examples
+//!
+//! ```rust
+//! extern crate actix;
+//!
+//! use actix::prelude::*;
+//! use actix::actors::router::{Router, Strategy};
+//!
+//! struct Job(u64);
+//!
+//! struct Worker;
+//!
+//! impl Actor for Worker {}
+//!
+//! impl MessageResponse<Job> for Worker {
+//!     type Item = u64;
+//!     type Error = ();
+//! }
+//!
+//! impl MessageHandler<Job> for Worker {
+//!     fn handle(&mut self, msg: Job, _: &mut Context<Self>) -> MessageFuture<Self, Job> {
+//!         Arbiter::system().send(actix::SystemExit(0));
+//!         (msg.0 * 2).to_result()
+//!     }
+//! }
+//!
+//! fn main() {
+//!    let sys = System::new("test".to_owned());
+//!
+//!    // pool of 4 workers
+//!    let router = Router::<Worker>::from_factory(
+//!        Strategy::RoundRobin, 4, || Worker.start());
+//!    let pool = router.pool();
+//!    let addr: Address<_> = router.start();
+//!
+//!    addr.send(Job(1));
+//!    pool.resize(2);
+//!
+//!    sys.run();
+//! }
+//! ```
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+use futures::Future;
+use rand::{self, Rng};
+
+use fut;
+use prelude::*;
+
+/// Routing strategy
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Strategy {
+    /// Routees receive messages in turn
+    RoundRobin,
+    /// Routee is chosen randomly
+    Random,
+    /// Message is sent to routee with smallest number of queued messages
+    SmallestMailbox,
+    /// Routee is chosen by hash of the message key, messages with same key are
+    /// sent to same routee as long as pool size does not change. Key extractor has
+    /// to be registered with `Router::hash_key()`, otherwise round-robin is used.
+    ConsistentHash,
+    /// Message is sent to all routees, response of the last routee is returned
+    /// to caller. Message type has to be registered with `Router::broadcast()`,
+    /// otherwise round-robin is used.
+    Broadcast,
+}
+
+/// Router error
+#[derive(Debug)]
+pub enum RouterError<E> {
+    /// Routees pool is empty
+    NoRoutees,
+    /// Routee has been stopped before response is ready
+    Canceled,
+    /// Routee's message handler failed
+    Routee(E),
+}
+
+type Factory<A> = Box<Fn() -> SyncAddress<A>>;
+
+struct Routees<A: Actor> {
+    addrs: Vec<SyncAddress<A>>,
+    next: usize,
+    factory: Option<Factory<A>>,
+}
+
+impl<A: Actor> Routees<A> {
+
+    fn resize(&mut self, size: usize) -> usize {
+        self.addrs.retain(|addr| !addr.is_closed());
+        self.addrs.truncate(size);
+        if let Some(ref factory) = self.factory {
+            while self.addrs.len() < size {
+                self.addrs.push(factory());
+            }
+        }
+        self.addrs.len()
+    }
+
+    fn round_robin(&mut self) -> usize {
+        let idx = self.next % self.addrs.len();
+        self.next = idx + 1;
+        idx
+    }
+}
+
+/// Routees pool handle
+///
+/// Handle could be used only within router's arbiter.
+pub struct Pool<A: Actor>(Rc<RefCell<Routees<A>>>);
+
+impl<A: Actor> Clone for Pool<A> {
+    fn clone(&self) -> Self {
+        Pool(Rc::clone(&self.0))
+    }
+}
+
+impl<A: Actor> Pool<A> {
+
+    /// Number of routees
+    pub fn len(&self) -> usize {
+        self.0.borrow().addrs.len()
+    }
+
+    /// Check if pool is empty
+    pub fn is_empty(&self) -> bool {
+        self.0.borrow().addrs.is_empty()
+    }
+
+    /// Add routee to the pool
+    pub fn add(&self, addr: SyncAddress<A>) {
+        self.0.borrow_mut().addrs.push(addr)
+    }
+
+    /// Resize pool and return new size of the pool.
+    ///
+    /// Addresses of excessive routees get dropped. Pool could grow only if
+    /// router has been created with `Router::from_factory()`.
+    pub fn resize(&self, size: usize) -> usize {
+        self.0.borrow_mut().resize(size)
+    }
+}
+
+/// Router actor
+pub struct Router<A: Actor> {
+    strategy: Strategy,
+    routees: Rc<RefCell<Routees<A>>>,
+    keys: HashMap<TypeId, Box<Any>>,
+    cloners: HashMap<TypeId, Box<Any>>,
+    warned: HashSet<TypeId>,
+}
+
+impl<A: Actor> Router<A> {
+
+    /// Create router with specified routees
+    pub fn new(strategy: Strategy, routees: Vec<SyncAddress<A>>) -> Router<A> {
+        Router {
+            strategy: strategy,
+            routees: Rc::new(RefCell::new(
+                Routees{addrs: routees, next: 0, factory: None})),
+            keys: HashMap::new(),
+            cloners: HashMap::new(),
+            warned: HashSet::new(),
+        }
+    }
+
+    /// Create router and start `size` routees with factory function.
+    /// Factory is also used for growing the pool.
+    pub fn from_factory<F>(strategy: Strategy, size: usize, factory: F) -> Router<A>
+        where F: Fn() -> SyncAddress<A> + 'static
+    {
+        let router = Router::new(strategy, Vec::new());
+        {
+            let mut routees = router.routees.borrow_mut();
+            routees.factory = Some(Box::new(factory));
+            routees.resize(size);
+        }
+        router
+    }
+
+    /// Register key extractor for message type `M`, it is used
+    /// by `Strategy::ConsistentHash` strategy.
+    pub fn hash_key<M, K, F>(mut self, f: F) -> Self
+        where M: 'static, K: Hash, F: Fn(&M) -> K + 'static
+    {
+        let key: Box<Fn(&M) -> u64> = Box::new(move |msg| {
+            let mut hasher = DefaultHasher::new();
+            f(msg).hash(&mut hasher);
+            hasher.finish()
+        });
+        self.keys.insert(TypeId::of::<M>(), Box::new(key));
+        self
+    }
+
+    /// Register message type `M` for `Strategy::Broadcast` strategy.
+    pub fn broadcast<M: Clone + 'static>(mut self) -> Self {
+        let cloner: Box<Fn(&M) -> M> = Box::new(|msg| msg.clone());
+        self.cloners.insert(TypeId::of::<M>(), Box::new(cloner));
+        self
+    }
+
+    /// Get handle to the routees pool
+    pub fn pool(&self) -> Pool<A> {
+        Pool(Rc::clone(&self.routees))
+    }
+
+    fn key<M: 'static>(&self, msg: &M) -> Option<u64> {
+        self.keys.get(&TypeId::of::<M>())
+            .and_then(|key| key.downcast_ref::<Box<Fn(&M) -> u64>>())
+            .map(|key| key(msg))
+    }
+
+    fn cloner<M: 'static>(&self) -> Option<&Fn(&M) -> M> {
+        self.cloners.get(&TypeId::of::<M>())
+            .and_then(|cloner| cloner.downcast_ref::<Box<Fn(&M) -> M>>())
+            .map(|cloner| &**cloner)
+    }
+
+    /// Log warning only once per message type
+    fn warn_once<M: 'static>(&mut self, msg: &str) {
+        if self.warned.insert(TypeId::of::<M>()) {
+            warn!("{}", msg);
+        }
+    }
+
+    /// Select routee for the message
+    fn select<M: 'static>(&mut self, msg: &M) -> Option<SyncAddress<A>> {
+        let key = if self.strategy == Strategy::ConsistentHash {
+            let key = self.key(msg);
+            if key.is_none() {
+                self.warn_once::<M>("Key extractor is not registered, use round-robin");
+            }
+            key
+        } else {
+            None
+        };
+
+        let mut routees = self.routees.borrow_mut();
+
+        // remove stopped routees
+        routees.addrs.retain(|addr| !addr.is_closed());
+        if routees.addrs.is_empty() {
+            return None
+        }
+
+        let idx = match self.strategy {
+            Strategy::Random =>
+                rand::thread_rng().gen_range(0, routees.addrs.len()),
+            Strategy::SmallestMailbox =>
+                (0..routees.addrs.len())
+                .min_by_key(|idx| routees.addrs[*idx].mailbox_len()).unwrap_or(0),
+            Strategy::ConsistentHash => match key {
+                Some(key) => jump_hash(key, routees.addrs.len()),
+                None => routees.round_robin(),
+            },
+            Strategy::RoundRobin | Strategy::Broadcast => routees.round_robin(),
+        };
+        Some(routees.addrs[idx].clone())
+    }
+}
+
+impl<A: Actor> Actor for Router<A> {}
+
+impl<A, M> MessageResponse<M> for Router<A>
+    where A: Actor + MessageHandler<M>,
+          A::Item: Send,
+          A::Error: Send,
+          M: Send + 'static,
+{
+    type Item = A::Item;
+    type Error = RouterError<A::Error>;
+}
+
+impl<A, M> MessageHandler<M> for Router<A>
+    where A: Actor + MessageHandler<M>,
+          A::Item: Send,
+          A::Error: Send,
+          M: Send + 'static,
+{
+    fn handle(&mut self, msg: M, _: &mut Context<Self>) -> MessageFuture<Self, M> {
+        if self.strategy == Strategy::Broadcast {
+            if let Some(cloner) = self.cloner::<M>() {
+                let mut routees = self.routees.borrow_mut();
+
+                // remove stopped routees
+                routees.addrs.retain(|addr| !addr.is_closed());
+                if let Some((last, rest)) = routees.addrs.split_last() {
+                    for addr in rest {
+                        addr.send(cloner(&msg));
+                    }
+                    return response(last.call_fut(msg))
+                }
+            }
+            if self.cloner::<M>().is_none() {
+                self.warn_once::<M>("Message type is not registered for broadcast, use round-robin");
+            }
+        }
+
+        match self.select(&msg) {
+            Some(addr) => response(addr.call_fut(msg)),
+            None => RouterError::NoRoutees.to_error(),
+        }
+    }
+}
+
+/// Convert routee's response to router's `MessageFuture`
+fn response<A, M, F>(fut: F) -> MessageFuture<Router<A>, M>
+    where A: Actor + MessageHandler<M>,
+          A::Item: Send,
+          A::Error: Send,
+          M: Send + 'static,
+          F: Future<Item=Result<A::Item, A::Error>> + 'static,
+{
+    let fut = fut.then(|res| match res {
+        Ok(Ok(item)) => Ok(item),
+        Ok(Err(err)) => Err(RouterError::Routee(err)),
+        Err(_) => Err(RouterError::Canceled),
+    });
+    MessageFuture::from(fut::wrap_future::<_, Router<A>>(fut))
+}
+
+/// Jump consistent hash, maps `key` to one of `buckets` with minimal
+/// remapping on buckets number change.
+fn jump_hash(mut key: u64, buckets: usize) -> usize {
+    let mut b: i64 = -1;
+    let mut j: i64 = 0;
+    while j < buckets as i64 {
+        b = j;
+        key = key.wrapping_mul(2_862_933_555_777_941_757).wrapping_add(1);
+        j = ((b + 1) as f64 * ((1u64 << 31) as f64 / ((key >> 33) + 1) as f64)) as i64;
+    }
+    b as usize
+}
@@ -1,8 +1,17 @@
 //! An actor implementation of Unix signal handling
 //!
 //! This module implements asynchronous signal handling for Actix. For each signal
-//! `ProcessSignals` actor sends `Signal` message to all subscriber. To subscriber,
-//! send `Subscribe` message to `ProcessSignals` actor.
+//! `ProcessSignals` actor sends `Signal` message to all subscribers of the signal.
+//! To subscribe to default signals, send `Subscribe` message to `ProcessSignals`
+//! actor. `SubscribeSignals` message subscribes to selected signals.
+//! Both messages return subscription identifier, subscription can be removed
+//! with `Unsubscribe` message.
+//!
+//! Default signals are `SIGINT`, `SIGHUP`, `SIGTERM`, `SIGQUIT` and `SIGCHLD`,
+//! they are handled from the start. Handlers for other signals, including
+//! real-time signals, are installed once a subscriber selects them, these
+//! signals are delivered only to subscribers that selected them. Installed
+//! handler stays active after subscriber unsubscribes.
 //!
 //! # Examples
 //!
@@ -19,7 +28,9 @@
 //!   // disable real code for test
 //!   // fn started(&mut self, ctx: &mut Context<Self>) {
 //!   //     let addr: Address<_> = signal::ProcessSignals::run();
-//!   //     addr.send(signal::Subscribe(ctx.subscriber()))
+//!   //     let slf: SyncAddress<_> = ctx.address();
+//!   //     addr.send(signal::SubscribeSignals::new(
+//!   //         &[signal::SignalType::Int, signal::SignalType::Usr1], slf.subscriber()))
 //!   // }
 //! }
 //!
@@ -75,15 +86,16 @@
 //! }
 //! ```
 use std::io;
+use std::collections::{HashMap, HashSet};
 use libc;
-use futures::{Future, Stream};
-use tokio_signal;
+use futures::{future, Future, Stream};
+use futures::unsync::oneshot;
 use tokio_signal::unix;
 
 use prelude::*;
 
 /// Different types of process signals
-#[derive(PartialEq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
 pub enum SignalType {
     /// SIGHUP
     Hup,
@@ -95,19 +107,155 @@ pub enum SignalType {
     Quit,
     /// SIGCHILD
     Child,
+    /// SIGUSR1
+    Usr1,
+    /// SIGUSR2
+    Usr2,
+    /// SIGWINCH
+    Winch,
+    /// SIGPIPE
+    Pipe,
+    /// Real-time signal, number has to be in `SIGRTMIN..SIGRTMAX` range
+    RealTime(i32),
+}
+
+// Signals that are handled from the start
+const DEFAULT_SIGNALS: [SignalType; 5] = [
+    SignalType::Int, SignalType::Hup, SignalType::Term, SignalType::Quit, SignalType::Child];
+
+impl SignalType {
+    /// Signal number
+    pub fn number(&self) -> i32 {
+        match *self {
+            SignalType::Hup => libc::SIGHUP,
+            SignalType::Int => libc::SIGINT,
+            SignalType::Term => libc::SIGTERM,
+            SignalType::Quit => libc::SIGQUIT,
+            SignalType::Child => libc::SIGCHLD,
+            SignalType::Usr1 => libc::SIGUSR1,
+            SignalType::Usr2 => libc::SIGUSR2,
+            SignalType::Winch => libc::SIGWINCH,
+            SignalType::Pipe => libc::SIGPIPE,
+            SignalType::RealTime(num) => num,
+        }
+    }
+
+    // Real-time signal number has to be supported by the platform
+    fn validate(&self) -> io::Result<()> {
+        if let SignalType::RealTime(num) = *self {
+            match realtime_signals() {
+                Some((min, max)) if num >= min && num <= max => (),
+                _ => return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{} is not a real-time signal", num))),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(any(target_os="linux", target_os="android"))]
+fn realtime_signals() -> Option<(i32, i32)> {
+    Some((libc::SIGRTMIN(), libc::SIGRTMAX()))
+}
+
+#[cfg(not(any(target_os="linux", target_os="android")))]
+fn realtime_signals() -> Option<(i32, i32)> {
+    None
 }
 
 /// Process signal message
 pub struct Signal(pub SignalType);
 
+/// Subscription identifier, it is required for unsubscribe operation.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub struct SubscriptionId(usize);
+
+struct Subscription {
+    id: SubscriptionId,
+    // `None` for subscribers to default signals
+    signals: Option<Vec<SignalType>>,
+    subscriber: Box<Subscriber<Signal> + Send>,
+}
+
+type Installed = oneshot::Sender<io::Result<()>>;
+
 /// An actor implementation of Unix signal handling
 pub struct ProcessSignals {
-    subscribers: Vec<Box<Subscriber<Signal>>>,
+    next_id: usize,
+    // signals with installed handler
+    handled: HashSet<SignalType>,
+    // handlers being installed, with subscriptions waiting for them
+    installing: HashMap<SignalType, Vec<Installed>>,
+    subscriptions: Vec<Subscription>,
 }
 
 impl Default for ProcessSignals {
     fn default() -> Self {
-        ProcessSignals{subscribers: Vec::new()}
+        ProcessSignals{next_id: 0,
+                       handled: HashSet::new(),
+                       installing: HashMap::new(),
+                       subscriptions: Vec::new()}
+    }
+}
+
+impl ProcessSignals {
+
+    /// Install handler for the signal, if it is not installed yet.
+    /// Returned future resolves once handler is installed.
+    fn handle_signal(&mut self, sig: SignalType, ctx: &mut Context<Self>)
+                     -> Box<Future<Item=(), Error=io::Error>>
+    {
+        if self.handled.contains(&sig) {
+            return Box::new(future::ok(()))
+        }
+        if let Err(err) = sig.validate() {
+            return Box::new(future::err(err))
+        }
+
+        let (tx, rx) = oneshot::channel();
+        let install = {
+            let waiters = self.installing.entry(sig).or_insert_with(Vec::new);
+            waiters.push(tx);
+            waiters.len() == 1
+        };
+
+        if install {
+            unix::Signal::new(sig.number(), Arbiter::handle())
+                .then(|res| Ok::<_, ()>(res))
+                .actfuture()
+                .map(move |res, act: &mut ProcessSignals, ctx: &mut Context<Self>| {
+                    let waiters = act.installing.remove(&sig).unwrap_or_else(Vec::new);
+                    match res {
+                        Ok(stream) => {
+                            act.handled.insert(sig);
+                            ctx.add_stream(stream.map(move |_| sig));
+                            for tx in waiters {
+                                let _ = tx.send(Ok(()));
+                            }
+                        }
+                        Err(err) => {
+                            error!("Can not install handler for {:?}: {}", sig, err);
+                            for tx in waiters {
+                                let _ = tx.send(
+                                    Err(io::Error::new(err.kind(), err.to_string())));
+                            }
+                        }
+                    }
+                })
+                .spawn(ctx);
+        }
+
+        Box::new(rx.then(|res| match res {
+            Ok(res) => res,
+            Err(_) => Err(io::Error::new(io::ErrorKind::Other, "signal handler is dropped")),
+        }))
+    }
+
+    fn next_id(&mut self) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id);
+        self.next_id += 1;
+        id
     }
 }
 
@@ -120,52 +268,21 @@ impl Actor for ProcessSignals {
             return
         }
 
-        let handle = Arbiter::handle();
-
-        // SIGINT
-        tokio_signal::ctrl_c(handle).map_err(|_| ())
-            .actfuture()
-            .map(|sig, _: &mut ProcessSignals, ctx: &mut Context<Self>|
-                 ctx.add_stream(
-                     sig.map(|_| SignalType::Int)))
-            .spawn(ctx);
-
-        // SIGHUP
-        unix::Signal::new(libc::SIGHUP, handle).map_err(|_| ())
-            .actfuture()
-            .map(|sig, _: &mut ProcessSignals, ctx: &mut Context<Self>|
-                 ctx.add_stream(
-                     sig.map(|_| SignalType::Hup)))
-            .spawn(ctx);
-
-        // SIGTERM
-        unix::Signal::new(libc::SIGTERM, handle).map_err(|_| ())
-            .actfuture()
-            .map(|sig, _: &mut Self, ctx: &mut Context<Self>|
-                 ctx.add_stream(
-                     sig.map(|_| SignalType::Term)))
-            .spawn(ctx);
-
-        // SIGQUIT
-        unix::Signal::new(libc::SIGQUIT, handle).map_err(|_| ())
-            .actfuture()
-            .map(|sig, _: &mut ProcessSignals, ctx: &mut Context<Self>|
-                 ctx.add_stream(
-                     sig.map(|_| SignalType::Quit)))
-            .spawn(ctx);
-
-        // SIGCHLD
-        unix::Signal::new(libc::SIGCHLD, handle).map_err(|_| ())
-            .actfuture()
-            .map(|sig, _: &mut ProcessSignals, ctx: &mut Context<Self>|
-                 ctx.add_stream(
-                     sig.map(|_| SignalType::Child)))
-            .spawn(ctx);
+        for sig in &DEFAULT_SIGNALS {
+            // installation errors are logged
+            let _ = self.handle_signal(*sig, ctx);
+        }
     }
 }
 
 #[doc(hidden)]
-impl StreamHandler<SignalType, io::Error> for ProcessSignals {}
+impl StreamHandler<SignalType, io::Error> for ProcessSignals {
+
+    fn error(&mut self, err: io::Error, ctx: &mut Context<ProcessSignals>) -> StreamControl {
+        error!("Error during signal handling in {}: {}", ctx.identity(), err);
+        StreamControl::Continue
+    }
+}
 
 impl MessageResponse<SignalType> for ProcessSignals {
     type Item = ();
@@ -178,35 +295,111 @@ impl MessageHandler<SignalType, io::Error> for ProcessSignals {
     fn handle(&mut self, msg: SignalType, _: &mut Context<Self>)
               -> MessageFuture<Self, SignalType>
     {
-        for subscr in &self.subscribers {
-            subscr.send(Signal(msg))
-        }
+        self.subscriptions.retain(|s| {
+            let selected = match s.signals {
+                Some(ref signals) => signals.contains(&msg),
+                None => DEFAULT_SIGNALS.contains(&msg),
+            };
+            if selected {
+                s.subscriber.send(Signal(msg));
+            }
+            // remove dead subscribers
+            !s.subscriber.is_closed()
+        });
         ().to_result()
     }
-
-    fn error(&mut self, err: io::Error, _: &mut Context<ProcessSignals>) {
-        error!("Error during signal handling: {}", err);
-    }
 }
 
-/// Subscribe to process signals.
+/// Subscribe to default process signals: `SIGINT`, `SIGHUP`, `SIGTERM`,
+/// `SIGQUIT` and `SIGCHLD`. Signals selected by other subscribers
+/// are not delivered.
+///
+/// Result of this message is subscription identifier.
 pub struct Subscribe(pub Box<Subscriber<Signal> + Send>);
 
 impl MessageResponse<Subscribe> for ProcessSignals {
-    type Item = ();
+    type Item = SubscriptionId;
     type Error = ();
 }
 
-/// Add subscriber for signals
+/// Add subscriber for default signals
 impl MessageHandler<Subscribe> for ProcessSignals {
 
     fn handle(&mut self, msg: Subscribe,
               _: &mut Context<ProcessSignals>) -> MessageFuture<Self, Subscribe>
     {
-        self.subscribers.push(msg.0);
-        ().to_result()
+        let id = self.next_id();
+        self.subscriptions.push(Subscription{id: id, signals: None, subscriber: msg.0});
+        id.to_result()
     }
+}
+
+/// Subscribe to selected process signals, handlers for the signals
+/// are installed if needed.
+///
+/// Result of this message is subscription identifier, it is available once
+/// handlers are installed. If any handler can not be installed, subscription
+/// is not added and error is returned.
+pub struct SubscribeSignals {
+    signals: Vec<SignalType>,
+    subscriber: Box<Subscriber<Signal> + Send>,
+}
+
+impl SubscribeSignals {
+    /// Subscribe to `signals`
+    pub fn new(signals: &[SignalType], subscriber: Box<Subscriber<Signal> + Send>)
+               -> SubscribeSignals
+    {
+        SubscribeSignals{signals: signals.to_vec(), subscriber: subscriber}
+    }
+}
+
+impl MessageResponse<SubscribeSignals> for ProcessSignals {
+    type Item = SubscriptionId;
+    type Error = io::Error;
+}
 
+/// Add subscriber for selected signals
+impl MessageHandler<SubscribeSignals> for ProcessSignals {
+
+    fn handle(&mut self, msg: SubscribeSignals,
+              ctx: &mut Context<ProcessSignals>) -> MessageFuture<Self, SubscribeSignals>
+    {
+        let installed: Vec<_> = msg.signals.iter()
+            .map(|sig| self.handle_signal(*sig, ctx)).collect();
+
+        let id = self.next_id();
+        self.subscriptions.push(
+            Subscription{id: id, signals: Some(msg.signals), subscriber: msg.subscriber});
+
+        fut::wrap_future(future::join_all(installed))
+            .then(move |res, act: &mut ProcessSignals, _: &mut Context<ProcessSignals>| {
+                if res.is_err() {
+                    act.subscriptions.retain(|s| s.id != id);
+                }
+                fut::result(res.map(|_| id))
+            })
+            .into()
+    }
+}
+
+/// Remove subscription to selected signals
+pub struct Unsubscribe(pub SubscriptionId);
+
+impl MessageResponse<Unsubscribe> for ProcessSignals {
+    type Item = ();
+    type Error = ();
+}
+
+/// Remove subscriber
+impl MessageHandler<Unsubscribe> for ProcessSignals {
+
+    fn handle(&mut self, msg: Unsubscribe,
+              _: &mut Context<ProcessSignals>) -> MessageFuture<Self, Unsubscribe>
+    {
+        self.subscriptions.retain(|s| s.id != msg.0);
+        ().to_result()
+    }
 }
 
 /// Default signals handler. This actor sends `SystemExit` message to `System` actor
@@ -1,10 +1,20 @@
+use std::{fmt, mem, ptr};
+use std::marker::PhantomData;
+#[cfg(actix_maybe_uninit)]
+use std::mem::MaybeUninit;
+use std::any::{Any, TypeId};
+use std::hash::{Hash, Hasher};
 use futures::Future;
-use futures::unsync::oneshot::{channel, Receiver};
+use futures::unsync::oneshot::{channel, Receiver, Sender};
 
 use actor::{Actor, MessageHandler};
+use actors::dead_letters::{self, DeadLetterReason};
 use context::{Context, ContextProtocol};
+use identity::ActorId;
 use message::{Envelope, CallResult, MessageResult};
-use queue::unsync;
+use queue::{unsync, Priority};
+use retry::{Retry, RetryCall};
+use utils;
 pub use sync_address::SyncAddress;
 
 
@@ -21,6 +31,11 @@ pub trait Subscriber<M: 'static> {
 
     /// Unbuffered send
     fn unbuffered_send(&self, msg: M) -> Result<(), M>;
+
+    /// Indicates if subscriber can not receive messages anymore
+    fn is_closed(&self) -> bool {
+        false
+    }
 }
 
 pub trait AsyncSubscriber<M> {
@@ -41,19 +56,185 @@ pub(crate) trait MessageProxy {
 
     /// handle message within new actor and context
     fn handle(&mut self, act: &mut Self::Actor, ctx: &mut Context<Self::Actor>);
+
+    /// type name of the message
+    fn message_type(&self) -> &'static str;
+
+    /// type id of the message
+    fn message_type_id(&self) -> TypeId;
+
+    /// proxy as `Any`, used by mock addresses
+    fn as_any(&mut self) -> &mut Any;
 }
 
-pub(crate) struct Proxy<A>(pub(crate) Box<MessageProxy<Actor=A>>);
+pub(crate) struct Proxy<A>(pub(crate) ProxyStorage<A>);
 
 impl<A> Proxy<A> where A: Actor {
     pub(crate) fn new<M: 'static + MessageProxy<Actor=A>>(msg: M) -> Self {
-        Proxy(Box::new(msg))
+        if mem::size_of::<M>() <= mem::size_of::<InlineData>() &&
+            mem::align_of::<M>() <= mem::align_of::<InlineData>()
+        {
+            Proxy(ProxyStorage::Inline(InlineProxy::new(msg)))
+        } else {
+            Proxy(ProxyStorage::Boxed(Box::new(msg)))
+        }
+    }
+}
+
+// Storage for small envelopes, envelopes that do not fit are boxed.
+// Storage is never read as `u64`, envelopes could contain padding.
+#[cfg(actix_maybe_uninit)]
+type InlineData = MaybeUninit<[u64; 12]>;
+
+#[cfg(actix_maybe_uninit)]
+fn inline_data() -> InlineData {
+    MaybeUninit::uninit()
+}
+
+// `MaybeUninit` requires rust 1.36, older compilers box all envelopes
+#[cfg(not(actix_maybe_uninit))]
+type InlineData = [u64; 0];
+
+#[cfg(not(actix_maybe_uninit))]
+fn inline_data() -> InlineData {
+    []
+}
+
+/// Message proxy storage, small proxies are stored without heap allocation
+pub(crate) enum ProxyStorage<A> {
+    Inline(InlineProxy<A>),
+    Boxed(Box<MessageProxy<Actor=A>>),
+}
+
+impl<A: Actor> ProxyStorage<A> {
+
+    pub(crate) fn handle(&mut self, act: &mut A, ctx: &mut Context<A>) {
+        match *self {
+            ProxyStorage::Inline(ref mut proxy) => {
+                let ptr = proxy.ptr();
+                unsafe { (proxy.handle)(ptr, act, ctx) }
+            }
+            ProxyStorage::Boxed(ref mut proxy) => proxy.handle(act, ctx),
+        }
+    }
+
+    pub(crate) fn message_type(&self) -> &'static str {
+        match *self {
+            ProxyStorage::Inline(ref proxy) =>
+                unsafe { (proxy.message_type)(proxy.data.as_ptr() as *const u8) },
+            ProxyStorage::Boxed(ref proxy) => proxy.message_type(),
+        }
+    }
+
+    pub(crate) fn message_type_id(&self) -> TypeId {
+        match *self {
+            ProxyStorage::Inline(ref proxy) =>
+                unsafe { (proxy.message_type_id)(proxy.data.as_ptr() as *const u8) },
+            ProxyStorage::Boxed(ref proxy) => proxy.message_type_id(),
+        }
+    }
+
+    pub(crate) fn as_any(&mut self) -> &mut Any {
+        match *self {
+            ProxyStorage::Inline(ref mut proxy) => {
+                let ptr = proxy.ptr();
+                unsafe { &mut *(proxy.as_any)(ptr) }
+            }
+            ProxyStorage::Boxed(ref mut proxy) => proxy.as_any(),
+        }
     }
 }
 
+/// Proxy stored in place, operations are dispatched
+/// through functions monomorphized for the proxy type.
+pub(crate) struct InlineProxy<A> {
+    data: InlineData,
+    handle: unsafe fn(*mut u8, &mut A, &mut Context<A>),
+    message_type: unsafe fn(*const u8) -> &'static str,
+    message_type_id: unsafe fn(*const u8) -> TypeId,
+    as_any: unsafe fn(*mut u8) -> *mut Any,
+    drop: unsafe fn(*mut u8),
+    // stored envelope could be `!Send`, same as boxed proxy
+    _marker: PhantomData<*mut ()>,
+}
+
+impl<A> InlineProxy<A> {
+    fn new<M: 'static + MessageProxy<Actor=A>>(msg: M) -> InlineProxy<A> {
+        let mut proxy = InlineProxy {
+            data: inline_data(),
+            handle: inline_handle::<M>,
+            message_type: inline_message_type::<M>,
+            message_type_id: inline_message_type_id::<M>,
+            as_any: inline_as_any::<M>,
+            drop: inline_drop::<M>,
+            _marker: PhantomData,
+        };
+        // size and alignment are checked by `Proxy::new()`
+        unsafe { ptr::write(proxy.ptr() as *mut M, msg) };
+        proxy
+    }
+
+    fn ptr(&mut self) -> *mut u8 {
+        self.data.as_mut_ptr() as *mut u8
+    }
+}
+
+impl<A> Drop for InlineProxy<A> {
+    fn drop(&mut self) {
+        let ptr = self.ptr();
+        unsafe { (self.drop)(ptr) }
+    }
+}
+
+unsafe fn inline_handle<M: MessageProxy>(ptr: *mut u8, act: &mut M::Actor,
+                                         ctx: &mut Context<M::Actor>) {
+    (&mut *(ptr as *mut M)).handle(act, ctx)
+}
+
+unsafe fn inline_message_type<M: MessageProxy>(ptr: *const u8) -> &'static str {
+    (&*(ptr as *const M)).message_type()
+}
+
+unsafe fn inline_message_type_id<M: MessageProxy>(ptr: *const u8) -> TypeId {
+    (&*(ptr as *const M)).message_type_id()
+}
+
+unsafe fn inline_as_any<M: MessageProxy + 'static>(ptr: *mut u8) -> *mut Any {
+    ptr as *mut M as *mut Any
+}
+
+unsafe fn inline_drop<M>(ptr: *mut u8) {
+    ptr::drop_in_place(ptr as *mut M)
+}
+
 
 unsafe impl<T> Send for Proxy<T> {}
 
+/// Builds `SyncAddress` within actor's context, see `Address::upgrade()`
+struct Upgrade<A: Actor>(Option<Sender<SyncAddress<A>>>);
+
+impl<A: Actor> MessageProxy for Upgrade<A> {
+    type Actor = A;
+
+    fn handle(&mut self, _: &mut A, ctx: &mut Context<A>) {
+        if let Some(tx) = self.0.take() {
+            let _ = tx.send(<A as ActorAddress<A, SyncAddress<A>>>::get(ctx));
+        }
+    }
+
+    fn message_type(&self) -> &'static str {
+        utils::type_name::<Self>()
+    }
+
+    fn message_type_id(&self) -> TypeId {
+        TypeId::of::<Self>()
+    }
+
+    fn as_any(&mut self) -> &mut Any {
+        self
+    }
+}
+
 /// Address of the actor `A`.
 /// Actor has to run in the same thread as owner of the address.
 pub struct Address<A> where A: Actor {
@@ -66,6 +247,26 @@ impl<A> Clone for Address<A> where A: Actor {
     }
 }
 
+impl<A> PartialEq for Address<A> where A: Actor {
+    fn eq(&self, other: &Self) -> bool {
+        self.id() == other.id()
+    }
+}
+
+impl<A> Eq for Address<A> where A: Actor {}
+
+impl<A> Hash for Address<A> where A: Actor {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id().hash(state)
+    }
+}
+
+impl<A> fmt::Debug for Address<A> where A: Actor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Address({})", self.tx.identity())
+    }
+}
+
 impl<A> Address<A> where A: Actor {
 
     pub(crate) fn new(sender: unsync::UnboundedSender<ContextProtocol<A>>) -> Address<A> {
@@ -75,9 +276,7 @@ impl<A> Address<A> where A: Actor {
     /// Send message `M` to actor `A`.
     pub fn send<M: 'static>(&self, msg: M) where A: MessageHandler<M>
     {
-        let _ = self.tx.unbounded_send(
-            ContextProtocol::Envelope(
-                Proxy::new(Envelope::new(Some(msg), None))));
+        self.deliver(Envelope::new(Some(msg), None), None);
     }
 
     /// Send message to actor `A` and asyncronously wait for response.
@@ -86,9 +285,29 @@ impl<A> Address<A> where A: Actor {
               M: 'static
     {
         let (tx, rx) = channel();
-        let _ = self.tx.unbounded_send(
-            ContextProtocol::Envelope(
-                Proxy::new(Envelope::new(Some(msg), Some(tx)))));
+        self.deliver(Envelope::new(Some(msg), Some(tx)), None);
+
+        MessageResult::new(rx)
+    }
+
+    /// Send message `M` to actor `A` with specific priority.
+    ///
+    /// Messages with higher priority are handled first.
+    pub fn send_priority<M: 'static>(&self, msg: M, priority: Priority)
+        where A: MessageHandler<M>
+    {
+        self.deliver(Envelope::new(Some(msg), None), Some(priority));
+    }
+
+    /// Send message to actor `A` with specific priority and
+    /// asyncronously wait for response.
+    pub fn call_priority<B: Actor, M>(&self, msg: M, priority: Priority)
+                                      -> MessageResult<A, B, M>
+        where A: MessageHandler<M>,
+              M: 'static
+    {
+        let (tx, rx) = channel();
+        self.deliver(Envelope::new(Some(msg), Some(tx)), Some(priority));
 
         MessageResult::new(rx)
     }
@@ -99,19 +318,48 @@ impl<A> Address<A> where A: Actor {
               M: 'static
     {
         let (tx, rx) = channel();
-        let _ = self.tx.unbounded_send(
-            ContextProtocol::Envelope(
-                Proxy::new(Envelope::new(Some(msg), Some(tx)))));
+        self.deliver(Envelope::new(Some(msg), Some(tx)), None);
 
         rx
     }
 
+    /// Send message to actor `A` and asyncronously wait for response,
+    /// retry according to the retry policy. Message is cloned for each attempt.
+    pub fn call_retry<M>(&self, msg: M, retry: Retry<A::Item, A::Error>)
+                         -> RetryCall<A::Item, A::Error>
+        where A: MessageHandler<M>,
+              M: Clone + 'static
+    {
+        let addr = self.clone();
+        let generation = self.tx.identity().generation;
+        let call: Box<Fn() -> Receiver<Result<A::Item, A::Error>>> =
+            Box::new(move || addr.call_fut(msg.clone()));
+        RetryCall::new(call, retry, generation)
+    }
+
+    /// Indicates if actor's context is stopped and address can not deliver
+    /// messages anymore.
+    pub fn is_closed(&self) -> bool {
+        self.tx.is_closed()
+    }
+
+    /// Identifier of the actor
+    pub fn id(&self) -> ActorId {
+        self.tx.id()
+    }
+
+    /// Name of the actor, if actor is named and still alive
+    pub fn name(&self) -> Option<String> {
+        self.tx.identity().name()
+    }
+
     /// Upgrade address to SyncAddress.
     pub fn upgrade(&self) -> Receiver<SyncAddress<A>> {
         let (tx, rx) = channel();
+        // sync address is built within actor's context,
+        // so it gets identity of the context before it is sent back
         let _ = self.tx.unbounded_send(
-            ContextProtocol::SyncAddress(tx));
-
+            ContextProtocol::Envelope(Proxy::new(Upgrade(Some(tx)))));
         rx
     }
 
@@ -121,6 +369,30 @@ impl<A> Address<A> where A: Actor {
     {
         Box::new(self.clone())
     }
+
+    /// Send raw proxy to actor's mailbox, returns `false` if context is stopped
+    pub(crate) fn send_proxy<P>(&self, proxy: P) -> bool
+        where P: MessageProxy<Actor=A> + 'static
+    {
+        self.tx.unbounded_send(ContextProtocol::Envelope(Proxy::new(proxy))).is_ok()
+    }
+
+    /// Send envelope to actor's mailbox, undeliverable message is reported
+    /// to dead letters actor.
+    fn deliver<M: 'static>(&self, env: Envelope<A, M>, priority: Option<Priority>)
+        where A: MessageHandler<M>
+    {
+        let msg = ContextProtocol::Envelope(Proxy::new(env));
+        let res = match priority {
+            Some(priority) => self.tx.unbounded_send_priority(msg, priority),
+            None => self.tx.unbounded_send(msg),
+        };
+        if res.is_err() {
+            dead_letters::report::<A>(
+                self.id(), TypeId::of::<M>(), utils::type_name::<M>(),
+                DeadLetterReason::Stopped);
+        }
+    }
 }
 
 impl<A, M: 'static> Subscriber<M> for Address<A>
@@ -135,6 +407,10 @@ impl<A, M: 'static> Subscriber<M> for Address<A>
         self.send(msg);
         Ok(())
     }
+
+    fn is_closed(&self) -> bool {
+        self.is_closed()
+    }
 }
 
 impl<A, M: 'static> AsyncSubscriber<M> for Address<A>
@@ -145,9 +421,7 @@ impl<A, M: 'static> AsyncSubscriber<M> for Address<A>
     fn call(&self, msg: M) -> Self::Future
     {
         let (tx, rx) = channel();
-        let _ = self.tx.unbounded_send(
-            ContextProtocol::Envelope(
-                Proxy::new(Envelope::new(Some(msg), Some(tx)))));
+        self.deliver(Envelope::new(Some(msg), Some(tx)), None);
 
         CallResult::new(rx)
     }
@@ -155,9 +429,7 @@ impl<A, M: 'static> AsyncSubscriber<M> for Address<A>
     fn unbuffered_call(&self, msg: M) -> Result<Self::Future, M>
     {
         let (tx, rx) = channel();
-        let _ = self.tx.unbounded_send(
-            ContextProtocol::Envelope(
-                Proxy::new(Envelope::new(Some(msg), Some(tx)))));
+        self.deliver(Envelope::new(Some(msg), Some(tx)), None);
 
         Ok(CallResult::new(rx))
     }
@@ -166,6 +438,7 @@ impl<A, M: 'static> AsyncSubscriber<M> for Address<A>
 impl<A> ActorAddress<A, Address<A>> for A where A: Actor {
 
     fn get(ctx: &mut Context<A>) -> Address<A> {
+        ctx.metrics();
         ctx.address_cell().unsync_address()
     }
 }
@@ -173,13 +446,13 @@ impl<A> ActorAddress<A, Address<A>> for A where A: Actor {
 impl<A> ActorAddress<A, (Address<A>, SyncAddress<A>)> for A where A: Actor {
 
     fn get(ctx: &mut Context<A>) -> (Address<A>, SyncAddress<A>) {
-        (ctx.address_cell().unsync_address(), ctx.address_cell().sync_address())
+        (ctx.address_cell().unsync_address(), <A as ActorAddress<A, SyncAddress<A>>>::get(ctx))
     }
 }
 
 impl<A> ActorAddress<A, ()> for A where A: Actor {
 
-    fn get(_: &mut Context<A>) -> () {
-        ()
+    fn get(ctx: &mut Context<A>) -> () {
+        ctx.metrics();
     }
 }
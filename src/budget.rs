@@ -0,0 +1,169 @@
+//! Message budget, number of messages actor handles per tick.
+//!
+//! Actor's mailboxes and streams share one budget, each message is counted
+//! once, when it is received. Once budget is used up, all of them return
+//! `NotReady` until actor's context yields back to the event loop, so other
+//! actors of the same arbiter get a chance to run. Context's task is notified
+//! when budget is used up, and budget is renewed at the start of the next
+//! tick of the context.
+use std::cell::Cell;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use futures::task;
+
+use actor::Actor;
+use arbiter::Arbiter;
+use context::Context;
+
+/// Default number of messages per tick
+pub(crate) const DEFAULT_BUDGET: usize = 256;
+
+thread_local!(static ARBITER_BUDGET: Cell<usize> = Cell::new(DEFAULT_BUDGET));
+
+impl Arbiter {
+
+    /// Set number of messages that actors of current arbiter handle
+    /// per tick. Actor's own budget takes precedence, see
+    /// `Context::set_message_budget()`. Default budget is 256 messages.
+    pub fn set_message_budget(budget: usize) {
+        assert!(budget > 0, "message budget has to be greater than zero");
+        ARBITER_BUDGET.with(|b| b.set(budget));
+    }
+
+    /// Number of messages that actors of current arbiter handle per tick
+    pub fn message_budget() -> usize {
+        ARBITER_BUDGET.with(|b| b.get())
+    }
+}
+
+impl<A> Context<A> where A: Actor {
+
+    /// Message budget of the actor
+    pub(crate) fn budget(&mut self) -> Arc<MessageBudget> {
+        self.address_cell().unsync_sender().budget()
+    }
+
+    /// Renew message budget, called at the start of each tick of the context
+    pub(crate) fn renew_budget(&mut self) {
+        self.address_cell().unsync_sender().identity().budget.renew()
+    }
+}
+
+/// Actor's message budget, shared by actor's mailboxes through identity.
+/// Messages handled in current tick are accounted here as well, all sources
+/// of the actor's context draw from one tick. Budget is shared with other
+/// threads through sync mailbox identity, but `used` and `yielded` are
+/// updated only from actor's thread.
+#[derive(Debug, Default)]
+pub(crate) struct MessageBudget {
+    budget: AtomicUsize,
+    used: AtomicUsize,
+    yielded: AtomicBool,
+}
+
+impl MessageBudget {
+
+    /// Set actor's budget, `None` resets budget to arbiter's budget
+    pub fn set(&self, budget: Option<usize>) {
+        if let Some(budget) = budget {
+            assert!(budget > 0, "message budget has to be greater than zero");
+        }
+        self.budget.store(budget.unwrap_or(0), Ordering::Relaxed);
+    }
+
+    /// Effective budget of the actor
+    pub fn get(&self) -> usize {
+        match self.budget.load(Ordering::Relaxed) {
+            0 => Arbiter::message_budget(),
+            budget => budget,
+        }
+    }
+
+    /// Number of messages that could be handled in current tick.
+    ///
+    /// If budget is used up, context's task is notified and zero is returned
+    /// until budget is renewed at the start of the next tick.
+    /// Messages are counted since last yield, so actor that handles
+    /// a few messages per tick yields once per budget as well.
+    pub fn available(&self) -> usize {
+        if self.yielded.load(Ordering::Relaxed) {
+            return 0
+        }
+
+        let used = self.used.load(Ordering::Relaxed);
+        let budget = self.get();
+        if used < budget {
+            return budget - used
+        }
+
+        // context is polled again after other tasks of the event loop
+        self.yielded.store(true, Ordering::Relaxed);
+        task::current().notify();
+        0
+    }
+
+    /// Account handled messages
+    pub fn consume(&self, num: usize) {
+        self.used.fetch_add(num, Ordering::Relaxed);
+    }
+
+    /// Start new tick, budget is renewed only if context has yielded
+    pub fn renew(&self) {
+        if self.yielded.load(Ordering::Relaxed) {
+            self.used.store(0, Ordering::Relaxed);
+            self.yielded.store(false, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Mailbox's handle of actor's budget, budget is loaded from mailbox's
+/// identity on first use. Unbudgeted mailbox delivers all its messages.
+#[derive(Debug)]
+pub(crate) struct MailboxBudget {
+    budget: Option<Arc<MessageBudget>>,
+    enabled: bool,
+}
+
+impl Default for MailboxBudget {
+    fn default() -> MailboxBudget {
+        MailboxBudget{budget: None, enabled: true}
+    }
+}
+
+impl MailboxBudget {
+
+    /// Mailbox does not draw from actor's budget
+    pub fn disable(&mut self) {
+        self.enabled = false;
+        self.budget = None;
+    }
+
+    /// Mailbox identity is replaced, budget has to be loaded again
+    pub fn reload(&mut self) {
+        self.budget = None;
+    }
+
+    /// Number of messages mailbox could deliver
+    pub fn available<F>(&mut self, load: F) -> usize
+        where F: FnOnce() -> Arc<MessageBudget>
+    {
+        if !self.enabled {
+            return usize::max_value()
+        }
+        if self.budget.is_none() {
+            self.budget = Some(load());
+        }
+        match self.budget {
+            Some(ref budget) => budget.available(),
+            None => usize::max_value(),
+        }
+    }
+
+    /// Account delivered messages
+    pub fn consume(&self, num: usize) {
+        if let Some(ref budget) = self.budget {
+            budget.consume(num)
+        }
+    }
+}
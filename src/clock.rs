@@ -0,0 +1,157 @@
+//! Timers
+//!
+//! Actix timers are driven by reactor's timers, unless thread has virtual clock
+//! installed. Virtual clock is used by `TestSystem`, time does not pass until
+//! clock is advanced explicitly.
+use std::io;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use futures::{Async, Future, Poll};
+use futures::task::{self, Task};
+use tokio_core::reactor::Timeout;
+
+use arbiter::Arbiter;
+
+thread_local!(static CLOCK: RefCell<Option<Arc<VirtualClock>>> = RefCell::new(None));
+
+/// Manually advanced clock. Clock is shared with its timers,
+/// so `Delay` could be sent to other thread.
+pub(crate) struct VirtualClock {
+    inner: Mutex<ClockInner>,
+}
+
+struct ClockInner {
+    now: Duration,
+    next_id: usize,
+    timers: HashMap<usize, (Duration, Option<Task>)>,
+    fired: usize,
+}
+
+impl VirtualClock {
+
+    /// Install virtual clock for current thread
+    pub fn install() -> Arc<VirtualClock> {
+        let clock = Arc::new(VirtualClock {
+            inner: Mutex::new(ClockInner {
+                now: Duration::new(0, 0),
+                next_id: 0,
+                timers: HashMap::new(),
+                fired: 0,
+            })
+        });
+        CLOCK.with(|cell| *cell.borrow_mut() = Some(Arc::clone(&clock)));
+        clock
+    }
+
+    /// Remove virtual clock of current thread
+    pub fn uninstall() {
+        CLOCK.with(|cell| cell.borrow_mut().take());
+    }
+
+    fn inner(&self) -> MutexGuard<ClockInner> {
+        self.inner.lock().unwrap()
+    }
+
+    /// Time passed since clock is installed
+    pub fn now(&self) -> Duration {
+        self.inner().now
+    }
+
+    /// Number of expired timers
+    pub fn fired(&self) -> usize {
+        self.inner().fired
+    }
+
+    /// Deadline of the earliest pending timer
+    pub fn next_deadline(&self) -> Option<Duration> {
+        self.inner().timers.values().map(|&(deadline, _)| deadline).min()
+    }
+
+    /// Set current time and wake up expired timers
+    pub fn set(&self, now: Duration) {
+        let mut inner = self.inner();
+        if now > inner.now {
+            inner.now = now;
+        }
+        let now = inner.now;
+
+        let expired: Vec<_> = inner.timers.iter()
+            .filter(|&(_, &(deadline, _))| deadline <= now)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in expired {
+            if let Some((_, task)) = inner.timers.remove(&id) {
+                inner.fired += 1;
+                if let Some(task) = task {
+                    task.notify();
+                }
+            }
+        }
+    }
+
+    fn register(&self, dur: Duration) -> (usize, Duration) {
+        let mut inner = self.inner();
+        let id = inner.next_id;
+        let deadline = inner.now + dur;
+        inner.next_id += 1;
+        inner.timers.insert(id, (deadline, None));
+        (id, deadline)
+    }
+}
+
+/// Future that resolves after specific duration
+pub(crate) struct Delay {
+    kind: DelayKind,
+}
+
+enum DelayKind {
+    Reactor(Timeout),
+    Virtual{clock: Arc<VirtualClock>, id: usize, deadline: Duration},
+}
+
+impl Delay {
+
+    /// Create delay in current arbiter
+    pub fn new(dur: Duration) -> io::Result<Delay> {
+        let kind = match CLOCK.with(|cell| cell.borrow().clone()) {
+            Some(clock) => {
+                let (id, deadline) = clock.register(dur);
+                DelayKind::Virtual{clock: clock, id: id, deadline: deadline}
+            }
+            None => DelayKind::Reactor(Timeout::new(dur, Arbiter::handle())?),
+        };
+        Ok(Delay{kind: kind})
+    }
+}
+
+impl Future for Delay {
+    type Item = ();
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<(), io::Error> {
+        match self.kind {
+            DelayKind::Reactor(ref mut timeout) => timeout.poll(),
+            DelayKind::Virtual{ref clock, id, deadline} => {
+                let mut inner = clock.inner();
+                if deadline <= inner.now {
+                    return Ok(Async::Ready(()))
+                }
+                if let Some(timer) = inner.timers.get_mut(&id) {
+                    timer.1 = Some(task::current());
+                }
+                Ok(Async::NotReady)
+            }
+        }
+    }
+}
+
+impl Drop for Delay {
+    fn drop(&mut self) {
+        if let DelayKind::Virtual{ref clock, id, ..} = self.kind {
+            clock.inner().timers.remove(&id);
+        }
+    }
+}
@@ -0,0 +1,261 @@
+//! Finite state machine actors
+//!
+//! [`FsmActor`](trait.FsmActor.html) is an actor with explicit, typed state.
+//! Current state is stored in [`Fsm`](struct.Fsm.html) object owned by actor.
+//! State changes with `FsmActor::transition()` method, `on_exit` callback is called
+//! for old state and `on_enter` for new state. State could have timeout, if actor
+//! stays in the same state for longer than timeout, `on_timeout` get called.
+//!
+//! Messages are handled with [`FsmHandler`](trait.FsmHandler.html), handler
+//! receives current state and can refuse message, in this case message
+//! is passed to `FsmHandler::unhandled` method.
+//!
+//! # Examples
+//!
+//! ```rust
+//! extern crate actix;
+//!
+//! use std::time::Duration;
+//! use actix::prelude::*;
+//! use actix::fsm::{Fsm, FsmActor, FsmHandler};
+//!
+//! #[derive(PartialEq, Clone, Debug)]
+//! enum State {
+//!     Idle,
+//!     Connected,
+//! }
+//!
+//! struct Connect;
+//!
+//! struct Protocol {
+//!     fsm: Fsm<Protocol>,
+//! }
+//!
+//! impl Actor for Protocol {}
+//!
+//! impl FsmActor for Protocol {
+//!     type State = State;
+//!
+//!     fn fsm(&mut self) -> &mut Fsm<Self> {
+//!         &mut self.fsm
+//!     }
+//!
+//!     fn on_enter(&mut self, state: &State, _: &mut Context<Self>) {
+//!         println!("Entered state: {:?}", state);
+//!     }
+//!
+//!     fn on_timeout(&mut self, _: &State, _: &mut Context<Self>) {
+//!         Arbiter::system().send(actix::SystemExit(0));
+//!     }
+//! }
+//!
+//! impl MessageResponse<Connect> for Protocol {
+//!     type Item = ();
+//!     type Error = ();
+//! }
+//!
+//! impl FsmHandler<Connect> for Protocol {
+//!     fn handle_state(&mut self, state: &State, msg: Connect, ctx: &mut Context<Self>)
+//!                     -> Result<MessageFuture<Self, Connect>, Connect>
+//!     {
+//!         match *state {
+//!             State::Idle => {
+//!                 self.transition_with_timeout(
+//!                     State::Connected, Duration::from_millis(10), ctx);
+//!                 Ok(().to_result())
+//!             }
+//!             _ => Err(msg),
+//!         }
+//!     }
+//!
+//!     fn unhandled(&mut self, _: &State, _: Connect, _: &mut Context<Self>)
+//!                  -> MessageFuture<Self, Connect>
+//!     {
+//!         ().to_error()
+//!     }
+//! }
+//!
+//! impl MessageHandler<Connect> for Protocol {
+//!     fn handle(&mut self, msg: Connect, ctx: &mut Context<Self>)
+//!               -> MessageFuture<Self, Connect>
+//!     {
+//!         self.fsm_handle(msg, ctx)
+//!     }
+//! }
+//!
+//! fn main() {
+//!    let sys = System::new("test".to_owned());
+//!
+//!    let addr: Address<_> = Protocol{fsm: Fsm::new(State::Idle)}.start();
+//!    addr.send(Connect);
+//!
+//!    sys.run();
+//! }
+//! ```
+use std::fmt;
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use futures::{Async, Future, Poll};
+use futures::unsync::oneshot::{channel, Receiver, Sender};
+
+use fut::ActorFuture;
+use actor::{Actor, MessageResponse};
+use clock::Delay;
+use context::{Context, ContextFutureSpawner};
+use message::MessageFuture;
+
+/// State holder of the finite state machine actor
+pub struct Fsm<A: FsmActor> {
+    state: A::State,
+    // cancel handle of the state timeout, dropped on transition
+    timeout: Option<Sender<()>>,
+}
+
+impl<A: FsmActor> Fsm<A> {
+
+    /// Create state holder with initial state. `on_enter` is not called
+    /// for initial state.
+    pub fn new(state: A::State) -> Fsm<A> {
+        Fsm{state: state, timeout: None}
+    }
+
+    /// Current state
+    pub fn state(&self) -> &A::State {
+        &self.state
+    }
+}
+
+impl<A: FsmActor> fmt::Debug for Fsm<A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Fsm({:?})", self.state)
+    }
+}
+
+#[allow(unused_variables)]
+/// Finite state machine actor
+pub trait FsmActor: Actor {
+
+    /// State type
+    type State: Clone + PartialEq + fmt::Debug + 'static;
+
+    /// Access to state holder
+    fn fsm(&mut self) -> &mut Fsm<Self>;
+
+    /// Method is called when actor enters new state
+    fn on_enter(&mut self, state: &Self::State, ctx: &mut Context<Self>) {}
+
+    /// Method is called when actor leaves the state
+    fn on_exit(&mut self, state: &Self::State, ctx: &mut Context<Self>) {}
+
+    /// Method is called if state timeout expires
+    fn on_timeout(&mut self, state: &Self::State, ctx: &mut Context<Self>) {}
+
+    /// Current state
+    fn state(&mut self) -> Self::State {
+        self.fsm().state.clone()
+    }
+
+    /// Change state. Transition to the same state calls `on_exit` and `on_enter`
+    /// as well and cancels state timeout.
+    fn transition(&mut self, state: Self::State, ctx: &mut Context<Self>) {
+        change_state(self, state, None, ctx)
+    }
+
+    /// Change state and start state timeout. If actor does not leave the state
+    /// before timeout expires, `on_timeout` method get called.
+    /// Timeout is started before `on_enter` call, so transition made by
+    /// `on_enter` cancels it.
+    fn transition_with_timeout(&mut self, state: Self::State,
+                               timeout: Duration, ctx: &mut Context<Self>) {
+        change_state(self, state, Some(timeout), ctx)
+    }
+}
+
+fn change_state<A: FsmActor>(act: &mut A, state: A::State,
+                             timeout: Option<Duration>, ctx: &mut Context<A>) {
+    let old = act.state();
+    act.on_exit(&old, ctx);
+
+    debug!("Fsm state transition: {:?} -> {:?}", old, state);
+    {
+        let fsm = act.fsm();
+        fsm.state = state.clone();
+        // cancel timeout of the previous state
+        fsm.timeout.take();
+    }
+
+    if let Some(timeout) = timeout {
+        match Delay::new(timeout) {
+            Ok(delay) => {
+                let (tx, rx) = channel();
+                act.fsm().timeout = Some(tx);
+                StateTimeout{delay: delay, cancel: rx, act: PhantomData::<A>}.spawn(ctx);
+            }
+            Err(err) => error!("Can not start state timeout: {}", err),
+        }
+    }
+    act.on_enter(&state, ctx);
+}
+
+/// State timeout, future completes without calling `on_timeout`
+/// if actor leaves the state before timeout expires.
+struct StateTimeout<A> {
+    delay: Delay,
+    cancel: Receiver<()>,
+    act: PhantomData<A>,
+}
+
+impl<A: FsmActor> ActorFuture for StateTimeout<A> {
+    type Item = ();
+    type Error = ();
+    type Actor = A;
+
+    fn poll(&mut self, act: &mut A, ctx: &mut Context<A>) -> Poll<(), ()> {
+        // cancel handle is dropped on transition
+        match self.cancel.poll() {
+            Ok(Async::NotReady) => (),
+            _ => return Ok(Async::Ready(())),
+        }
+
+        match self.delay.poll() {
+            Ok(Async::Ready(_)) => {
+                act.fsm().timeout.take();
+                let state = act.state();
+                act.on_timeout(&state, ctx);
+                Ok(Async::Ready(()))
+            }
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            Err(err) => {
+                error!("State timeout failed: {}", err);
+                Err(())
+            }
+        }
+    }
+}
+
+/// Message handler of finite state machine actor
+pub trait FsmHandler<M>: FsmActor + MessageResponse<M> {
+
+    /// Handle message in current state. Return `Err(msg)` if message
+    /// can not be handled in this state.
+    fn handle_state(&mut self, state: &Self::State, msg: M, ctx: &mut Context<Self>)
+                    -> Result<MessageFuture<Self, M>, M>;
+
+    /// Method is called for messages that are not handled in current state
+    fn unhandled(&mut self, state: &Self::State, msg: M, ctx: &mut Context<Self>)
+                 -> MessageFuture<Self, M>;
+
+    /// Dispatch message to handler of the current state,
+    /// this method is intended to be called from `MessageHandler::handle`.
+    fn fsm_handle(&mut self, msg: M, ctx: &mut Context<Self>) -> MessageFuture<Self, M> {
+        let state = self.state();
+        match self.handle_state(&state, msg, ctx) {
+            Ok(fut) => fut,
+            Err(msg) => {
+                debug!("Message is not handled in state {:?}", state);
+                self.unhandled(&state, msg, ctx)
+            }
+        }
+    }
+}
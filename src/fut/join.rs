@@ -0,0 +1,162 @@
+use std::mem;
+use futures::{Async, Poll};
+
+use fut::{ActorFuture, IntoActorFuture};
+use context::Context;
+
+
+/// Future for the `join` combinator, waiting for two futures to
+/// complete.
+///
+/// This is created by the `Future::join` method.
+#[must_use = "futures do nothing unless polled"]
+pub struct Join<A, B> where A: ActorFuture, B: ActorFuture<Error=A::Error, Actor=A::Actor> {
+    a: MaybeDone<A>,
+    b: MaybeDone<B>,
+}
+
+pub fn new<A, B>(a: A, b: B) -> Join<A, B>
+    where A: ActorFuture,
+          B: ActorFuture<Error=A::Error, Actor=A::Actor>,
+{
+    Join {
+        a: MaybeDone::NotYet(a),
+        b: MaybeDone::NotYet(b),
+    }
+}
+
+impl<A, B> Join<A, B>
+    where A: ActorFuture,
+          B: ActorFuture<Error=A::Error, Actor=A::Actor>,
+{
+    fn erase(&mut self) {
+        self.a = MaybeDone::Gone;
+        self.b = MaybeDone::Gone;
+    }
+}
+
+impl<A, B> ActorFuture for Join<A, B>
+    where A: ActorFuture,
+          B: ActorFuture<Error=A::Error, Actor=A::Actor>,
+{
+    type Item = (A::Item, B::Item);
+    type Error = A::Error;
+    type Actor = A::Actor;
+
+    fn poll(&mut self, act: &mut A::Actor, ctx: &mut Context<A::Actor>)
+            -> Poll<Self::Item, Self::Error>
+    {
+        let mut all_done = match self.a.poll(act, ctx) {
+            Ok(done) => done,
+            Err(e) => {
+                self.erase();
+                return Err(e)
+            }
+        };
+        all_done = match self.b.poll(act, ctx) {
+            Ok(done) => all_done && done,
+            Err(e) => {
+                self.erase();
+                return Err(e)
+            }
+        };
+
+        if all_done {
+            Ok(Async::Ready((self.a.take(), self.b.take())))
+        } else {
+            Ok(Async::NotReady)
+        }
+    }
+}
+
+/// Future for the `join_all` combinator, waiting for all of a list of futures
+/// to finish.
+///
+/// This is created by the `join_all` function.
+#[must_use = "futures do nothing unless polled"]
+pub struct JoinAll<I> where I: IntoIterator, I::Item: IntoActorFuture {
+    elems: Vec<MaybeDone<<I::Item as IntoActorFuture>::Future>>,
+}
+
+/// Creates a future which represents a collection of the results of the
+/// futures given.
+///
+/// The returned future will drive execution for all of its underlying futures,
+/// collecting the results into a destination `Vec<T>` in the same order as they
+/// were provided. If any future returns an error then all other futures will be
+/// canceled and an error will be returned immediately. If all futures complete
+/// successfully, however, then the returned future will succeed with a `Vec` of
+/// all the successful results.
+pub fn join_all<I>(i: I) -> JoinAll<I>
+    where I: IntoIterator,
+          I::Item: IntoActorFuture,
+{
+    let elems = i.into_iter().map(|f| {
+        MaybeDone::NotYet(f.into_future())
+    }).collect();
+    JoinAll { elems: elems }
+}
+
+impl<I> ActorFuture for JoinAll<I>
+    where I: IntoIterator,
+          I::Item: IntoActorFuture,
+{
+    type Item = Vec<<I::Item as IntoActorFuture>::Item>;
+    type Error = <I::Item as IntoActorFuture>::Error;
+    type Actor = <I::Item as IntoActorFuture>::Actor;
+
+    fn poll(&mut self, act: &mut Self::Actor, ctx: &mut Context<Self::Actor>)
+            -> Poll<Self::Item, Self::Error>
+    {
+        let mut all_done = true;
+
+        for idx in 0 .. self.elems.len() {
+            match self.elems[idx].poll(act, ctx) {
+                Ok(done) => all_done = all_done && done,
+                Err(e) => {
+                    self.elems = Vec::new();
+                    return Err(e)
+                }
+            }
+        }
+
+        if all_done {
+            let elems = mem::replace(&mut self.elems, Vec::new());
+            Ok(Async::Ready(elems.into_iter().map(|mut e| e.take()).collect()))
+        } else {
+            Ok(Async::NotReady)
+        }
+    }
+}
+
+enum MaybeDone<A: ActorFuture> {
+    NotYet(A),
+    Done(A::Item),
+    Gone,
+}
+
+impl<A: ActorFuture> MaybeDone<A> {
+    fn poll(&mut self, act: &mut A::Actor, ctx: &mut Context<A::Actor>)
+            -> Result<bool, A::Error>
+    {
+        let res = match *self {
+            MaybeDone::NotYet(ref mut a) => a.poll(act, ctx)?,
+            MaybeDone::Done(_) => return Ok(true),
+            MaybeDone::Gone => panic!("cannot poll Join twice"),
+        };
+        match res {
+            Async::Ready(res) => {
+                *self = MaybeDone::Done(res);
+                Ok(true)
+            }
+            Async::NotReady => Ok(false),
+        }
+    }
+
+    fn take(&mut self) -> A::Item {
+        match mem::replace(self, MaybeDone::Gone) {
+            MaybeDone::Done(a) => a,
+            _ => unreachable!("MaybeDone::take() is called before future is done"),
+        }
+    }
+}
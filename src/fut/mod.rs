@@ -1,18 +1,43 @@
 use std::marker::PhantomData;
-use futures::{future, Poll};
+use std::time::Duration;
+use futures::{future, stream, Poll};
 
 mod chain;
 mod and_then;
+mod from_err;
+mod join;
 mod result;
+mod select;
 mod then;
 mod map;
 mod map_err;
+mod or_else;
+mod timeout;
+mod stream_filter;
+mod stream_finish;
+mod stream_fold;
+mod stream_map;
+mod stream_then;
+mod stream_take_while;
+mod stream_timeout;
 
 pub use self::and_then::AndThen;
+pub use self::from_err::FromErr;
+pub use self::join::{Join, JoinAll, join_all};
+pub use self::select::{Select, SelectNext};
 pub use self::then::Then;
 pub use self::map::Map;
 pub use self::map_err::MapErr;
+pub use self::or_else::OrElse;
+pub use self::timeout::Timeout;
 pub use self::result::{result, ok, err, FutureResult};
+pub use self::stream_filter::StreamFilter;
+pub use self::stream_finish::StreamFinish;
+pub use self::stream_fold::StreamFold;
+pub use self::stream_map::StreamMap;
+pub use self::stream_then::StreamThen;
+pub use self::stream_take_while::StreamTakeWhile;
+pub use self::stream_timeout::StreamTimeout;
 
 use actor::Actor;
 use context::Context;
@@ -66,6 +91,69 @@ pub trait ActorFuture {
         and_then::new(self, f)
     }
 
+    /// Execute another future if this one resolves with an error.
+    fn or_else<F, B>(self, f: F) -> OrElse<Self, B, F>
+        where F: FnOnce(Self::Error, &mut Self::Actor, &mut Context<Self::Actor>) -> B,
+              B: IntoActorFuture<Item=Self::Item, Actor=Self::Actor>,
+              Self: Sized,
+    {
+        or_else::new(self, f)
+    }
+
+    /// Map this future's error to any error implementing `From` for
+    /// this future's `Error`, returning a new future.
+    fn from_err<E: From<Self::Error>>(self) -> FromErr<Self, E>
+        where Self: Sized,
+    {
+        from_err::new(self)
+    }
+
+    /// Waits for either one of two futures to complete.
+    ///
+    /// Result of this future is a tuple with result of the first completed
+    /// future and future which represents another future.
+    fn select<B>(self, other: B) -> Select<Self, B::Future>
+        where B: IntoActorFuture<Item=Self::Item, Error=Self::Error, Actor=Self::Actor>,
+              Self: Sized,
+    {
+        select::new(self, other.into_future())
+    }
+
+    /// Joins the result of two futures, waiting for them both to complete.
+    fn join<B>(self, other: B) -> Join<Self, B::Future>
+        where B: IntoActorFuture<Error=Self::Error, Actor=Self::Actor>,
+              Self: Sized,
+    {
+        join::new(self, other.into_future())
+    }
+
+    /// Add timeout to futures chain.
+    ///
+    /// `err` value get returned as a timeout error.
+    fn timeout(self, timeout: Duration, err: Self::Error) -> Timeout<Self>
+        where Self: Sized,
+    {
+        timeout::new(self, timeout, err)
+    }
+
+    /// Wrap this future in a `Box`
+    fn boxed(self) -> Box<ActorFuture<Item=Self::Item, Error=Self::Error, Actor=Self::Actor>>
+        where Self: Sized + 'static
+    {
+        Box::new(self)
+    }
+}
+
+impl<F: ?Sized + ActorFuture> ActorFuture for Box<F> {
+    type Item = F::Item;
+    type Error = F::Error;
+    type Actor = F::Actor;
+
+    fn poll(&mut self, srv: &mut Self::Actor, ctx: &mut Context<Self::Actor>)
+            -> Poll<Self::Item, Self::Error>
+    {
+        (**self).poll(srv, ctx)
+    }
 }
 
 
@@ -146,3 +234,151 @@ impl<F, A> ActorFuture for FutureWrap<F, A>
         self.fut.poll()
     }
 }
+
+
+/// A stream of values, not all of which may have been produced yet.
+///
+/// This is similar to `futures::Stream` trait, except it works with `Actor`.
+/// Stream get polled within actor's execution context, so all combinators
+/// have access to actor's state and context.
+pub trait ActorStream {
+
+    /// The type of item this stream will yield on success.
+    type Item;
+
+    /// The type of error this stream may generate.
+    type Error;
+
+    /// The actor within which this stream runs.
+    type Actor: Actor;
+
+    fn poll(&mut self, srv: &mut Self::Actor, ctx: &mut Context<Self::Actor>)
+            -> Poll<Option<Self::Item>, Self::Error>;
+
+    /// Converts a stream of type `T` to a stream of type `U`.
+    fn map<U, F>(self, f: F) -> StreamMap<Self, F>
+        where F: FnMut(Self::Item, &mut Self::Actor, &mut Context<Self::Actor>) -> U,
+              Self: Sized
+    {
+        stream_map::new(self, f)
+    }
+
+    /// Filters the values produced by this stream according to the provided
+    /// predicate.
+    fn filter<F>(self, f: F) -> StreamFilter<Self, F>
+        where F: FnMut(&Self::Item, &mut Self::Actor, &mut Context<Self::Actor>) -> bool,
+              Self: Sized
+    {
+        stream_filter::new(self, f)
+    }
+
+    /// Chain on a computation for when a value is ready, passing the resulting
+    /// item to the provided closure `f`.
+    fn then<F, U>(self, f: F) -> StreamThen<Self, F, U>
+        where F: FnMut(Result<Self::Item, Self::Error>,
+                       &mut Self::Actor, &mut Context<Self::Actor>) -> U,
+              U: IntoActorFuture<Actor=Self::Actor>,
+              Self: Sized
+    {
+        stream_then::new(self, f)
+    }
+
+    /// Execute an accumulating computation over a stream, collecting all the
+    /// values into one final result.
+    fn fold<F, T, Fut>(self, init: T, f: F) -> StreamFold<Self, F, Fut, T>
+        where F: FnMut(T, Self::Item, &mut Self::Actor, &mut Context<Self::Actor>) -> Fut,
+              Fut: IntoActorFuture<Item=T, Actor=Self::Actor>,
+              Self::Error: From<Fut::Error>,
+              Self: Sized
+    {
+        stream_fold::new(self, f, init)
+    }
+
+    /// Take elements from this stream while the provided predicate
+    /// resolves to `true`.
+    fn take_while<P, R>(self, p: P) -> StreamTakeWhile<Self, P, R>
+        where P: FnMut(&Self::Item, &mut Self::Actor, &mut Context<Self::Actor>) -> R,
+              R: IntoActorFuture<Item=bool, Error=Self::Error, Actor=Self::Actor>,
+              Self: Sized
+    {
+        stream_take_while::new(self, p)
+    }
+
+    /// Add timeout to stream.
+    ///
+    /// `err` value get returned as a timeout error if next item is not received
+    /// within `timeout`.
+    fn timeout(self, timeout: Duration, err: Self::Error) -> StreamTimeout<Self>
+        where Self::Error: Clone,
+              Self: Sized,
+    {
+        stream_timeout::new(self, timeout, err)
+    }
+
+    /// Converts a stream to a future that resolves when stream finishes.
+    fn finish(self) -> StreamFinish<Self> where Self: Sized {
+        stream_finish::new(self)
+    }
+}
+
+impl<S: ?Sized + ActorStream> ActorStream for Box<S> {
+    type Item = S::Item;
+    type Error = S::Error;
+    type Actor = S::Actor;
+
+    fn poll(&mut self, srv: &mut Self::Actor, ctx: &mut Context<Self::Actor>)
+            -> Poll<Option<Self::Item>, Self::Error>
+    {
+        (**self).poll(srv, ctx)
+    }
+}
+
+/// Helper trait that allows conversion of normal stream into `ActorStream`
+pub trait WrapStream<A> where A: Actor {
+    /// The stream that this type can be converted into.
+    type Stream: ActorStream<Item=Self::Item, Error=Self::Error, Actor=A>;
+
+    /// The item that the stream may resolve with.
+    type Item;
+    /// The error that the stream may resolve with.
+    type Error;
+
+    /// Convert normal stream to a ActorStream
+    fn actstream(self) -> Self::Stream;
+}
+
+impl<S: stream::Stream, A: Actor> WrapStream<A> for S {
+    type Stream = StreamWrap<S, A>;
+    type Item = S::Item;
+    type Error = S::Error;
+
+    fn actstream(self) -> Self::Stream {
+        wrap_stream(self)
+    }
+}
+
+pub struct StreamWrap<S, A> where S: stream::Stream {
+    st: S,
+    act: PhantomData<A>,
+}
+
+pub fn wrap_stream<S, A>(s: S) -> StreamWrap<S, A>
+    where S: stream::Stream
+{
+    StreamWrap{st: s, act: PhantomData}
+}
+
+impl<S, A> ActorStream for StreamWrap<S, A>
+    where S: stream::Stream,
+          A: Actor,
+{
+    type Item = S::Item;
+    type Error = S::Error;
+    type Actor = A;
+
+    fn poll(&mut self, _: &mut Self::Actor, _: &mut Context<Self::Actor>)
+            -> Poll<Option<Self::Item>, Self::Error>
+    {
+        self.st.poll()
+    }
+}
@@ -0,0 +1,48 @@
+use futures::Poll;
+
+use super::chain::Chain;
+use super::{ActorFuture, IntoActorFuture};
+use context::Context;
+
+
+/// Future for the `or_else` combinator, chaining a computation onto the end of
+/// a future which fails with an error.
+///
+/// This is created by the `Future::or_else` method.
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct OrElse<A, B, F>
+    where A: ActorFuture,
+          B: IntoActorFuture<Actor=A::Actor>
+{
+    state: Chain<A, B::Future, F>,
+}
+
+pub fn new<A, B, F>(future: A, f: F) -> OrElse<A, B, F>
+    where A: ActorFuture,
+          B: IntoActorFuture<Actor=A::Actor>
+{
+    OrElse {
+        state: Chain::new(future, f),
+    }
+}
+
+impl<A, B, F> ActorFuture for OrElse<A, B, F>
+    where A: ActorFuture,
+          B: IntoActorFuture<Actor=A::Actor, Item=A::Item>,
+          F: FnOnce(A::Error, &mut A::Actor, &mut Context<A::Actor>) -> B,
+{
+    type Item = B::Item;
+    type Error = B::Error;
+    type Actor = A::Actor;
+
+    fn poll(&mut self, act: &mut A::Actor, ctx: &mut Context<A::Actor>) -> Poll<B::Item, B::Error>
+    {
+        self.state.poll(act, ctx, |a, f, act, ctx| {
+            match a {
+                Ok(item) => Ok(Ok(item)),
+                Err(e) => Ok(Err(f(e, act, ctx).into_future()))
+            }
+        })
+    }
+}
@@ -0,0 +1,88 @@
+use futures::{Async, Poll};
+
+use fut::ActorFuture;
+use context::Context;
+
+
+/// Future for the `select` combinator, waiting for one of two futures to
+/// complete.
+///
+/// This is created by the `ActorFuture::select` method.
+#[must_use = "futures do nothing unless polled"]
+pub struct Select<A, B> where A: ActorFuture {
+    inner: Option<(A, B)>,
+}
+
+/// Future yielded as the second result in a `Select` future.
+///
+/// This sentinel future represents the completion of the second future to a
+/// `select` which finished second.
+#[must_use = "futures do nothing unless polled"]
+pub enum SelectNext<A, B> where A: ActorFuture {
+    A(A),
+    B(B),
+}
+
+pub fn new<A, B>(a: A, b: B) -> Select<A, B>
+    where A: ActorFuture,
+          B: ActorFuture<Item=A::Item, Error=A::Error, Actor=A::Actor>,
+{
+    Select {
+        inner: Some((a, b)),
+    }
+}
+
+impl<A, B> ActorFuture for Select<A, B>
+    where A: ActorFuture,
+          B: ActorFuture<Item=A::Item, Error=A::Error, Actor=A::Actor>,
+{
+    type Item = (A::Item, SelectNext<A, B>);
+    type Error = (A::Error, SelectNext<A, B>);
+    type Actor = A::Actor;
+
+    fn poll(&mut self, act: &mut A::Actor, ctx: &mut Context<A::Actor>)
+            -> Poll<Self::Item, Self::Error>
+    {
+        let (ret, is_a) = match self.inner {
+            Some((ref mut a, ref mut b)) => {
+                match a.poll(act, ctx) {
+                    Err(a) => (Err(a), true),
+                    Ok(Async::Ready(a)) => (Ok(a), true),
+                    Ok(Async::NotReady) => {
+                        match b.poll(act, ctx) {
+                            Err(a) => (Err(a), false),
+                            Ok(Async::Ready(a)) => (Ok(a), false),
+                            Ok(Async::NotReady) => return Ok(Async::NotReady),
+                        }
+                    }
+                }
+            }
+            None => panic!("cannot poll select twice"),
+        };
+
+        let (a, b) = self.inner.take().unwrap();
+        let next = if is_a {SelectNext::B(b)} else {SelectNext::A(a)};
+        match ret {
+            Ok(a) => Ok(Async::Ready((a, next))),
+            Err(e) => Err((e, next)),
+        }
+    }
+}
+
+impl<A, B> ActorFuture for SelectNext<A, B>
+    where A: ActorFuture,
+          B: ActorFuture<Item=A::Item, Error=A::Error, Actor=A::Actor>,
+{
+    type Item = A::Item;
+    type Error = A::Error;
+    type Actor = A::Actor;
+
+    fn poll(&mut self, act: &mut A::Actor, ctx: &mut Context<A::Actor>)
+            -> Poll<Self::Item, Self::Error>
+    {
+        match *self {
+            SelectNext::A(ref mut a) => a.poll(act, ctx),
+            SelectNext::B(ref mut b) => b.poll(act, ctx),
+        }
+    }
+}
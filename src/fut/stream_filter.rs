@@ -0,0 +1,49 @@
+use futures::{Async, Poll};
+
+use fut::ActorStream;
+use context::Context;
+
+
+/// A stream combinator used to filter the results of a stream and only yield
+/// some values.
+///
+/// This is produced by the `ActorStream::filter` method.
+#[derive(Debug)]
+#[must_use = "streams do nothing unless polled"]
+pub struct StreamFilter<S, F> {
+    stream: S,
+    f: F,
+}
+
+pub fn new<S, F>(s: S, f: F) -> StreamFilter<S, F>
+    where S: ActorStream,
+          F: FnMut(&S::Item, &mut S::Actor, &mut Context<S::Actor>) -> bool,
+{
+    StreamFilter {
+        stream: s,
+        f: f,
+    }
+}
+
+impl<S, F> ActorStream for StreamFilter<S, F>
+    where S: ActorStream,
+          F: FnMut(&S::Item, &mut S::Actor, &mut Context<S::Actor>) -> bool,
+{
+    type Item = S::Item;
+    type Error = S::Error;
+    type Actor = S::Actor;
+
+    fn poll(&mut self, act: &mut Self::Actor, ctx: &mut Context<Self::Actor>)
+            -> Poll<Option<S::Item>, S::Error>
+    {
+        loop {
+            match self.stream.poll(act, ctx)? {
+                Async::Ready(Some(item)) => if (self.f)(&item, act, ctx) {
+                    return Ok(Async::Ready(Some(item)))
+                },
+                Async::Ready(None) => return Ok(Async::Ready(None)),
+                Async::NotReady => return Ok(Async::NotReady),
+            }
+        }
+    }
+}
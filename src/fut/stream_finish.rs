@@ -0,0 +1,37 @@
+use futures::{Async, Poll};
+
+use fut::{ActorFuture, ActorStream};
+use context::Context;
+
+
+/// A combinator used to convert stream into a future, future resolves
+/// when stream completes.
+///
+/// This structure is produced by the `ActorStream::finish` method.
+#[must_use = "futures do nothing unless polled"]
+pub struct StreamFinish<S>(S);
+
+pub fn new<S>(s: S) -> StreamFinish<S>
+    where S: ActorStream,
+{
+    StreamFinish(s)
+}
+
+impl<S> ActorFuture for StreamFinish<S>
+    where S: ActorStream,
+{
+    type Item = ();
+    type Error = S::Error;
+    type Actor = S::Actor;
+
+    fn poll(&mut self, act: &mut S::Actor, ctx: &mut Context<S::Actor>) -> Poll<(), S::Error>
+    {
+        loop {
+            match self.0.poll(act, ctx)? {
+                Async::Ready(Some(_)) => (),
+                Async::Ready(None) => return Ok(Async::Ready(())),
+                Async::NotReady => return Ok(Async::NotReady),
+            }
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use futures::{Async, Poll};
+
+use fut::ActorStream;
+use context::Context;
+
+
+/// A stream combinator which will change the type of a stream from one
+/// type to another.
+///
+/// This is produced by the `ActorStream::map` method.
+#[derive(Debug)]
+#[must_use = "streams do nothing unless polled"]
+pub struct StreamMap<S, F> {
+    stream: S,
+    f: F,
+}
+
+pub fn new<S, F, U>(s: S, f: F) -> StreamMap<S, F>
+    where S: ActorStream,
+          F: FnMut(S::Item, &mut S::Actor, &mut Context<S::Actor>) -> U,
+{
+    StreamMap {
+        stream: s,
+        f: f,
+    }
+}
+
+impl<S, F, U> ActorStream for StreamMap<S, F>
+    where S: ActorStream,
+          F: FnMut(S::Item, &mut S::Actor, &mut Context<S::Actor>) -> U,
+{
+    type Item = U;
+    type Error = S::Error;
+    type Actor = S::Actor;
+
+    fn poll(&mut self, act: &mut Self::Actor, ctx: &mut Context<Self::Actor>)
+            -> Poll<Option<U>, S::Error>
+    {
+        match self.stream.poll(act, ctx)? {
+            Async::Ready(Some(item)) => Ok(Async::Ready(Some((self.f)(item, act, ctx)))),
+            Async::Ready(None) => Ok(Async::Ready(None)),
+            Async::NotReady => Ok(Async::NotReady),
+        }
+    }
+}
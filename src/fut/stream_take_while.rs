@@ -0,0 +1,73 @@
+use futures::{Async, Poll};
+
+use fut::{ActorFuture, ActorStream, IntoActorFuture};
+use context::Context;
+
+
+/// A stream combinator which takes elements from a stream while a predicate
+/// holds.
+///
+/// This structure is produced by the `ActorStream::take_while` method.
+#[must_use = "streams do nothing unless polled"]
+pub struct StreamTakeWhile<S, P, R> where S: ActorStream, R: IntoActorFuture {
+    stream: S,
+    pred: P,
+    pending: Option<(R::Future, S::Item)>,
+    done_taking: bool,
+}
+
+pub fn new<S, P, R>(s: S, p: P) -> StreamTakeWhile<S, P, R>
+    where S: ActorStream,
+          P: FnMut(&S::Item, &mut S::Actor, &mut Context<S::Actor>) -> R,
+          R: IntoActorFuture<Item=bool, Error=S::Error, Actor=S::Actor>,
+{
+    StreamTakeWhile {
+        stream: s,
+        pred: p,
+        pending: None,
+        done_taking: false,
+    }
+}
+
+impl<S, P, R> ActorStream for StreamTakeWhile<S, P, R>
+    where S: ActorStream,
+          P: FnMut(&S::Item, &mut S::Actor, &mut Context<S::Actor>) -> R,
+          R: IntoActorFuture<Item=bool, Error=S::Error, Actor=S::Actor>,
+{
+    type Item = S::Item;
+    type Error = S::Error;
+    type Actor = S::Actor;
+
+    fn poll(&mut self, act: &mut S::Actor, ctx: &mut Context<S::Actor>)
+            -> Poll<Option<S::Item>, S::Error>
+    {
+        if self.done_taking {
+            return Ok(Async::Ready(None));
+        }
+
+        if self.pending.is_none() {
+            let item = match try_ready!(self.stream.poll(act, ctx)) {
+                Some(e) => e,
+                None => return Ok(Async::Ready(None)),
+            };
+            self.pending = Some(((self.pred)(&item, act, ctx).into_future(), item));
+        }
+
+        assert!(self.pending.is_some());
+        match self.pending.as_mut().unwrap().0.poll(act, ctx) {
+            Ok(Async::Ready(true)) => {
+                let (_, item) = self.pending.take().unwrap();
+                Ok(Async::Ready(Some(item)))
+            },
+            Ok(Async::Ready(false)) => {
+                self.done_taking = true;
+                Ok(Async::Ready(None))
+            }
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            Err(e) => {
+                self.pending = None;
+                Err(e)
+            }
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use std::time::Duration;
+use futures::{Async, Future, Poll};
+
+use fut::ActorStream;
+use clock::Delay;
+use context::Context;
+
+
+/// Stream for the `timeout` combinator, interrupts computations if it takes more
+/// than `timeout` to receive next item.
+///
+/// This is created by the `ActorStream::timeout()` method.
+#[must_use = "streams do nothing unless polled"]
+pub struct StreamTimeout<S> where S: ActorStream {
+    stream: S,
+    dur: Duration,
+    err: S::Error,
+    timeout: Option<Delay>,
+}
+
+pub fn new<S>(stream: S, timeout: Duration, err: S::Error) -> StreamTimeout<S>
+    where S: ActorStream, S::Error: Clone
+{
+    StreamTimeout {
+        stream: stream,
+        dur: timeout,
+        err: err,
+        timeout: None,
+    }
+}
+
+impl<S> ActorStream for StreamTimeout<S> where S: ActorStream, S::Error: Clone
+{
+    type Item = S::Item;
+    type Error = S::Error;
+    type Actor = S::Actor;
+
+    fn poll(&mut self, act: &mut S::Actor, ctx: &mut Context<S::Actor>)
+            -> Poll<Option<S::Item>, S::Error>
+    {
+        match self.stream.poll(act, ctx) {
+            Ok(Async::NotReady) => (),
+            result => {
+                // reset timeout
+                self.timeout = None;
+                return result
+            }
+        }
+
+        if self.timeout.is_none() {
+            match Delay::new(self.dur) {
+                Ok(timeout) => self.timeout = Some(timeout),
+                Err(_) => return Err(self.err.clone()),
+            }
+        }
+
+        // check timeout
+        match self.timeout.as_mut().unwrap().poll() {
+            Ok(Async::Ready(_)) | Err(_) => {
+                self.timeout = None;
+                Err(self.err.clone())
+            }
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+        }
+    }
+}
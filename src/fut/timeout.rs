@@ -0,0 +1,62 @@
+use std::time::Duration;
+use futures::{Async, Future, Poll};
+
+use fut::ActorFuture;
+use clock::Delay;
+use context::Context;
+
+
+/// Future for the `timeout` combinator, interrupts computations if it takes more
+/// than `timeout`.
+///
+/// This is created by the `ActorFuture::timeout()` method. Timer starts
+/// when future get polled first time, if timer can not be created
+/// future resolves with timeout error.
+#[must_use = "futures do nothing unless polled"]
+pub struct Timeout<F> where F: ActorFuture {
+    fut: F,
+    err: Option<F::Error>,
+    dur: Duration,
+    timeout: Option<Delay>,
+}
+
+pub fn new<F>(future: F, timeout: Duration, err: F::Error) -> Timeout<F>
+    where F: ActorFuture
+{
+    Timeout {
+        fut: future,
+        err: Some(err),
+        dur: timeout,
+        timeout: None,
+    }
+}
+
+impl<F> ActorFuture for Timeout<F> where F: ActorFuture
+{
+    type Item = F::Item;
+    type Error = F::Error;
+    type Actor = F::Actor;
+
+    fn poll(&mut self, act: &mut F::Actor, ctx: &mut Context<F::Actor>)
+            -> Poll<F::Item, F::Error>
+    {
+        match self.fut.poll(act, ctx) {
+            Ok(Async::NotReady) => (),
+            result => return result,
+        }
+
+        if self.timeout.is_none() {
+            match Delay::new(self.dur) {
+                Ok(timeout) => self.timeout = Some(timeout),
+                Err(_) => return Err(self.err.take().expect("cannot poll Timeout twice")),
+            }
+        }
+
+        // check timeout
+        match self.timeout.as_mut().unwrap().poll() {
+            Ok(Async::Ready(_)) | Err(_) =>
+                Err(self.err.take().expect("cannot poll Timeout twice")),
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+        }
+    }
+}
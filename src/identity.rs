@@ -0,0 +1,158 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::AtomicUsize;
+use uuid::Uuid;
+
+use actor::Actor;
+use budget::MessageBudget;
+use context::Context;
+use metrics::Metrics;
+
+/// Actor identifier
+///
+/// Each actor's context is assigned unique identifier. Identifier is also
+/// available from actor's addresses, addresses of the same actor are equal.
+/// Supervised actor keeps its identifier after restart.
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+pub struct ActorId(Uuid);
+
+impl ActorId {
+    pub(crate) fn new() -> ActorId {
+        ActorId(Uuid::new_v4())
+    }
+}
+
+impl fmt::Display for ActorId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl fmt::Debug for ActorId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ActorId({})", self.0)
+    }
+}
+
+/// Identifier and name of the actor. Identifier is generated on first use,
+/// mailboxes that get identity of other mailbox never generate their own.
+#[derive(Default)]
+pub(crate) struct ActorInfo {
+    id: Mutex<Option<ActorId>>,
+    name: Mutex<Option<String>>,
+}
+
+impl ActorInfo {
+
+    pub fn id(&self) -> ActorId {
+        let mut id = self.id.lock().unwrap();
+        match *id {
+            Some(id) => id,
+            None => {
+                let new = ActorId::new();
+                *id = Some(new);
+                new
+            }
+        }
+    }
+
+    pub fn name(&self) -> Option<String> {
+        self.name.lock().unwrap().clone()
+    }
+
+    pub fn set_name(&self, name: String) {
+        *self.name.lock().unwrap() = Some(name);
+    }
+}
+
+impl fmt::Debug for ActorInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ActorInfo({:?})", *self.id.lock().unwrap())
+    }
+}
+
+/// Actor identity: identifier and name, metrics collector, message budget
+/// and restart counter. Identity is stored in actor's mailboxes,
+/// clones of identity share all of them.
+#[derive(Clone, Debug)]
+pub(crate) struct Identity {
+    pub info: Arc<ActorInfo>,
+    pub metrics: Arc<Metrics>,
+    pub budget: Arc<MessageBudget>,
+    /// Number of restarts, incremented by supervisor before restarted
+    /// actor handles messages
+    pub generation: Arc<AtomicUsize>,
+}
+
+impl Identity {
+    pub fn new() -> Identity {
+        Identity::with_info(Arc::new(ActorInfo::default()))
+    }
+
+    pub fn with_info(info: Arc<ActorInfo>) -> Identity {
+        Identity{metrics: Arc::new(Metrics::new(Arc::clone(&info))),
+                 info: info,
+                 budget: Arc::new(MessageBudget::default()),
+                 generation: Arc::new(AtomicUsize::new(0))}
+    }
+
+    pub fn id(&self) -> ActorId {
+        self.info.id()
+    }
+
+    pub fn name(&self) -> Option<String> {
+        self.info.name()
+    }
+
+    pub fn set_name(&self, name: String) {
+        self.info.set_name(name)
+    }
+}
+
+impl fmt::Display for Identity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "{}({})", name, self.id()),
+            None => write!(f, "{}", self.id()),
+        }
+    }
+}
+
+impl<A> Context<A> where A: Actor {
+
+    /// Actor's identifier
+    pub fn id(&mut self) -> ActorId {
+        self.address_cell().unsync_sender().id()
+    }
+
+    /// Actor's name
+    pub fn name(&mut self) -> Option<String> {
+        self.address_cell().unsync_sender().identity().name()
+    }
+
+    /// Set actor's name. Name is used in log messages and is
+    /// available from actor's addresses.
+    pub fn set_name<T: Into<String>>(&mut self, name: T) {
+        let name = name.into();
+        debug!("Actor {} is named {}", self.id(), name);
+        // sync mailbox shares identity of the context
+        self.address_cell().unsync_sender().set_name(name);
+    }
+
+    /// Set number of messages actor handles per tick, `None` resets budget
+    /// to arbiter's budget. Once budget is used up, context yields back
+    /// to the event loop, other actors of the arbiter get a chance to run.
+    pub fn set_message_budget(&mut self, budget: Option<usize>) {
+        self.address_cell().unsync_sender().identity().budget.set(budget);
+    }
+
+    /// Number of messages actor handles per tick
+    pub fn message_budget(&mut self) -> usize {
+        self.address_cell().unsync_sender().identity().budget.get()
+    }
+
+    /// Actor's identity in `name(id)` format, for log messages
+    pub fn identity(&mut self) -> String {
+        self.address_cell().unsync_sender().identity().to_string()
+    }
+}
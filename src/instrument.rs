@@ -0,0 +1,111 @@
+//! Instrumentation settings of the system
+//!
+//! Message handling is instrumented only if the system has message interceptors
+//! or timing metrics are enabled. Settings are checked for every handled message,
+//! so each arbiter keeps its own copy of the settings and refreshes it only
+//! after settings of the system change.
+use std::cell::RefCell;
+use std::sync::{Arc, RwLock, Weak};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use arbiter::Arbiter;
+use trace::{Interceptor, Interceptors};
+
+thread_local!(static CACHE: RefCell<Option<Cached>> = RefCell::new(None));
+
+/// Instrumentation settings, shared by system registry clones
+pub(crate) struct Instrumentation {
+    // list is immutable, spans hold snapshot of the list
+    interceptors: RwLock<Interceptors>,
+    timings: AtomicBool,
+    // incremented after each change, checked without locking
+    version: AtomicUsize,
+}
+
+impl Instrumentation {
+
+    pub fn new() -> Instrumentation {
+        Instrumentation{interceptors: RwLock::new(Arc::new(Vec::new())),
+                        timings: AtomicBool::new(false),
+                        version: AtomicUsize::new(0)}
+    }
+
+    pub fn add_interceptor(&self, interceptor: Arc<Interceptor>) {
+        match self.interceptors.write() {
+            Ok(mut list) => {
+                let mut new: Vec<_> = list.iter().cloned().collect();
+                new.push(interceptor);
+                *list = Arc::new(new);
+            }
+            Err(_) => panic!("RwLock is poisoned"),
+        }
+        self.version.fetch_add(1, Ordering::Release);
+    }
+
+    pub fn enable_timings(&self) {
+        if !self.timings.swap(true, Ordering::Relaxed) {
+            self.version.fetch_add(1, Ordering::Release);
+        }
+    }
+
+    fn version(&self) -> usize {
+        self.version.load(Ordering::Acquire)
+    }
+
+    fn snapshot(&self) -> Instruments {
+        let interceptors = match self.interceptors.read() {
+            Ok(list) => Arc::clone(&*list),
+            Err(_) => panic!("RwLock is poisoned"),
+        };
+        Instruments {
+            interceptors: if interceptors.is_empty() { None } else { Some(interceptors) },
+            timings: self.timings.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Instruments of the message handling
+#[derive(Clone)]
+pub(crate) struct Instruments {
+    /// Message interceptors, `None` if there are no interceptors
+    pub interceptors: Option<Interceptors>,
+    /// Measure time of message handlers and response futures
+    pub timings: bool,
+}
+
+/// Arbiter's copy of the settings, copy is refreshed after settings
+/// change or if thread runs new system.
+struct Cached {
+    settings: Weak<Instrumentation>,
+    version: usize,
+    instruments: Instruments,
+}
+
+/// Instruments of the current thread's system
+pub(crate) fn current() -> Instruments {
+    CACHE.with(|cell| {
+        let mut cache = cell.borrow_mut();
+        let valid = match *cache {
+            Some(ref cached) => match cached.settings.upgrade() {
+                Some(settings) => settings.version() == cached.version,
+                None => false,
+            },
+            None => false,
+        };
+        if !valid {
+            let registry = Arbiter::system_registry();
+            let settings = registry.instrumentation();
+            // version is loaded first, concurrent change causes one more refresh
+            let version = settings.version();
+            *cache = Some(Cached {
+                settings: Arc::downgrade(settings),
+                version: version,
+                instruments: settings.snapshot(),
+            });
+        }
+        match *cache {
+            Some(ref cached) => cached.instruments.clone(),
+            None => Instruments{interceptors: None, timings: false},
+        }
+    })
+}
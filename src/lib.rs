@@ -18,6 +18,7 @@
 
 #[macro_use]
 extern crate log;
+extern crate rand;
 extern crate uuid;
 
 extern crate bytes;
@@ -31,17 +32,26 @@ extern crate tokio_core;
 extern crate tokio_signal;
 #[cfg(feature="signal")]
 extern crate libc;
+#[cfg(all(unix, feature="uds"))]
+extern crate tokio_uds;
 
 mod actor;
 mod arbiter;
 mod address;
+mod budget;
 mod sync_address;
 mod builder;
+mod clock;
 mod context;
+mod identity;
+mod instrument;
 mod message;
 mod queue;
 mod registry;
+mod retry;
 mod sink;
+mod stash;
+mod stream;
 mod system;
 mod supervisor;
 mod utils;
@@ -50,15 +60,25 @@ pub mod fut;
 pub mod prelude;
 pub mod actors;
 pub mod framed;
+pub mod fsm;
+pub mod metrics;
+pub mod test;
+pub mod trace;
+pub mod transport;
 
-pub use actor::{Actor, SupervisedActor, MessageHandler, MessageResponse, StreamHandler};
+pub use actor::{Actor, SupervisedActor, MessageHandler, MessageResponse,
+                StreamHandler, StreamControl, StreamEnd, SinkHandler};
 pub use address::{Address, SyncAddress, Subscriber, AsyncSubscriber};
 pub use arbiter::{Arbiter, Execute, StartActor, StopArbiter};
 pub use builder::ActorBuilder;
 pub use context::{ActorState, Context, ContextFutureSpawner};
+pub use identity::ActorId;
+pub use queue::Priority;
 pub use message::{MessageResult, MessageFuture, MessageFutureResult, MessageFutureError};
 pub use registry::{Registry, SystemRegistry};
+pub use retry::{Retry, RetryCall, RetryError, SyncRetryCall};
 pub use sink::Sink;
+pub use stash::Stash;
 pub use system::{System, SystemExit, SystemRunner};
 pub use utils::Condition;
 pub use supervisor::Supervisor;
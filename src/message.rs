@@ -1,13 +1,23 @@
 use std;
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
 use std::marker::PhantomData;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Instant;
 
 use futures::{Async, Future, Poll};
+use futures::sync::oneshot;
 use futures::unsync::oneshot::{Canceled, Receiver, Sender};
 
 use fut::ActorFuture;
+use instrument;
 use context::Context;
 use address::MessageProxy;
 use actor::{Actor, MessageHandler, MessageResponse};
+use metrics::Metrics;
+use trace::{self, MessageSpan, TraceContext};
+use utils;
 
 
 /// `MessageResult` is a `Future` which represents asyncronous message response.
@@ -85,7 +95,8 @@ enum MessageFutureItem<A, M> where A: Actor + MessageResponse<M>
 {
     Item(A::Item),
     Error(A::Error),
-    Fut(Box<ActorFuture<Item=A::Item, Error=A::Error, Actor=A>>)
+    Fut(Box<ActorFuture<Item=A::Item, Error=A::Error, Actor=A>>),
+    Deferred(ReplySlot<A::Item, A::Error>),
 }
 
 /// `MessageFuture` represents asyncronous message handling process.
@@ -156,11 +167,134 @@ impl<A, M> MessageFuture<A, M> where A: Actor + MessageResponse<M>
                     }
                 }
                 MessageFutureItem::Item(item) => return Ok(Async::Ready(item)),
-                MessageFutureItem::Error(err) => return Err(err)
+                MessageFutureItem::Error(err) => return Err(err),
+                // message is stashed, response is delivered through reply slot
+                // after replay, so this future never completes
+                MessageFutureItem::Deferred(_) => panic!(
+                    "Future of stashed message is polled, result of `Stash::defer()` \
+                     has to be returned from message handler"),
             }
         }
         Ok(Async::NotReady)
     }
+
+    /// Future of the message that is stashed by actor, see `Stash::defer()`
+    pub(crate) fn deferred(slot: ReplySlot<A::Item, A::Error>) -> MessageFuture<A, M> {
+        MessageFuture {inner: Some(MessageFutureItem::Deferred(slot))}
+    }
+
+    /// Take reply slot if message is stashed by actor
+    pub(crate) fn take_deferred(&mut self) -> Option<ReplySlot<A::Item, A::Error>> {
+        match self.inner.take() {
+            Some(MessageFutureItem::Deferred(slot)) => Some(slot),
+            item => {
+                self.inner = item;
+                None
+            }
+        }
+    }
+
+    /// Take handler's result if it is available without polling
+    pub(crate) fn take_result(&mut self) -> Option<Result<A::Item, A::Error>> {
+        match self.inner.take() {
+            Some(MessageFutureItem::Item(item)) => Some(Ok(item)),
+            Some(MessageFutureItem::Error(err)) => Some(Err(err)),
+            item => {
+                self.inner = item;
+                None
+            }
+        }
+    }
+}
+
+/// Response channel of the message envelope
+pub(crate) trait Responder<I, E> {
+    fn respond(self, res: Result<I, E>);
+}
+
+impl<I, E> Responder<I, E> for Sender<Result<I, E>> {
+    fn respond(self, res: Result<I, E>) {
+        let _ = self.send(res);
+    }
+}
+
+impl<I, E> Responder<I, E> for oneshot::Sender<Result<I, E>> {
+    fn respond(self, res: Result<I, E>) {
+        let _ = self.send(res);
+    }
+}
+
+/// Type erased response channel
+pub(crate) trait BoxedResponder<I, E> {
+    fn respond_boxed(self: Box<Self>, res: Result<I, E>);
+}
+
+impl<I, E, R: Responder<I, E>> BoxedResponder<I, E> for R {
+    fn respond_boxed(self: Box<Self>, res: Result<I, E>) {
+        (*self).respond(res)
+    }
+}
+
+impl<I, E> Responder<I, E> for Box<BoxedResponder<I, E>> {
+    fn respond(self, res: Result<I, E>) {
+        self.respond_boxed(res)
+    }
+}
+
+/// Response channel of the stashed message. Envelope moves its response
+/// channel into the slot, so response is sent directly to the caller after replay.
+pub(crate) type ReplySlot<I, E> = Rc<RefCell<Option<Box<BoxedResponder<I, E>>>>>;
+
+/// Call message handler. Immediate result is sent to the caller in place,
+/// only pending response future is spawned into the actor's context.
+pub(crate) fn handle_message<A, M, R>(act: &mut A, ctx: &mut Context<A>, msg: M,
+                                      tx: Option<R>, trace: Option<TraceContext>)
+    where A: Actor + MessageHandler<M>,
+          M: 'static,
+          R: Responder<A::Item, A::Error> + 'static
+{
+    let metrics = ctx.metrics();
+    metrics.received();
+
+    // span and timings are skipped if nobody observes them
+    let instruments = instrument::current();
+    let span = MessageSpan::new::<A, M>(trace, instruments.interceptors, ctx);
+    let guard = span.enter();
+    span.before();
+
+    let started = if instruments.timings { Some(Instant::now()) } else { None };
+    let mut fut = <A as MessageHandler<M>>::handle(act, msg, ctx);
+    let returned = started.map(|started| {
+        let returned = Instant::now();
+        metrics.handler_time(returned - started);
+        returned
+    });
+    drop(guard);
+
+    if let Some(slot) = fut.take_deferred() {
+        // message is stashed, it get handled again after replay
+        if let Some(tx) = tx {
+            *slot.borrow_mut() = Some(Box::new(tx));
+        }
+        return
+    }
+
+    if let Some(res) = fut.take_result() {
+        span.after();
+        metrics.handled(None);
+        if let Some(tx) = tx {
+            tx.respond(res);
+        }
+        return
+    }
+
+    metrics.spawned();
+    ctx.spawn(EnvelopFuture{msg: PhantomData,
+                            fut: fut,
+                            tx: tx,
+                            metrics: metrics,
+                            returned: returned,
+                            span: span});
 }
 
 pub(crate)
@@ -168,6 +302,7 @@ struct Envelope<A, M> where A: Actor + MessageHandler<M> {
     msg: Option<M>,
     act: PhantomData<A>,
     tx: Option<Sender<Result<A::Item, A::Error>>>,
+    trace: Option<TraceContext>,
 }
 
 impl<A, M> Envelope<A, M>
@@ -176,7 +311,19 @@ impl<A, M> Envelope<A, M>
     pub(crate) fn new(msg: Option<M>,
                       tx: Option<Sender<Result<A::Item, A::Error>>>) -> Envelope<A, M>
     {
-        Envelope{msg: msg, tx: tx, act: PhantomData}
+        Envelope{msg: msg, tx: tx, act: PhantomData, trace: trace::current()}
+    }
+
+    /// Take message without handling, used by mock addresses
+    pub(crate) fn take_message(&mut self) -> Option<M> {
+        self.msg.take()
+    }
+
+    /// Send response to the caller
+    pub(crate) fn respond(&mut self, res: Result<A::Item, A::Error>) {
+        if let Some(tx) = self.tx.take() {
+            let _ = tx.send(res);
+        }
     }
 }
 
@@ -190,24 +337,38 @@ impl<A, M> MessageProxy for Envelope<A, M>
     fn handle(&mut self, act: &mut Self::Actor, ctx: &mut Context<A>)
     {
         if let Some(msg) = self.msg.take() {
-            let fut = <Self::Actor as MessageHandler<M>>::handle(act, msg, ctx);
-            let f: EnvelopFuture<Self::Actor, _> = EnvelopFuture {msg: PhantomData,
-                                                                  fut: fut,
-                                                                  tx: self.tx.take()};
-            ctx.spawn(f);
+            handle_message(act, ctx, msg, self.tx.take(), self.trace.take());
         }
     }
+
+    fn message_type(&self) -> &'static str {
+        utils::type_name::<M>()
+    }
+
+    fn message_type_id(&self) -> TypeId {
+        TypeId::of::<M>()
+    }
+
+    fn as_any(&mut self) -> &mut Any {
+        self
+    }
 }
 
-pub(crate) struct EnvelopFuture<A, M> where A: MessageHandler<M>
+struct EnvelopFuture<A, M, R> where A: MessageHandler<M>
 {
     msg: PhantomData<M>,
     fut: MessageFuture<A, M>,
-    tx: Option<Sender<Result<A::Item, A::Error>>>,
+    tx: Option<R>,
+    metrics: Arc<Metrics>,
+    // response future is polled first time after handler returns,
+    // set if timings are enabled
+    returned: Option<Instant>,
+    span: MessageSpan,
 }
 
-impl<A, M> ActorFuture for EnvelopFuture<A, M>
-    where A: Actor + MessageHandler<M>
+impl<A, M, R> ActorFuture for EnvelopFuture<A, M, R>
+    where A: Actor + MessageHandler<M>,
+          R: Responder<A::Item, A::Error>
 {
     type Item = ();
     type Error = ();
@@ -215,17 +376,28 @@ impl<A, M> ActorFuture for EnvelopFuture<A, M>
 
     fn poll(&mut self, act: &mut A, ctx: &mut Context<A>) -> Poll<Self::Item, Self::Error>
     {
-        match self.fut.poll(act, ctx) {
+        // message is accounted in actor's budget when it is received,
+        // response future does not draw from the budget
+        let res = {
+            let _guard = self.span.enter();
+            self.fut.poll(act, ctx)
+        };
+
+        match res {
             Ok(Async::Ready(val)) => {
+                self.span.after();
+                self.metrics.handled(self.returned);
                 if let Some(tx) = self.tx.take() {
-                    let _ = tx.send(Ok(val));
+                    tx.respond(Ok(val));
                 }
                 Ok(Async::Ready(()))
             },
             Ok(Async::NotReady) => Ok(Async::NotReady),
             Err(err) => {
+                self.span.after();
+                self.metrics.handled(self.returned);
                 if let Some(tx) = self.tx.take() {
-                    let _ = tx.send(Err(err));
+                    tx.respond(Err(err));
                 }
                 Err(())
             }
@@ -0,0 +1,310 @@
+//! Actor runtime metrics
+//!
+//! Metrics are collected for each running actor: number of received and
+//! handled messages, current length of actor's mailboxes, time spent in
+//! message handlers, number of spawned response futures and number of restarts
+//! for supervised actors. Actor is registered for metrics collection
+//! once its address is requested or first message is handled. Handler and
+//! response times are measured only after
+//! [`System::enable_timings()`](../struct.System.html#method.enable_timings)
+//! is called.
+//!
+//! Metrics are pulled with [`System::metrics()`](../struct.System.html#method.metrics)
+//! method, it returns snapshot of metrics of all actors running in the system.
+//!
+//! ```rust
+//! # extern crate actix;
+//! # use actix::prelude::*;
+//! struct MyActor;
+//!
+//! impl Actor for MyActor {
+//!     fn started(&mut self, ctx: &mut Context<Self>) {
+//!         let metrics = System::metrics();
+//!         let me = metrics.actor(ctx.id()).unwrap();
+//!         println!("{} received {} messages", me.actor_type, me.received);
+//!         Arbiter::system().send(actix::SystemExit(0));
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let sys = System::new("test".to_owned());
+//!     let _: () = MyActor.start();
+//!     sys.run();
+//! }
+//! ```
+use std::{fmt, thread};
+use std::any::TypeId;
+use std::sync::{Arc, Mutex, Weak};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use actor::Actor;
+use arbiter::Arbiter;
+use context::Context;
+use identity::{ActorId, ActorInfo};
+use system::System;
+use utils;
+
+/// Upper bounds of histogram buckets in microseconds
+const BUCKETS: [u64; 7] = [10, 100, 1_000, 10_000, 100_000, 1_000_000, 10_000_000];
+
+/// Snapshot of metrics of all actors in the system
+#[derive(Clone, Debug)]
+pub struct SystemMetrics {
+    pub actors: Vec<ActorMetrics>,
+}
+
+impl SystemMetrics {
+
+    /// Metrics of specific actor
+    pub fn actor(&self, id: ActorId) -> Option<&ActorMetrics> {
+        self.actors.iter().find(|m| m.id == id)
+    }
+
+    /// Metrics of all actors of specific type
+    pub fn actors_of<A: Actor>(&self) -> Vec<&ActorMetrics> {
+        let type_id = Some(TypeId::of::<A>());
+        self.actors.iter().filter(|m| m.type_id == type_id).collect()
+    }
+}
+
+/// Snapshot of actor's metrics
+#[derive(Clone, Debug)]
+pub struct ActorMetrics {
+    /// Actor's identifier
+    pub id: ActorId,
+    /// Actor's name
+    pub name: Option<String>,
+    /// Type name of the actor, placeholder on rust older than 1.38
+    pub actor_type: &'static str,
+    /// Name of the arbiter's thread actor runs in
+    pub arbiter: String,
+    /// Number of messages passed to message handlers
+    pub received: usize,
+    /// Number of messages with completed response
+    pub handled: usize,
+    /// Number of messages in actor's `Address` mailbox
+    pub mailbox: usize,
+    /// Number of messages in actor's `SyncAddress` mailbox
+    pub sync_mailbox: usize,
+    /// Number of spawned response futures
+    pub spawned: usize,
+    /// Number of restarts of supervised actor
+    pub restarts: usize,
+    /// Time spent in `MessageHandler::handle` method, empty unless
+    /// timings are enabled
+    pub handler_time: Histogram,
+    /// Time from `MessageHandler::handle` return till response future completion,
+    /// messages with immediate result are not observed. Empty unless
+    /// timings are enabled.
+    pub future_time: Histogram,
+    type_id: Option<TypeId>,
+}
+
+/// Snapshot of duration histogram
+#[derive(Clone, Debug)]
+pub struct Histogram {
+    /// Number of observations
+    pub count: usize,
+    /// Sum of all observations
+    pub sum: Duration,
+    /// Cumulative number of observations less than or equal to upper bound
+    pub buckets: Vec<(Duration, usize)>,
+}
+
+/// Source of sync mailbox length
+pub(crate) trait Gauge: Send + Sync {
+    fn value(&self) -> usize;
+}
+
+struct AtomicHistogram {
+    count: AtomicUsize,
+    // sum of observations in microseconds
+    sum: AtomicUsize,
+    buckets: [AtomicUsize; 7],
+}
+
+impl AtomicHistogram {
+    fn new() -> AtomicHistogram {
+        AtomicHistogram {
+            count: AtomicUsize::new(0),
+            sum: AtomicUsize::new(0),
+            buckets: [AtomicUsize::new(0), AtomicUsize::new(0), AtomicUsize::new(0),
+                      AtomicUsize::new(0), AtomicUsize::new(0), AtomicUsize::new(0),
+                      AtomicUsize::new(0)],
+        }
+    }
+
+    fn observe(&self, dur: Duration) {
+        let micros = dur.as_secs() * 1_000_000 + u64::from(dur.subsec_nanos() / 1_000);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum.fetch_add(micros as usize, Ordering::Relaxed);
+        for (idx, bound) in BUCKETS.iter().enumerate() {
+            if micros <= *bound {
+                self.buckets[idx].fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn snapshot(&self) -> Histogram {
+        let sum = self.sum.load(Ordering::Relaxed) as u64;
+        Histogram {
+            count: self.count.load(Ordering::Relaxed),
+            sum: Duration::new(sum / 1_000_000, (sum % 1_000_000) as u32 * 1_000),
+            buckets: BUCKETS.iter().zip(self.buckets.iter())
+                .map(|(bound, cnt)| (
+                    Duration::new(bound / 1_000_000, (bound % 1_000_000) as u32 * 1_000),
+                    cnt.load(Ordering::Relaxed)))
+                .collect(),
+        }
+    }
+}
+
+/// Metrics collector of the actor, shared by actor's mailboxes
+pub(crate) struct Metrics {
+    // identifier and name of the actor
+    actor: Arc<ActorInfo>,
+    // actor type and arbiter, available after registration
+    info: Mutex<Option<(TypeId, &'static str, String)>>,
+    registered: AtomicBool,
+    received: AtomicUsize,
+    handled: AtomicUsize,
+    mailbox: Arc<AtomicUsize>,
+    sync_mailbox: Mutex<Vec<(usize, Weak<Gauge>)>>,
+    spawned: AtomicUsize,
+    restarts: AtomicUsize,
+    handler_time: AtomicHistogram,
+    future_time: AtomicHistogram,
+}
+
+impl Metrics {
+
+    pub fn new(actor: Arc<ActorInfo>) -> Metrics {
+        Metrics {
+            actor: actor,
+            info: Mutex::new(None),
+            registered: AtomicBool::new(false),
+            received: AtomicUsize::new(0),
+            handled: AtomicUsize::new(0),
+            mailbox: Arc::new(AtomicUsize::new(0)),
+            sync_mailbox: Mutex::new(Vec::new()),
+            spawned: AtomicUsize::new(0),
+            restarts: AtomicUsize::new(0),
+            handler_time: AtomicHistogram::new(),
+            future_time: AtomicHistogram::new(),
+        }
+    }
+
+    /// Register metrics in system registry
+    pub fn register<A: Actor>(metrics: &Arc<Metrics>) {
+        // checked for every handled message, swap only unregistered collector
+        if !metrics.registered.load(Ordering::Relaxed) &&
+            !metrics.registered.swap(true, Ordering::Relaxed)
+        {
+            let arbiter = thread::current().name().unwrap_or("unnamed").to_owned();
+            *metrics.info.lock().unwrap() =
+                Some((TypeId::of::<A>(), utils::type_name::<A>(), arbiter));
+            Arbiter::system_registry().register_metrics(Arc::downgrade(metrics));
+        }
+    }
+
+    /// Counter of messages in unsync mailbox
+    pub fn mailbox(&self) -> Arc<AtomicUsize> {
+        Arc::clone(&self.mailbox)
+    }
+
+    /// Add source of sync mailbox length, `key` identifies the mailbox
+    pub fn add_sync_mailbox(&self, key: usize, gauge: Weak<Gauge>) {
+        let mut gauges = self.sync_mailbox.lock().unwrap();
+        gauges.retain(|&(_, ref gauge)| gauge.upgrade().is_some());
+        if gauges.iter().all(|&(k, _)| k != key) {
+            gauges.push((key, gauge));
+        }
+    }
+
+    pub fn received(&self) {
+        self.received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Response is completed, `returned` is set if response future
+    /// has been spawned after handler returned
+    pub fn handled(&self, returned: Option<Instant>) {
+        self.handled.fetch_add(1, Ordering::Relaxed);
+        if let Some(returned) = returned {
+            self.future_time.observe(returned.elapsed());
+        }
+    }
+
+    pub fn handler_time(&self, dur: Duration) {
+        self.handler_time.observe(dur);
+    }
+
+    pub fn spawned(&self) {
+        self.spawned.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn restarted(&self) {
+        self.restarts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn restarts(&self) -> usize {
+        self.restarts.load(Ordering::Relaxed)
+    }
+
+    pub fn snapshot(&self) -> ActorMetrics {
+        let (type_id, actor_type, arbiter) = match *self.info.lock().unwrap() {
+            Some((type_id, actor_type, ref arbiter)) =>
+                (Some(type_id), actor_type, arbiter.clone()),
+            None => (None, "unknown", String::new()),
+        };
+        let sync_mailbox = self.sync_mailbox.lock().unwrap().iter()
+            .filter_map(|&(_, ref gauge)| gauge.upgrade())
+            .map(|gauge| gauge.value())
+            .sum();
+        ActorMetrics {
+            id: self.actor.id(),
+            name: self.actor.name(),
+            actor_type: actor_type,
+            arbiter: arbiter,
+            received: self.received.load(Ordering::Relaxed),
+            handled: self.handled.load(Ordering::Relaxed),
+            mailbox: self.mailbox.load(Ordering::Relaxed),
+            sync_mailbox: sync_mailbox,
+            spawned: self.spawned.load(Ordering::Relaxed),
+            restarts: self.restarts.load(Ordering::Relaxed),
+            handler_time: self.handler_time.snapshot(),
+            future_time: self.future_time.snapshot(),
+            type_id: type_id,
+        }
+    }
+}
+
+impl fmt::Debug for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Metrics({:?})", self.actor)
+    }
+}
+
+impl System {
+
+    /// Snapshot of metrics of all actors running in the system
+    pub fn metrics() -> SystemMetrics {
+        Arbiter::system_registry().metrics()
+    }
+
+    /// Measure time spent in message handlers and response futures.
+    /// Timings are disabled by default, they cost two clock reads per message.
+    pub fn enable_timings() {
+        Arbiter::system_registry().instrumentation().enable_timings()
+    }
+}
+
+impl<A> Context<A> where A: Actor {
+
+    /// Metrics collector of the actor, actor is registered on first call
+    pub(crate) fn metrics(&mut self) -> Arc<Metrics> {
+        let metrics = self.address_cell().unsync_sender().metrics();
+        Metrics::register::<A>(&metrics);
+        metrics
+    }
+}
@@ -8,21 +8,26 @@
 //! use actix::prelude::*;
 //! ```
 
-pub use fut::{self, ActorFuture, WrapFuture, IntoActorFuture};
+pub use fut::{self, ActorFuture, ActorStream, WrapFuture, WrapStream, IntoActorFuture};
 
-pub use actor::{Actor, SupervisedActor, MessageHandler, MessageResponse, StreamHandler};
+pub use actor::{Actor, SupervisedActor, MessageHandler, MessageResponse,
+                StreamHandler, StreamControl, StreamEnd, SinkHandler};
 pub use arbiter::Arbiter;
 pub use address::{Address, SyncAddress, Subscriber, AsyncSubscriber};
 pub use builder::ActorBuilder;
 pub use context::{ActorState, Context, ContextFutureSpawner};
 pub use framed::{ActixFramed, ActixFramedRead, ActixFramedWrite};
 pub use message::{MessageFuture, MessageFutureResult, MessageFutureError, MessageResult};
+pub use queue::Priority;
 pub use system::System;
 pub use supervisor::Supervisor;
 
 pub mod actix {
     pub use actors;
     pub use sink::Sink;
+    pub use identity::ActorId;
+    pub use stash::Stash;
+    pub use retry::Retry;
     pub use utils::Condition;
     pub use system::SystemExit;
     pub use arbiter::{Execute, StartActor, StopArbiter};
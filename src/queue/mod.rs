@@ -1,5 +1,8 @@
 pub mod sync;
 pub mod unsync;
+pub mod priority;
+
+pub use self::priority::Priority;
 
 #[cfg_attr(feature="cargo-clippy", allow(module_inception))]
 mod queue;
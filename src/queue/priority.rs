@@ -0,0 +1,198 @@
+//! Message priority lanes
+//!
+//! Priority is a layer on top of the channel's queue: channel keeps one
+//! queue per lane in [`Lanes`](struct.Lanes.html) and receiver asks
+//! [`Scheduler`](struct.Scheduler.html) which lane to take next message from.
+//!
+//! Actor's mailbox consists of three lanes, `High`, `Normal` and `Low`.
+//! Receiver drains higher lanes first. Each lane counts messages received
+//! from higher lanes since its own last message, once lane waited for
+//! `STARVATION_LIMIT` messages it is checked before higher lanes,
+//! so every lane makes progress under load of higher lanes.
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::collections::VecDeque;
+
+/// Number of messages from higher lanes that lane could wait for.
+pub(crate) const STARVATION_LIMIT: usize = 16;
+
+const LANES: [Priority; 3] = [Priority::High, Priority::Normal, Priority::Low];
+
+/// Message priority
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Priority {
+    /// Control messages
+    High,
+    /// Default priority
+    Normal,
+    /// Bulk messages
+    Low,
+}
+
+impl Default for Priority {
+    fn default() -> Priority {
+        Priority::Normal
+    }
+}
+
+impl Priority {
+    fn index(&self) -> usize {
+        match *self {
+            Priority::High => 0,
+            Priority::Normal => 1,
+            Priority::Low => 2,
+        }
+    }
+}
+
+/// Message lanes, one queue per priority
+#[derive(Debug)]
+pub(crate) struct Lanes<Q> {
+    lanes: [Q; 3],
+}
+
+impl<Q> Lanes<Q> {
+
+    pub fn new<F: Fn() -> Q>(f: F) -> Lanes<Q> {
+        Lanes{lanes: [f(), f(), f()]}
+    }
+
+    pub fn get(&self, lane: Priority) -> &Q {
+        &self.lanes[lane.index()]
+    }
+
+    pub fn get_mut(&mut self, lane: Priority) -> &mut Q {
+        &mut self.lanes[lane.index()]
+    }
+
+    pub fn iter(&self) -> ::std::slice::Iter<Q> {
+        self.lanes.iter()
+    }
+}
+
+/// Lanes scheduler, decides in which order lanes have to be checked.
+#[derive(Debug, Default)]
+pub(crate) struct Scheduler {
+    // number of messages received from higher lanes, per lane
+    waited: [usize; 3],
+}
+
+impl Scheduler {
+
+    /// Order of lanes for next receive operation, starved lanes go first
+    pub fn order(&self) -> [Priority; 3] {
+        let mut order = LANES;
+        let mut idx = 0;
+        for lane in &LANES {
+            if self.waited[lane.index()] >= STARVATION_LIMIT {
+                order[idx] = *lane;
+                idx += 1;
+            }
+        }
+        for lane in &LANES {
+            if self.waited[lane.index()] < STARVATION_LIMIT {
+                order[idx] = *lane;
+                idx += 1;
+            }
+        }
+        order
+    }
+
+    /// Message has been received from `lane`, lower lanes wait one more message
+    pub fn received(&mut self, lane: Priority) {
+        self.waited[lane.index()] = 0;
+        for lower in &LANES[lane.index() + 1..] {
+            self.waited[lower.index()] += 1;
+        }
+    }
+
+    /// Lane is empty, it does not wait for anything
+    pub fn empty(&mut self, lane: Priority) {
+        self.waited[lane.index()] = 0;
+    }
+
+    /// Receive next message. Lanes are checked in scheduler's order,
+    /// `pop` returns message of the lane or `None` if lane is empty.
+    pub fn next<T, F>(&mut self, mut pop: F) -> Option<T>
+        where F: FnMut(Priority) -> Option<T>
+    {
+        for lane in &self.order() {
+            if let Some(msg) = pop(*lane) {
+                self.received(*lane);
+                return Some(msg)
+            }
+            self.empty(*lane);
+        }
+        None
+    }
+}
+
+/// Single threaded priority queue
+#[derive(Debug)]
+pub(crate) struct PriorityQueue<T> {
+    lanes: Lanes<VecDeque<T>>,
+    scheduler: Scheduler,
+    // shared counter of queued messages, used for mailbox metrics
+    depth: Option<Arc<AtomicUsize>>,
+}
+
+impl<T> PriorityQueue<T> {
+
+    pub fn new() -> PriorityQueue<T> {
+        PriorityQueue {
+            lanes: Lanes::new(VecDeque::new),
+            scheduler: Scheduler::default(),
+            depth: None,
+        }
+    }
+
+    /// Set depth counter, queued messages are moved to new counter
+    pub fn set_depth(&mut self, depth: Arc<AtomicUsize>) {
+        let len = self.len();
+        if let Some(ref depth) = self.depth {
+            depth.fetch_sub(len, Ordering::Relaxed);
+        }
+        depth.fetch_add(len, Ordering::Relaxed);
+        self.depth = Some(depth);
+    }
+
+    /// Push message to normal lane
+    pub fn push_back(&mut self, msg: T) {
+        self.push(msg, Priority::Normal)
+    }
+
+    /// Push message to specific lane
+    pub fn push(&mut self, msg: T, priority: Priority) {
+        if let Some(ref depth) = self.depth {
+            depth.fetch_add(1, Ordering::Relaxed);
+        }
+        self.lanes.get_mut(priority).push_back(msg)
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        let lanes = &mut self.lanes;
+        let msg = self.scheduler.next(|lane| lanes.get_mut(lane).pop_front());
+        if msg.is_some() {
+            if let Some(ref depth) = self.depth {
+                depth.fetch_sub(1, Ordering::Relaxed);
+            }
+        }
+        msg
+    }
+
+    pub fn len(&self) -> usize {
+        self.lanes.iter().map(|lane| lane.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lanes.iter().all(|lane| lane.is_empty())
+    }
+}
+
+impl<T> Drop for PriorityQueue<T> {
+    fn drop(&mut self) {
+        if let Some(ref depth) = self.depth {
+            depth.fetch_sub(self.len(), Ordering::Relaxed);
+        }
+    }
+}
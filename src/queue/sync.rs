@@ -1,4 +1,4 @@
-//! This is copy of sync/mpsc/ from https://github.com/alexcrichton/futures-rs
+//! This is based on sync/mpsc/ from https://github.com/alexcrichton/futures-rs
 //!
 //! A multi-producer, single-consumer, futures-aware, FIFO queue with back pressure.
 //!
@@ -68,20 +68,39 @@
 // queue the primary coordination structure and establishes the necessary
 // happens-before semantics required for the acquire / release semantics used
 // by the queue structure.
+//
+// Unlike futures' channel, receiver of unbounded channel does not decrement
+// the message count for every message. Received messages are counted locally
+// and subtracted from the channel state in batches of `RELEASE_BATCH` messages,
+// or when the receiver is about to park. The receiver publishes the number of
+// not yet subtracted messages in `unreleased`, so the channel length is exact
+// and does not lag behind by a batch. Bounded channel releases every received
+// message immediately and unparks a waiting sender, so senders never wait
+// for buffer space that is occupied by already received messages.
+//
+// Receiver wakeups are coalesced. Sender sets `notified` flag after pushing
+// a message and notifies receiver's task only if the flag was not set already.
+// The receiver registers its task and then consumes the flag before parking,
+// so a message is either observed by the receiver or its sender notifies
+// the registered task.
 #![allow(dead_code)]
 
-use std::fmt;
+use std::{cmp, fmt};
 use std::error::Error;
 use std::any::Any;
-use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::{AtomicBool, AtomicUsize};
 use std::sync::atomic::Ordering::{Relaxed, SeqCst};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, Weak};
 use std::thread;
 use std::usize;
 
-use futures::task::{self, Task};
+use futures::task::{self, AtomicTask, Task};
 use futures::{Async, AsyncSink, Poll, StartSend, Sink, Stream};
 use super::queue::{Queue, PopResult};
+use super::priority::{Lanes, Priority, Scheduler};
+use budget::MailboxBudget;
+use identity::Identity;
+use metrics::Gauge;
 
 
 /// The transmission end of a channel which is used to send values.
@@ -120,6 +139,17 @@ impl AssertKinds for UnboundedSender<u32> {}
 #[derive(Debug)]
 pub struct Receiver<T> {
     inner: Arc<Inner<T>>,
+
+    // Lanes scheduler
+    scheduler: Scheduler,
+
+    // Termination message has been received from normal lane,
+    // but high and low lanes still could contain messages.
+    closing: bool,
+
+    // Number of received messages that are not subtracted
+    // from the channel state yet
+    received: usize,
 }
 
 /// The receiving end of a channel which implements the `Stream` trait.
@@ -127,8 +157,11 @@ pub struct Receiver<T> {
 /// This is a concrete implementation of a stream which can be used to represent
 /// a stream of values being computed elsewhere. This is created by the
 /// `unbounded` method.
+///
+/// Receiver draws from actor's message budget, once budget is used up
+/// it yields with `NotReady`, see `budget` module.
 #[derive(Debug)]
-pub struct UnboundedReceiver<T>(Receiver<T>);
+pub struct UnboundedReceiver<T>(Receiver<T>, MailboxBudget);
 
 /// Error type for sending, used when the receiving end of a channel is
 /// dropped
@@ -234,7 +267,6 @@ impl<T> TrySendError<T> {
     }
 }
 
-#[derive(Debug)]
 struct Inner<T> {
     // Max buffer size of the channel. If `None` then the channel is unbounded.
     buffer: Option<usize>,
@@ -243,8 +275,9 @@ struct Inner<T> {
     // channel as well as a flag signalling that the channel is closed.
     state: AtomicUsize,
 
-    // Atomic, FIFO queue used to send messages to the receiver
-    message_queue: Queue<Option<T>>,
+    // Atomic, FIFO queues used to send messages to the receiver, one per
+    // priority lane. Termination message is always sent through normal lane
+    lanes: Lanes<Queue<Option<T>>>,
 
     // Atomic, FIFO queue used to send parked task handles to the receiver.
     parked_queue: Queue<Arc<Mutex<SenderTask>>>,
@@ -252,8 +285,31 @@ struct Inner<T> {
     // Number of senders in existence
     num_senders: AtomicUsize,
 
+    // Identity of the channel
+    identity: Mutex<Identity>,
+
     // Handle to the receiver's task.
-    recv_task: Mutex<ReceiverTask>,
+    recv_task: AtomicTask,
+
+    // Receiver has been notified about new messages since last park
+    notified: AtomicBool,
+
+    // Received messages that are not subtracted from `state` yet,
+    // written by the receiver only
+    unreleased: AtomicUsize,
+
+    // Identity is replaced, receiver has to reload actor's budget
+    identity_changed: AtomicBool,
+}
+
+impl<T> fmt::Debug for Inner<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Inner")
+            .field("buffer", &self.buffer)
+            .field("state", &decode_state(self.state.load(SeqCst)))
+            .field("num_senders", &self.num_senders)
+            .finish()
+    }
 }
 
 // Struct representation of `Inner::state`.
@@ -266,12 +322,6 @@ struct State {
     num_messages: usize,
 }
 
-#[derive(Debug)]
-struct ReceiverTask {
-    unparked: bool,
-    task: Option<Task>,
-}
-
 // Returned from Receiver::try_park()
 enum TryPark {
     Parked,
@@ -293,6 +343,9 @@ const MAX_CAPACITY: usize = !(OPEN_MASK);
 // a channel. This is because each sender gets a guaranteed slot.
 const MAX_BUFFER: usize = MAX_CAPACITY >> 1;
 
+// Maximum number of received messages before receiver updates channel state
+const RELEASE_BATCH: usize = 32;
+
 // Sent to the consumer to wake up blocked producers
 #[derive(Debug)]
 struct SenderTask {
@@ -351,20 +404,21 @@ pub fn channel<T>(buffer: usize) -> (Sender<T>, Receiver<T>) {
 /// process to run out of memory. In this case, the process will be aborted.
 pub fn unbounded<T>() -> (UnboundedSender<T>, UnboundedReceiver<T>) {
     let (tx, rx) = channel2(None);
-    (UnboundedSender(tx), UnboundedReceiver(rx))
+    (UnboundedSender(tx), UnboundedReceiver(rx, MailboxBudget::default()))
 }
 
 fn channel2<T>(buffer: Option<usize>) -> (Sender<T>, Receiver<T>) {
     let inner = Arc::new(Inner {
         buffer: buffer,
         state: AtomicUsize::new(INIT_STATE),
-        message_queue: Queue::new(),
+        lanes: Lanes::new(Queue::new),
         parked_queue: Queue::new(),
         num_senders: AtomicUsize::new(1),
-        recv_task: Mutex::new(ReceiverTask {
-            unparked: false,
-            task: None,
-        }),
+        identity: Mutex::new(Identity::new()),
+        recv_task: AtomicTask::new(),
+        notified: AtomicBool::new(false),
+        unreleased: AtomicUsize::new(0),
+        identity_changed: AtomicBool::new(false),
     });
 
     let tx = Sender {
@@ -375,6 +429,9 @@ fn channel2<T>(buffer: Option<usize>) -> (Sender<T>, Receiver<T>) {
 
     let rx = Receiver {
         inner: inner,
+        scheduler: Scheduler::default(),
+        closing: false,
+        received: 0,
     };
 
     (tx, rx)
@@ -464,12 +521,18 @@ impl<T> Sender<T> {
     //
     // To be called from unbounded sender.
     fn do_send_nb(&self, msg: T) -> Result<(), SendError<T>> {
+        self.do_send_nb_priority(msg, Priority::Normal)
+    }
+
+    // Do the send to specific lane without parking current task.
+    fn do_send_nb_priority(&self, msg: T, priority: Priority) -> Result<(), SendError<T>> {
         match self.inc_num_messages(false) {
             Some(park_self) => assert!(!park_self),
             None => return Err(SendError(msg)),
         };
 
-        self.queue_push_and_signal(Some(msg));
+        self.inner.lanes.get(priority).push(Some(msg));
+        self.signal();
 
         Ok(())
     }
@@ -477,7 +540,7 @@ impl<T> Sender<T> {
     // Push message to the queue and signal to the receiver
     fn queue_push_and_signal(&self, msg: Option<T>) {
         // Push the message onto the message queue
-        self.inner.message_queue.push(msg);
+        self.inner.lanes.get(Priority::Normal).push(msg);
 
         // Signal to the receiver that a message has been enqueued. If the
         // receiver is parked, this will unpark the task.
@@ -485,8 +548,45 @@ impl<T> Sender<T> {
     }
 
     // Increment the number of queued messages. Returns if the sender should
-    // block.
+    // block, `None` is returned if the channel is closed.
     fn inc_num_messages(&self, close: bool) -> Option<bool> {
+        if close {
+            return self.inc_num_messages_and_close()
+        }
+
+        // Single atomic update per message. Count is incremented even
+        // if the channel is closed, in that case increment is reverted.
+        let state = decode_state(self.inner.state.fetch_add(1, SeqCst));
+
+        // The receiver end closed the channel.
+        if !state.is_open {
+            self.inner.state.fetch_sub(1, SeqCst);
+
+            // receiver could observe reverted increment and wait
+            // for the message, wake it up
+            self.signal();
+            return None
+        }
+
+        // Count must never reach the `is_open` bit. Senders are bounded
+        // by `max_senders()`, so concurrent increments can not overflow
+        // before this check reverts them.
+        if state.num_messages + 1 >= MAX_CAPACITY {
+            self.inner.state.fetch_sub(1, SeqCst);
+            panic!("buffer space exhausted; sending this messages would overflow the state");
+        }
+
+        // Block if the current number of pending messages has exceeded
+        // the configured buffer size
+        Some(match self.inner.buffer {
+            Some(buffer) => state.num_messages + 1 > buffer,
+            None => false,
+        })
+    }
+
+    // Increment the number of queued messages for termination message
+    // and close the channel.
+    fn inc_num_messages_and_close(&self) -> Option<bool> {
         let mut curr = self.inner.state.load(SeqCst);
 
         loop {
@@ -506,53 +606,21 @@ impl<T> Sender<T> {
             state.num_messages += 1;
 
             // The channel is closed by all sender handles being dropped.
-            if close {
-                state.is_open = false;
-            }
+            state.is_open = false;
 
             let next = encode_state(&state);
             match self.inner.state.compare_exchange(curr, next, SeqCst, SeqCst) {
-                Ok(_) => {
-                    // Block if the current number of pending messages has exceeded
-                    // the configured buffer size
-                    let park_self = match self.inner.buffer {
-                        Some(buffer) => state.num_messages > buffer,
-                        None => false,
-                    };
-
-                    return Some(park_self)
-                }
+                Ok(_) => return Some(false),
                 Err(actual) => curr = actual,
             }
         }
     }
 
-    // Signal to the receiver task that a message has been enqueued
+    // Signal to the receiver task that a message has been enqueued. Only
+    // first message after receiver parks notifies receiver's task.
     fn signal(&self) {
-        // TODO
-        // This logic can probably be improved by guarding the lock with an
-        // atomic.
-        //
-        // Do this step first so that the lock is dropped when
-        // `unpark` is called
-        let task = {
-            let mut recv_task = self.inner.recv_task.lock().unwrap();
-
-            // If the receiver has already been unparked, then there is nothing
-            // more to do
-            if recv_task.unparked {
-                return;
-            }
-
-            // Setting this flag enables the receiving end to detect that
-            // an unpark event happened in order to avoid unnecessarily
-            // parking.
-            recv_task.unparked = true;
-            recv_task.task.take()
-        };
-
-        if let Some(task) = task {
-            task.notify();
+        if !self.inner.notified.swap(true, SeqCst) {
+            self.inner.recv_task.notify();
         }
     }
 
@@ -677,6 +745,42 @@ impl<T> UnboundedSender<T> {
     pub fn unbounded_send(&self, msg: T) -> Result<(), SendError<T>> {
         self.0.do_send_nb(msg)
     }
+
+    /// Sends the provided message along this channel with specific priority.
+    ///
+    /// Messages from higher priority lanes are received first.
+    pub fn unbounded_send_priority(&self, msg: T, priority: Priority)
+                                   -> Result<(), SendError<T>>
+    {
+        self.0.do_send_nb_priority(msg, priority)
+    }
+
+    /// Number of messages in the channel
+    pub fn len(&self) -> usize {
+        self.0.inner.len()
+    }
+
+    /// Check if the channel is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Check if receiving half is closed or dropped
+    pub fn is_closed(&self) -> bool {
+        !decode_state(self.0.inner.state.load(SeqCst)).is_open
+    }
+
+    /// Identity of the channel
+    pub fn identity(&self) -> Identity {
+        self.0.inner.identity.lock().unwrap().clone()
+    }
+}
+
+impl<T: Send + 'static> UnboundedSender<T> {
+    /// Replace channel identity
+    pub fn set_identity(&self, identity: Identity) {
+        set_identity(&self.0.inner, identity)
+    }
 }
 
 impl<T> Sink for UnboundedSender<T> {
@@ -765,6 +869,32 @@ impl<T> Drop for Sender<T> {
     }
 }
 
+// Pop message from the queue.
+//
+// Only one thread can pop from the queue.
+unsafe fn pop_message<T>(queue: &Queue<T>) -> Option<T> {
+    loop {
+        match queue.pop() {
+            PopResult::Data(msg) => return Some(msg),
+            PopResult::Empty => return None,
+            PopResult::Inconsistent => {
+                // Inconsistent means that there will be a message to pop
+                // in a short time. This branch can only be reached if
+                // values are being produced from another thread, so there
+                // are a few ways that we can deal with this:
+                //
+                // 1) Spin
+                // 2) thread::yield_now()
+                // 3) task::current().unwrap() & return NotReady
+                //
+                // For now, thread::yield_now() is used, but it would
+                // probably be better to spin a few times then yield.
+                thread::yield_now();
+            }
+        }
+    }
+}
+
 /*
  *
  * ===== impl Receiver =====
@@ -841,45 +971,49 @@ impl<T> Receiver<T> {
     }
 
     fn next_message(&mut self) -> Async<Option<T>> {
-        // Pop off a message
         loop {
-            match unsafe { self.inner.message_queue.pop() } {
-                PopResult::Data(msg) => {
-                    return Async::Ready(msg);
-                }
-                PopResult::Empty => {
-                    // The queue is empty, return NotReady
-                    return Async::NotReady;
-                }
-                PopResult::Inconsistent => {
-                    // Inconsistent means that there will be a message to pop
-                    // in a short time. This branch can only be reached if
-                    // values are being produced from another thread, so there
-                    // are a few ways that we can deal with this:
-                    //
-                    // 1) Spin
-                    // 2) thread::yield_now()
-                    // 3) task::current().unwrap() & return NotReady
-                    //
-                    // For now, thread::yield_now() is used, but it would
-                    // probably be better to spin a few times then yield.
-                    thread::yield_now();
-                }
+            let closing = self.closing;
+
+            let msg = {
+                let inner = &self.inner;
+                let terminated = &mut self.closing;
+                self.scheduler.next(|lane| match unsafe { pop_message(inner.lanes.get(lane)) } {
+                    Some(Some(msg)) => Some(msg),
+                    Some(None) => {
+                        *terminated = true;
+                        None
+                    }
+                    None => None,
+                })
+            };
+            if let Some(msg) = msg {
+                return Async::Ready(Some(msg))
             }
+
+            if closing {
+                // all lanes are drained, report termination
+                self.closing = false;
+                return Async::Ready(None)
+            } else if !self.closing {
+                return Async::NotReady
+            }
+            // termination message just received, check other lanes once more,
+            // messages could be pushed to lanes that already have been checked
         }
     }
 
-    // Unpark a single task handle if there is one pending in the parked queue
-    fn unpark_one(&mut self) {
+    // Unpark a single task handle if there is one pending in the parked queue.
+    // Returns `false` if parked queue is empty.
+    fn unpark_one(&mut self) -> bool {
         loop {
             match unsafe { self.inner.parked_queue.pop() } {
                 PopResult::Data(task) => {
                     task.lock().unwrap().notify();
-                    return;
+                    return true;
                 }
                 PopResult::Empty => {
                     // Queue empty, no task to wake up.
-                    return;
+                    return false;
                 }
                 PopResult::Inconsistent => {
                     // Same as above
@@ -889,8 +1023,51 @@ impl<T> Receiver<T> {
         }
     }
 
+    // Subtract received messages from the channel state and unpark
+    // senders that are waiting for buffer space.
+    fn release(&mut self) {
+        if self.received == 0 {
+            return
+        }
+
+        // each received message frees one slot in the buffer,
+        // unpark senders before decrementing number of messages
+        if self.inner.buffer.is_some() {
+            for _ in 0..self.received {
+                if !self.unpark_one() {
+                    break
+                }
+            }
+        }
+
+        // reset published counter first, concurrent `len()` could
+        // overestimate the length for a moment but never underestimate it
+        self.inner.unreleased.store(0, SeqCst);
+        self.inner.state.fetch_sub(self.received, SeqCst);
+        self.received = 0;
+    }
+
+    // Record received messages, state of unbounded channel is updated in batches
+    fn record_batch(&mut self, num: usize) {
+        self.received += num;
+        if self.received >= RELEASE_BATCH || self.inner.buffer.is_some() {
+            self.release();
+        } else {
+            self.inner.unreleased.store(self.received, SeqCst);
+        }
+    }
+
+    // Record received message
+    fn record(&mut self) {
+        self.record_batch(1)
+    }
+
     // Try to park the receiver task
-    fn try_park(&self) -> TryPark {
+    fn try_park(&mut self) -> TryPark {
+        // channel is empty, all received messages have to be released,
+        // otherwise senders could wait for buffer space forever
+        self.release();
+
         let curr = self.inner.state.load(SeqCst);
         let state = decode_state(curr);
 
@@ -899,32 +1076,70 @@ impl<T> Receiver<T> {
             return TryPark::Closed;
         }
 
-        // First, track the task in the `recv_task` slot
-        let mut recv_task = self.inner.recv_task.lock().unwrap();
+        // First, track the task in the `recv_task` slot, then consume
+        // notification. Sender that pushed message after this point
+        // notifies registered task.
+        self.inner.recv_task.register();
 
-        if recv_task.unparked {
-            // Consume the `unpark` signal without actually parking
-            recv_task.unparked = false;
+        if self.inner.notified.swap(false, SeqCst) {
             return TryPark::NotEmpty;
         }
 
-        recv_task.task = Some(task::current());
         TryPark::Parked
     }
 
-    fn dec_num_messages(&self) {
-        let mut curr = self.inner.state.load(SeqCst);
-
+    /// Receive buffered message without parking current task, used for
+    /// draining closed channel.
+    pub fn try_recv(&mut self) -> Option<T> {
         loop {
-            let mut state = decode_state(curr);
+            match self.next_message() {
+                Async::Ready(Some(msg)) => {
+                    self.record();
+                    return Some(msg)
+                }
+                // termination message is counted as well, check remaining lanes
+                Async::Ready(None) => {
+                    self.record();
+                    continue
+                }
+                Async::NotReady => return None,
+            }
+        }
+    }
 
-            state.num_messages -= 1;
+    /// Receive up to `max` messages into `buf` with single state update.
+    ///
+    /// Returns number of received messages, `Async::Ready(None)` is returned
+    /// if channel is closed and all messages are received.
+    pub fn poll_batch(&mut self, buf: &mut Vec<T>, max: usize) -> Poll<Option<usize>, ()> {
+        let mut num = 0;
+        while num < max {
+            match self.next_message() {
+                Async::Ready(Some(msg)) => {
+                    buf.push(msg);
+                    num += 1;
+                }
+                Async::Ready(None) => {
+                    // termination message, next poll reports closed channel
+                    self.record();
+                    break
+                }
+                Async::NotReady => break,
+            }
+        }
 
-            let next = encode_state(&state);
-            match self.inner.state.compare_exchange(curr, next, SeqCst, SeqCst) {
-                Ok(_) => break,
-                Err(actual) => curr = actual,
+        if num > 0 {
+            self.record_batch(num);
+            return Ok(Async::Ready(Some(num)))
+        }
+
+        match self.poll()? {
+            Async::Ready(Some(msg)) => {
+                buf.push(msg);
+                Ok(Async::Ready(Some(1)))
             }
+            Async::Ready(None) => Ok(Async::Ready(None)),
+            Async::NotReady => Ok(Async::NotReady),
         }
     }
 }
@@ -963,12 +1178,9 @@ impl<T> Stream for Receiver<T> {
                 }
             };
 
-            // If there are any parked task handles in the parked queue, pop
-            // one and unpark it.
-            self.unpark_one();
-
-            // Decrement number of messages
-            self.dec_num_messages();
+            // Count received message, number of messages and parked senders
+            // are updated once per batch
+            self.record();
 
             // Return the message
             return Ok(Async::Ready(msg));
@@ -1002,6 +1214,55 @@ impl<T> UnboundedReceiver<T> {
     pub fn close(&mut self) {
         self.0.close();
     }
+
+    /// Receive buffered message without parking current task
+    pub fn try_recv(&mut self) -> Option<T> {
+        self.0.try_recv()
+    }
+
+    /// Identity of the channel
+    pub fn identity(&self) -> Identity {
+        self.0.inner.identity.lock().unwrap().clone()
+    }
+}
+
+impl<T: Send + 'static> UnboundedReceiver<T> {
+    /// Replace channel identity
+    pub fn set_identity(&self, identity: Identity) {
+        set_identity(&self.0.inner, identity)
+    }
+}
+
+impl<T> UnboundedReceiver<T> {
+    /// Deliver all messages regardless of actor's message budget
+    pub(crate) fn unbudgeted(mut self) -> Self {
+        self.1.disable();
+        self
+    }
+
+    // Number of messages receiver could deliver within actor's budget
+    fn available(&mut self) -> usize {
+        let inner = &self.0.inner;
+        if inner.identity_changed.load(SeqCst) && inner.identity_changed.swap(false, SeqCst) {
+            self.1.reload();
+        }
+        self.1.available(|| Arc::clone(&inner.identity.lock().unwrap().budget))
+    }
+
+    /// Receive up to `max` messages into `buf`, batch is limited by
+    /// actor's message budget
+    pub fn poll_batch(&mut self, buf: &mut Vec<T>, max: usize) -> Poll<Option<usize>, ()> {
+        let max = cmp::min(max, self.available());
+        if max == 0 {
+            return Ok(Async::NotReady)
+        }
+
+        let res = self.0.poll_batch(buf, max);
+        if let Ok(Async::Ready(Some(num))) = res {
+            self.1.consume(num);
+        }
+        res
+    }
 }
 
 impl<T> Stream for UnboundedReceiver<T> {
@@ -1009,7 +1270,15 @@ impl<T> Stream for UnboundedReceiver<T> {
     type Error = ();
 
     fn poll(&mut self) -> Poll<Option<T>, ()> {
-        self.0.poll()
+        if self.available() == 0 {
+            return Ok(Async::NotReady)
+        }
+
+        let res = self.0.poll();
+        if let Ok(Async::Ready(Some(_))) = res {
+            self.1.consume(1);
+        }
+        res
     }
 }
 
@@ -1021,6 +1290,13 @@ impl<T> Stream for UnboundedReceiver<T> {
  */
 
 impl<T> Inner<T> {
+    // Number of messages in the channel, excluding received messages
+    // that are not subtracted from the state yet
+    fn len(&self) -> usize {
+        let num = decode_state(self.state.load(SeqCst)).num_messages;
+        num.saturating_sub(self.unreleased.load(SeqCst))
+    }
+
     // The return value is such that the total number of messages that can be
     // enqueued into the channel will never exceed MAX_CAPACITY
     fn max_senders(&self) -> usize {
@@ -1034,6 +1310,20 @@ impl<T> Inner<T> {
 unsafe impl<T: Send> Send for Inner<T> {}
 unsafe impl<T: Send> Sync for Inner<T> {}
 
+impl<T: Send> Gauge for Inner<T> {
+    fn value(&self) -> usize {
+        self.len()
+    }
+}
+
+fn set_identity<T: Send + 'static>(inner: &Arc<Inner<T>>, identity: Identity) {
+    // channel's length is reported as sync mailbox length of the actor
+    let gauge: Weak<Gauge> = Arc::downgrade(inner);
+    identity.metrics.add_sync_mailbox(&**inner as *const Inner<T> as usize, gauge);
+    *inner.identity.lock().unwrap() = identity;
+    inner.identity_changed.store(true, SeqCst);
+}
+
 /*
  *
  * ===== Helpers =====
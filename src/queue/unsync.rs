@@ -14,10 +14,16 @@ use std::error::Error;
 use std::fmt;
 use std::mem;
 use std::rc::{Rc, Weak};
+use std::sync::Arc;
 
 use futures::task::{self, Task};
 use futures::{Async, AsyncSink, Poll, StartSend, Sink, Stream};
 
+use super::priority::{Priority, PriorityQueue};
+use budget::{MailboxBudget, MessageBudget};
+use identity::{ActorId, ActorInfo, Identity};
+use metrics::Metrics;
+
 /// Creates a bounded in-memory channel with buffered storage.
 ///
 /// This method creates concrete implementations of the `Stream` and `Sink`
@@ -29,24 +35,30 @@ pub fn channel<T>(buffer: usize) -> Receiver<T> {
 }
 
 fn channel_<T>(buffer: Option<usize>) -> Receiver<T> {
+    let identity = Identity::new();
+    let mut queue = PriorityQueue::new();
+    queue.set_depth(identity.metrics.mailbox());
+
     let shared = Rc::new(RefCell::new(Shared {
-        buffer: VecDeque::new(),
+        buffer: queue,
         capacity: buffer,
         blocked_senders: VecDeque::new(),
         blocked_recv: None,
         sender_count: 0,
+        identity: identity,
     }));
     Receiver { state: State::Open(shared) }
 }
 
 #[derive(Debug)]
 struct Shared<T> {
-    buffer: VecDeque<T>,
+    buffer: PriorityQueue<T>,
     capacity: Option<usize>,
     blocked_senders: VecDeque<Task>,
     blocked_recv: Option<Task>,
     // TODO: Redundant to Rc::weak_count; use that if/when stabilized
     sender_count: usize,
+    identity: Identity,
 }
 
 /// The transmission end of a channel.
@@ -55,6 +67,8 @@ struct Shared<T> {
 #[derive(Debug)]
 pub struct Sender<T> {
     shared: Weak<RefCell<Shared<T>>>,
+    // identifier of the channel, available after receiver is gone
+    info: Arc<ActorInfo>,
 }
 
 impl<T> Sender<T> {
@@ -84,7 +98,7 @@ impl<T> Sender<T> {
 
 impl<T> Clone for Sender<T> {
     fn clone(&self) -> Self {
-        let result = Sender { shared: Weak::clone(&self.shared) };
+        let result = Sender { shared: Weak::clone(&self.shared), info: Arc::clone(&self.info) };
         if let Some(shared) = self.shared.upgrade() {
             shared.borrow_mut().sender_count += 1;
         }
@@ -140,7 +154,7 @@ pub struct Receiver<T> {
 #[derive(Debug)]
 enum State<T> {
     Open(Rc<RefCell<Shared<T>>>),
-    Closed(VecDeque<T>),
+    Closed(PriorityQueue<T>, Identity),
 }
 
 impl<T> Receiver<T> {
@@ -150,7 +164,7 @@ impl<T> Receiver<T> {
             State::Open(ref state) => {
                 state.borrow().sender_count != 0
             }
-            State::Closed(ref deque) => {
+            State::Closed(ref deque, _) => {
                 !deque.is_empty()
             }
         }
@@ -163,25 +177,28 @@ impl<T> Receiver<T> {
     pub fn sender(&mut self) -> Sender<T> {
         let (sender, items) = match self.state {
             State::Open(ref state) => {
-                let sender = Sender { shared: Rc::downgrade(state) };
+                let info = Arc::clone(&state.borrow().identity.info);
+                let sender = Sender { shared: Rc::downgrade(state), info: info };
                 state.borrow_mut().sender_count += 1;
                 (Some(sender), None)
             }
-            State::Closed(ref mut buf) => {
-                let items = mem::replace(buf, VecDeque::new());
-                (None, Some(items))
+            State::Closed(ref mut buf, ref identity) => {
+                let items = mem::replace(buf, PriorityQueue::new());
+                (None, Some((items, identity.clone())))
             }
         };
 
-        if let Some(items) = items {
+        if let Some((items, identity)) = items {
+            let info = Arc::clone(&identity.info);
             let shared = Rc::new(RefCell::new(Shared {
                 buffer: items,
                 capacity: None,
                 blocked_senders: VecDeque::new(),
                 blocked_recv: None,
                 sender_count: 1,
+                identity: identity,
             }));
-            let sender = Sender { shared: Rc::downgrade(&shared) };
+            let sender = Sender { shared: Rc::downgrade(&shared), info: info };
             self.state = State::Open(shared);
             sender
         } else {
@@ -194,16 +211,16 @@ impl<T> Receiver<T> {
     /// This prevents any further messages from being sent on the channel while
     /// still enabling the receiver to drain messages that are buffered.
     pub fn close(&mut self) {
-        let (blockers, items) = match self.state {
+        let (blockers, items, identity) = match self.state {
             State::Open(ref state) => {
                 let mut state = state.borrow_mut();
-                let items = mem::replace(&mut state.buffer, VecDeque::new());
+                let items = mem::replace(&mut state.buffer, PriorityQueue::new());
                 let blockers = mem::replace(&mut state.blocked_senders, VecDeque::new());
-                (blockers, items)
+                (blockers, items, state.identity.clone())
             }
-            State::Closed(_) => return,
+            State::Closed(..) => return,
         };
-        self.state = State::Closed(items);
+        self.state = State::Closed(items, identity);
         for task in blockers {
             task.notify();
         }
@@ -212,10 +229,18 @@ impl<T> Receiver<T> {
     /// Check if the receiving half is closed
     pub fn is_closed(&self) -> bool {
         match self.state {
-            State::Closed(_) => true,
+            State::Closed(..) => true,
             _ => false
         }
     }
+
+    /// Receive buffered message without registering current task
+    pub fn try_recv(&mut self) -> Option<T> {
+        match self.state {
+            State::Open(ref state) => state.borrow_mut().buffer.pop_front(),
+            State::Closed(ref mut items, _) => items.pop_front(),
+        }
+    }
 }
 
 impl<T> Stream for Receiver<T> {
@@ -225,7 +250,7 @@ impl<T> Stream for Receiver<T> {
     fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
         let me = match self.state {
             State::Open(ref mut me) => me,
-            State::Closed(ref mut items) => {
+            State::Closed(ref mut items, _) => {
                 return Ok(Async::Ready(items.pop_front()))
             }
         };
@@ -318,12 +343,74 @@ impl<T> UnboundedSender<T> {
     /// by ensuring the return type reflects that the channel is always ready to
     /// receive messages.
     pub fn unbounded_send(&self, msg: T) -> Result<(), SendError<T>> {
+        self.unbounded_send_priority(msg, Priority::Normal)
+    }
+
+    /// Check if receiving half is dropped or closed
+    pub fn is_closed(&self) -> bool {
+        self.0.shared.upgrade().is_none()
+    }
+
+    /// Channel identifier
+    pub fn id(&self) -> ActorId {
+        self.0.info.id()
+    }
+
+    /// Identity of the channel. Name is not available
+    /// if receiving half is dropped.
+    pub fn identity(&self) -> Identity {
+        match self.0.shared.upgrade() {
+            Some(shared) => shared.borrow().identity.clone(),
+            None => Identity::with_info(Arc::clone(&self.0.info)),
+        }
+    }
+
+    /// Metrics collector of the channel
+    pub fn metrics(&self) -> Arc<Metrics> {
+        match self.0.shared.upgrade() {
+            Some(shared) => Arc::clone(&shared.borrow().identity.metrics),
+            None => Arc::new(Metrics::new(Arc::clone(&self.0.info))),
+        }
+    }
+
+    /// Message budget of the channel's actor
+    pub(crate) fn budget(&self) -> Arc<MessageBudget> {
+        match self.0.shared.upgrade() {
+            Some(shared) => Arc::clone(&shared.borrow().identity.budget),
+            None => Arc::new(MessageBudget::default()),
+        }
+    }
+
+    /// Set channel name
+    pub fn set_name(&self, name: String) {
+        if let Some(shared) = self.0.shared.upgrade() {
+            shared.borrow().identity.set_name(name);
+        }
+    }
+
+    /// Replace channel identity. Clones of this sender that were
+    /// created before this call keep old identifier.
+    pub fn set_identity(&mut self, identity: Identity) {
+        if let Some(shared) = self.0.shared.upgrade() {
+            self.0.info = Arc::clone(&identity.info);
+            let mut shared = shared.borrow_mut();
+            shared.buffer.set_depth(identity.metrics.mailbox());
+            shared.identity = identity;
+        }
+    }
+
+    /// Sends the provided message along this channel with specific priority.
+    ///
+    /// Messages from higher priority lanes are received first.
+    pub fn unbounded_send_priority(&self, msg: T, priority: Priority)
+                                   -> Result<(), SendError<T>>
+    {
         let shared = match self.0.shared.upgrade() {
             Some(shared) => shared,
             None => return Err(SendError(msg)),
         };
         let mut shared = shared.borrow_mut();
-        shared.buffer.push_back(msg);
+        shared.buffer.push(msg, priority);
         if let Some(task) = shared.blocked_recv.take() {
             drop(shared);
             task.notify();
@@ -335,8 +422,11 @@ impl<T> UnboundedSender<T> {
 /// The receiving end of an unbounded channel.
 ///
 /// This is created by the `unbounded` function.
+///
+/// Receiver draws from actor's message budget, once budget is used up
+/// it yields with `NotReady`, see `budget` module.
 #[derive(Debug)]
-pub struct UnboundedReceiver<T>(Receiver<T>);
+pub struct UnboundedReceiver<T>(Receiver<T>, MailboxBudget);
 
 impl<T> UnboundedReceiver<T> {
     /// Check if receiver connected to senders
@@ -361,6 +451,43 @@ impl<T> UnboundedReceiver<T> {
     pub fn is_closed(&self) -> bool {
         self.0.is_closed()
     }
+
+    /// Receive buffered message without registering current task
+    pub fn try_recv(&mut self) -> Option<T> {
+        self.0.try_recv()
+    }
+
+    /// Identity of the channel
+    pub fn identity(&self) -> Identity {
+        match self.0.state {
+            State::Open(ref state) => state.borrow().identity.clone(),
+            State::Closed(_, ref identity) => identity.clone(),
+        }
+    }
+
+    /// Replace channel identity. Existing senders keep old identifier.
+    pub fn set_identity(&mut self, identity: Identity) {
+        self.1.reload();
+        match self.0.state {
+            State::Open(ref state) => {
+                let mut state = state.borrow_mut();
+                state.buffer.set_depth(identity.metrics.mailbox());
+                state.identity = identity;
+            }
+            State::Closed(ref mut items, ref mut ident) => {
+                items.set_depth(identity.metrics.mailbox());
+                *ident = identity;
+            }
+        }
+    }
+}
+
+impl<T> UnboundedReceiver<T> {
+    /// Deliver all messages regardless of actor's message budget
+    pub(crate) fn unbudgeted(mut self) -> Self {
+        self.1.disable();
+        self
+    }
 }
 
 impl<T> Stream for UnboundedReceiver<T> {
@@ -368,7 +495,22 @@ impl<T> Stream for UnboundedReceiver<T> {
     type Error = ();
 
     fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
-        self.0.poll()
+        let available = {
+            let state = &self.0.state;
+            self.1.available(|| match *state {
+                State::Open(ref state) => Arc::clone(&state.borrow().identity.budget),
+                State::Closed(_, ref identity) => Arc::clone(&identity.budget),
+            })
+        };
+        if available == 0 {
+            return Ok(Async::NotReady)
+        }
+
+        let res = self.0.poll();
+        if let Ok(Async::Ready(Some(_))) = res {
+            self.1.consume(1);
+        }
+        res
     }
 }
 
@@ -376,7 +518,7 @@ impl<T> Stream for UnboundedReceiver<T> {
 ///
 /// Identical semantics to `channel`, except with no limit to buffer size.
 pub fn unbounded<T>() -> UnboundedReceiver<T> {
-    UnboundedReceiver(channel_(None))
+    UnboundedReceiver(channel_(None), MailboxBudget::default())
 }
 
 /// Error type for sending, used when the receiving end of a channel is
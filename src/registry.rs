@@ -1,11 +1,14 @@
 use std::any::{Any, TypeId};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, Weak};
 use std::cell::RefCell;
 use std::collections::HashMap;
 
 use actor::Actor;
 use builder::ActorBuilder;
 use address::{Address, SyncAddress};
+use metrics::{Metrics, SystemMetrics};
+use instrument::Instrumentation;
+use trace::Interceptor;
 
 /// Per type actors registry
 ///
@@ -93,6 +96,7 @@ impl Registry {
             }
         }
         let addr: Address<_> = A::run();
+        debug!("Started registry actor {:?}", addr);
         self.registry.borrow_mut().insert(id, Box::new(addr.clone()));
         addr
     }
@@ -114,6 +118,7 @@ impl Registry {
     pub fn register<A: Actor>(&self, addr: Address<A>) -> Result<(), Address<A>> {
         let id = TypeId::of::<A>();
         if self.registry.borrow().get(&id).is_some() {
+            debug!("Actor of the same type is already registered, {:?} is rejected", addr);
             return Err(addr)
         }
         self.registry.borrow_mut().insert(id, Box::new(addr));
@@ -128,13 +133,17 @@ impl Registry {
 pub struct SystemRegistry {
     #[cfg_attr(feature="cargo-clippy", allow(type_complexity))]
     registry: Arc<Mutex<RefCell<HashMap<TypeId, Box<Any>>>>>,
+    metrics: Arc<Mutex<Vec<Weak<Metrics>>>>,
+    instrumentation: Arc<Instrumentation>,
 }
 
 unsafe impl Send for SystemRegistry {}
 
 impl SystemRegistry {
     pub(crate) fn new() -> Self {
-        SystemRegistry{registry: Arc::new(Mutex::new(RefCell::new(HashMap::new())))}
+        SystemRegistry{registry: Arc::new(Mutex::new(RefCell::new(HashMap::new()))),
+                       metrics: Arc::new(Mutex::new(Vec::new())),
+                       instrumentation: Arc::new(Instrumentation::new())}
     }
 
     /// Query registry for the address of specific actor.
@@ -157,6 +166,7 @@ impl SystemRegistry {
         if let Ok(hm) = self.registry.lock() {
             let id = TypeId::of::<A>();
             if hm.borrow().get(&id).is_some() {
+                debug!("Actor of the same type is already registered, {:?} is rejected", addr);
                 return Err(addr)
             }
             hm.borrow_mut().insert(id, Box::new(addr));
@@ -165,10 +175,40 @@ impl SystemRegistry {
             panic!("Mutex is poisoned");
         }
     }
+
+    /// Register actor's metrics collector
+    pub(crate) fn register_metrics(&self, metrics: Weak<Metrics>) {
+        if let Ok(mut collectors) = self.metrics.lock() {
+            // drop collectors of stopped actors
+            collectors.retain(|m| m.upgrade().is_some());
+            collectors.push(metrics);
+        }
+    }
+
+    /// Snapshot of metrics of all running actors
+    pub(crate) fn metrics(&self) -> SystemMetrics {
+        let collectors: Vec<_> = match self.metrics.lock() {
+            Ok(collectors) => collectors.iter().filter_map(|m| m.upgrade()).collect(),
+            Err(_) => panic!("Mutex is poisoned"),
+        };
+        SystemMetrics{actors: collectors.iter().map(|m| m.snapshot()).collect()}
+    }
+
+    /// Register message interceptor
+    pub(crate) fn add_interceptor(&self, interceptor: Arc<Interceptor>) {
+        self.instrumentation.add_interceptor(interceptor)
+    }
+
+    /// Instrumentation settings of the system
+    pub(crate) fn instrumentation(&self) -> &Arc<Instrumentation> {
+        &self.instrumentation
+    }
 }
 
 impl Clone for SystemRegistry {
     fn clone(&self) -> Self {
-        SystemRegistry{registry: Arc::clone(&self.registry)}
+        SystemRegistry{registry: Arc::clone(&self.registry),
+                       metrics: Arc::clone(&self.metrics),
+                       instrumentation: Arc::clone(&self.instrumentation)}
     }
 }
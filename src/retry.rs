@@ -0,0 +1,198 @@
+use std::{cmp, io};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use futures::{sync, unsync, Async, Future, Poll};
+
+use clock::Delay;
+
+/// Default initial delay between attempts
+const DEFAULT_BACKOFF: u64 = 100;
+
+/// Default maximum delay between attempts
+const DEFAULT_MAX_BACKOFF: u64 = 10_000;
+
+/// Retry policy for `call_retry` methods
+///
+/// By default call is retried only if actor drops message without response,
+/// for example if supervised actor restarts during message processing, in this
+/// case caller receives `Canceled`. Message is sent to the same address,
+/// so in case of supervised actor new attempt is handled by new incarnation of the actor.
+/// If `Supervisor` restarted the actor after the attempt has been sent, next attempt
+/// is sent immediately without backoff delay.
+/// Results of message handler could be retried as well with `retry_if` predicate.
+///
+/// Delay between attempts starts with `backoff` and doubles after each
+/// attempt, but it is never bigger than `max_backoff`.
+pub struct Retry<I, E> {
+    attempts: usize,
+    backoff: Duration,
+    max_backoff: Duration,
+    predicate: Option<Box<Fn(&Result<I, E>) -> bool + Send>>,
+}
+
+impl<I, E> Retry<I, E> {
+
+    /// Create retry policy with number of attempts, including first one
+    pub fn new(attempts: usize) -> Retry<I, E> {
+        Retry {
+            attempts: cmp::max(attempts, 1),
+            backoff: Duration::from_millis(DEFAULT_BACKOFF),
+            max_backoff: Duration::from_millis(DEFAULT_MAX_BACKOFF),
+            predicate: None,
+        }
+    }
+
+    /// Set initial delay between attempts
+    pub fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Set maximum delay between attempts
+    pub fn max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Retry call if predicate returns `true` for message handler's result.
+    /// Predicate is `Send`, so policy could be used with `SyncAddress`.
+    pub fn retry_if<F>(mut self, f: F) -> Self
+        where F: Fn(&Result<I, E>) -> bool + Send + 'static
+    {
+        self.predicate = Some(Box::new(f));
+        self
+    }
+
+    fn next_backoff(&mut self) -> Duration {
+        let backoff = self.backoff;
+        self.backoff = cmp::min(self.backoff * 2, self.max_backoff);
+        backoff
+    }
+}
+
+/// Error of the retrying call
+#[derive(Debug)]
+pub enum RetryError {
+    /// Last attempt is canceled
+    Canceled,
+    /// Backoff timer failed
+    Timer(io::Error),
+}
+
+enum RetryState<F> {
+    Call(F),
+    Backoff(Delay),
+}
+
+/// Future of the retrying call
+///
+/// Resolves with result of the last attempt. If last attempt is canceled,
+/// resolves with `RetryError::Canceled` error.
+#[must_use = "future do nothing unless polled"]
+pub struct RetryCall<I, E, F=unsync::oneshot::Receiver<Result<I, E>>, C=Box<Fn() -> F>> {
+    call: C,
+    retry: Retry<I, E>,
+    attempt: usize,
+    state: RetryState<F>,
+    // restarts of the actor, incremented by supervisor
+    generation: Arc<AtomicUsize>,
+    restarts: usize,
+}
+
+/// Future of the retrying call made through `SyncAddress`, it is `Send`
+pub type SyncRetryCall<I, E> = RetryCall<I, E, sync::oneshot::Receiver<Result<I, E>>,
+                                         Box<Fn() -> sync::oneshot::Receiver<Result<I, E>> + Send>>;
+
+/// Sends next attempt of the call
+pub trait Attempt<F> {
+    fn attempt(&self) -> F;
+}
+
+impl<F> Attempt<F> for Box<Fn() -> F> {
+    fn attempt(&self) -> F {
+        (**self)()
+    }
+}
+
+impl<F> Attempt<F> for Box<Fn() -> F + Send> {
+    fn attempt(&self) -> F {
+        (**self)()
+    }
+}
+
+impl<I, E, F, C> RetryCall<I, E, F, C> where C: Attempt<F> {
+
+    pub(crate) fn new(call: C, retry: Retry<I, E>, generation: Arc<AtomicUsize>)
+                      -> RetryCall<I, E, F, C>
+    {
+        let restarts = generation.load(Ordering::SeqCst);
+        let fut = call.attempt();
+        RetryCall {
+            call: call,
+            retry: retry,
+            attempt: 1,
+            state: RetryState::Call(fut),
+            generation: generation,
+            restarts: restarts,
+        }
+    }
+
+    fn call(&mut self) -> F {
+        self.attempt += 1;
+        self.restarts = self.generation.load(Ordering::SeqCst);
+        self.call.attempt()
+    }
+}
+
+impl<I, E, F, C> Future for RetryCall<I, E, F, C>
+    where C: Attempt<F>,
+          F: Future<Item=Result<I, E>>,
+{
+    type Item = Result<I, E>;
+    type Error = RetryError;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        loop {
+            // `true` if next attempt is sent without backoff
+            let immediate = match self.state {
+                RetryState::Call(ref mut fut) => match fut.poll() {
+                    Ok(Async::NotReady) => return Ok(Async::NotReady),
+                    Ok(Async::Ready(res)) => {
+                        let retry = match self.retry.predicate {
+                            Some(ref predicate) => predicate(&res),
+                            None => false,
+                        };
+                        if !retry || self.attempt >= self.retry.attempts {
+                            return Ok(Async::Ready(res))
+                        }
+                        false
+                    }
+                    Err(_) => {
+                        if self.attempt >= self.retry.attempts {
+                            return Err(RetryError::Canceled)
+                        }
+                        // actor has been restarted by supervisor
+                        let restarted = self.generation.load(Ordering::SeqCst) != self.restarts;
+                        debug!("Message is canceled, retry (restarted: {})", restarted);
+                        restarted
+                    }
+                },
+                RetryState::Backoff(ref mut timeout) => match timeout.poll() {
+                    Ok(Async::NotReady) => return Ok(Async::NotReady),
+                    Ok(Async::Ready(_)) => true,
+                    Err(err) => return Err(RetryError::Timer(err)),
+                },
+            };
+
+            if immediate {
+                let fut = self.call();
+                self.state = RetryState::Call(fut);
+            } else {
+                let backoff = self.retry.next_backoff();
+                self.state = RetryState::Backoff(
+                    Delay::new(backoff).map_err(RetryError::Timer)?);
+            }
+        }
+    }
+}
@@ -1,32 +1,106 @@
+use std::any::Any;
+use std::rc::{Rc, Weak};
+use std::cell::RefCell;
 use std::collections::VecDeque;
 use futures::{self, Async, AsyncSink};
 use futures::unsync::oneshot::{channel, Sender};
 
-use actor::Actor;
+use actor::{Actor, SinkHandler};
 use address::{Subscriber, AsyncSubscriber};
 use context::Context;
 use message::CallResult;
 
+/// Default number of items that could be buffered with unbuffered operations
+const DEFAULT_CAPACITY: usize = 1;
+
 /// Sink wrapper
+///
+/// Sink items are buffered and get sent to underlying sink during context poll.
+/// If underlying sink fails, sink get closed and all pending items get dropped,
+/// pending `call` futures resolve with `Canceled`. Actor can observe sink
+/// failure and other sink events with
+/// [`SinkHandler`](trait.SinkHandler.html), see `Context::observe_sink()`.
+/// Failure of unobserved sink is logged.
+///
+/// `Sink` does not own sink context, it is safe to use `Sink` after actor
+/// get stopped, in this case all items get dropped and `is_closed()` returns `true`.
+///
+/// `unbuffered_send` and `unbuffered_call` methods accept items only if number of
+/// buffered items is less than sink capacity, otherwise item is returned back.
+/// `send` and `call` methods always buffer items.
 pub struct Sink<I, E> {
-    // FIXME: Should this be replaced with Rc<RefCell<SinkContext>>
-    srv: *mut SinkContext<I, E>
+    srv: Weak<RefCell<SinkBuffer<I, E>>>,
 }
 
 impl<I, E> Sink<I, E> {
-    pub(crate) fn new(srv: *mut SinkContext<I, E>) -> Sink<I, E> {
-        Sink{srv: srv as *mut _}
+    /// Create `Sink` for sink context registered in actor's context.
+    ///
+    /// Sink context is owned by actor's context, `Sink` keeps weak
+    /// reference to sink's buffer.
+    pub(crate) fn new(srv: &SinkContext<I, E>) -> Sink<I, E> {
+        Sink{srv: Rc::downgrade(&srv.0)}
+    }
+
+    /// Indicates if sink is closed. Sink is closed if it failed, or
+    /// `close()` method has been called, or actor's context is stopped.
+    pub fn is_closed(&self) -> bool {
+        if let Some(srv) = self.srv.upgrade() {
+            srv.borrow().state != SinkState::Open
+        } else {
+            true
+        }
+    }
+
+    /// Close sink. All buffered items get sent and flushed first, then
+    /// underlying sink get closed.
+    pub fn close(&self) {
+        if let Some(srv) = self.srv.upgrade() {
+            srv.borrow_mut().close()
+        }
+    }
+
+    /// Number of buffered items
+    pub fn buffered(&self) -> usize {
+        if let Some(srv) = self.srv.upgrade() {
+            srv.borrow().sink_items.len()
+        } else {
+            0
+        }
+    }
+
+    /// Set buffer capacity for unbuffered operations. By default capacity is 1,
+    /// so unbuffered operations succeed only if buffer is empty.
+    pub fn set_capacity(&self, capacity: usize) {
+        if let Some(srv) = self.srv.upgrade() {
+            srv.borrow_mut().capacity = capacity;
+        }
+    }
+}
+
+impl<I, E> Clone for Sink<I, E> {
+    fn clone(&self) -> Self {
+        Sink{srv: Weak::clone(&self.srv)}
     }
 }
 
 impl<I: 'static, E> Subscriber<I> for Sink<I, E> {
 
     fn send(&self, msg: I) {
-        unsafe {(&mut *self.srv).send(msg)}
+        if let Some(srv) = self.srv.upgrade() {
+            srv.borrow_mut().send(msg)
+        }
     }
 
     fn unbuffered_send(&self, msg: I) -> Result<(), I> {
-        unsafe {(&mut *self.srv).unbuffered_send(msg)}
+        if let Some(srv) = self.srv.upgrade() {
+            srv.borrow_mut().unbuffered_send(msg)
+        } else {
+            Err(msg)
+        }
+    }
+
+    fn is_closed(&self) -> bool {
+        self.is_closed()
     }
 }
 
@@ -35,11 +109,53 @@ impl<I, E> AsyncSubscriber<I> for Sink<I, E> {
     type Future = CallResult<(), E>;
 
     fn call(&self, msg: I) -> Self::Future {
-        unsafe {(&mut *self.srv).call(msg)}
+        if let Some(srv) = self.srv.upgrade() {
+            srv.borrow_mut().call(msg)
+        } else {
+            // sender get dropped, so caller receives `Canceled`
+            let (_, rx) = channel();
+            CallResult::new(rx)
+        }
     }
 
     fn unbuffered_call(&self, msg: I) -> Result<Self::Future, I> {
-        unsafe {(&mut *self.srv).unbuffered_call(msg)}
+        if let Some(srv) = self.srv.upgrade() {
+            srv.borrow_mut().unbuffered_call(msg)
+        } else {
+            Err(msg)
+        }
+    }
+}
+
+impl<A: Actor> Context<A> {
+
+    /// Report events of the sink to actor's `SinkHandler` implementation.
+    ///
+    /// Sink has to be registered in this context, events of sinks of other
+    /// contexts are ignored.
+    pub fn observe_sink<I, E>(&mut self, sink: &Sink<I, E>) where A: SinkHandler<E> {
+        if let Some(srv) = sink.srv.upgrade() {
+            srv.borrow_mut().handler = Some(sink_event::<A, E>);
+        }
+    }
+}
+
+/// Actor's sink event handler. Sink context is not aware of actor type,
+/// so actor and context are passed as `Any`.
+type SinkEventHandler<E> = fn(&mut Any, &mut Any, SinkEvent<E>);
+
+fn sink_event<A, E>(act: &mut Any, ctx: &mut Any, event: SinkEvent<E>)
+    where A: Actor + SinkHandler<E>
+{
+    if let (Some(act), Some(ctx)) =
+        (act.downcast_mut::<A>(), ctx.downcast_mut::<Context<A>>())
+    {
+        match event {
+            SinkEvent::NotReady => (),
+            SinkEvent::Drained => act.drained(ctx),
+            SinkEvent::Closed => act.closed(ctx),
+            SinkEvent::Error(err) => act.error(err, ctx),
+        }
     }
 }
 
@@ -48,35 +164,68 @@ enum IoItem<I, E> {
     Call((I, Sender<Result<(), E>>)),
 }
 
-pub(crate) struct SinkContext<I, E>
+struct SinkBuffer<I, E>
 {
     sink: Box<futures::Sink<SinkItem=I, SinkError=E>>,
     sink_items: VecDeque<IoItem<I, E>>,
     sink_flushed: bool,
+    state: SinkState,
+    capacity: usize,
+    handler: Option<SinkEventHandler<E>>,
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+enum SinkState {
+    Open,
+    Closing,
+    Closed,
+}
+
+/// Sink events, reported to actor after sink context get released
+enum SinkEvent<E> {
+    NotReady,
+    Drained,
+    Closed,
+    Error(E),
 }
 
-/// Sink execution context
-impl<I, E> SinkContext<I, E>
+/// Sink buffer, shared by sink context and `Sink` objects
+impl<I, E> SinkBuffer<I, E>
 {
-    pub(crate) fn new<S>(sink: S) -> SinkContext<I, E>
+    fn new<S>(sink: S) -> SinkBuffer<I, E>
         where S: futures::Sink<SinkItem=I, SinkError=E> + 'static
     {
-        SinkContext {
+        SinkBuffer {
             sink: Box::new(sink),
             sink_items: VecDeque::new(),
             sink_flushed: true,
+            state: SinkState::Open,
+            capacity: DEFAULT_CAPACITY,
+            handler: None,
+        }
+    }
+
+    fn close(&mut self) {
+        if self.state == SinkState::Open {
+            self.state = SinkState::Closing;
         }
     }
 
-    pub fn call(&mut self, msg: I) -> CallResult<(), E> {
+    fn is_full(&self) -> bool {
+        self.sink_items.len() >= self.capacity
+    }
+
+    fn call(&mut self, msg: I) -> CallResult<(), E> {
         let (tx, rx) = channel();
-        self.sink_items.push_back(IoItem::Call((msg, tx)));
+        if self.state == SinkState::Open {
+            self.sink_items.push_back(IoItem::Call((msg, tx)));
+        }
 
         CallResult::new(rx)
     }
 
-    pub fn unbuffered_call(&mut self, msg: I) -> Result<CallResult<(), E>, I> {
-        if self.sink_items.is_empty() {
+    fn unbuffered_call(&mut self, msg: I) -> Result<CallResult<(), E>, I> {
+        if self.state == SinkState::Open && !self.is_full() {
             let (tx, rx) = channel();
             self.sink_items.push_back(IoItem::Call((msg, tx)));
 
@@ -86,32 +235,33 @@ impl<I, E> SinkContext<I, E>
         }
     }
 
-    pub fn send(&mut self, msg: I) {
-        self.sink_items.push_back(IoItem::Message(msg));
+    fn send(&mut self, msg: I) {
+        if self.state == SinkState::Open {
+            self.sink_items.push_back(IoItem::Message(msg));
+        }
     }
 
-    pub fn unbuffered_send(&mut self, msg: I) -> Result<(), I> {
-        if self.sink_items.is_empty() {
+    fn unbuffered_send(&mut self, msg: I) -> Result<(), I> {
+        if self.state == SinkState::Open && !self.is_full() {
             self.sink_items.push_back(IoItem::Message(msg));
             Ok(())
         } else {
             Err(msg)
         }
     }
-}
-
-pub(crate) trait SinkContextService<A: Actor> {
 
-    fn poll(&mut self, srv: &mut A, ctx: &mut Context<A>) -> Async<()>;
-
-}
+    /// Sink failed, drop all pending items
+    fn failed(&mut self, err: E) -> SinkEvent<E> {
+        self.state = SinkState::Closed;
+        self.sink_items.clear();
+        SinkEvent::Error(err)
+    }
 
-impl<A, I, E> SinkContextService<A> for SinkContext<I, E>
-    where A: Actor
-{
+    fn poll(&mut self) -> SinkEvent<E> {
+        if self.state == SinkState::Closed {
+            return SinkEvent::Closed
+        }
 
-    fn poll(&mut self, _act: &mut A, _: &mut Context<A>) -> Async<()>
-    {
         loop {
             let mut not_ready = true;
 
@@ -127,7 +277,7 @@ impl<A, I, E> SinkContextService<A> for SinkContext<I, E>
                                 self.sink_flushed = false;
                                 continue
                             }
-                            Err(_) => return Async::Ready(()),
+                            Err(err) => return self.failed(err),
                         },
                         IoItem::Call((msg, tx)) => match self.sink.start_send(msg) {
                             Ok(AsyncSink::NotReady(msg)) => {
@@ -138,10 +288,8 @@ impl<A, I, E> SinkContextService<A> for SinkContext<I, E>
                                 self.sink_flushed = false;
                                 continue
                             }
-                            Err(err) => {
-                                let _ = tx.send(Err(err));
-                                return Async::Ready(())
-                            }
+                            // error get reported to the actor, caller receives `Canceled`
+                            Err(err) => return self.failed(err),
                         }
                     }
                 }
@@ -154,15 +302,82 @@ impl<A, I, E> SinkContextService<A> for SinkContext<I, E>
                     Ok(Async::Ready(_)) => {
                         not_ready = false;
                         self.sink_flushed = true;
+                        if self.sink_items.is_empty() {
+                            return SinkEvent::Drained
+                        }
                     }
                     Ok(Async::NotReady) => (),
-                    Err(_) => return Async::Ready(())
+                    Err(err) => return self.failed(err),
                 };
             }
 
+            // close sink
+            if self.state == SinkState::Closing &&
+                self.sink_flushed && self.sink_items.is_empty()
+            {
+                match self.sink.close() {
+                    Ok(Async::Ready(_)) => {
+                        self.state = SinkState::Closed;
+                        return SinkEvent::Closed
+                    }
+                    Ok(Async::NotReady) => (),
+                    Err(err) => return self.failed(err),
+                }
+            }
+
             // are we done
             if not_ready {
-                return Async::NotReady
+                return SinkEvent::NotReady
+            }
+        }
+    }
+}
+
+pub(crate) trait SinkContextService<A: Actor> {
+
+    fn poll(&mut self, srv: &mut A, ctx: &mut Context<A>) -> Async<()>;
+
+}
+
+/// Sink execution context, owned by actor's context. `Sink` objects hold
+/// weak reference to sink's buffer, so buffer get dropped with the context.
+pub(crate) struct SinkContext<I, E>(Rc<RefCell<SinkBuffer<I, E>>>);
+
+impl<I, E> SinkContext<I, E> {
+    pub(crate) fn new<S>(sink: S) -> SinkContext<I, E>
+        where S: futures::Sink<SinkItem=I, SinkError=E> + 'static
+    {
+        SinkContext(Rc::new(RefCell::new(SinkBuffer::new(sink))))
+    }
+}
+
+impl<A, I, E> SinkContextService<A> for SinkContext<I, E>
+    where A: Actor
+{
+
+    fn poll(&mut self, act: &mut A, ctx: &mut Context<A>) -> Async<()>
+    {
+        loop {
+            // sink context must not be borrowed during actor's callbacks,
+            // actor can use `Sink` object
+            let (event, handler) = {
+                let mut srv = self.0.borrow_mut();
+                (srv.poll(), srv.handler)
+            };
+
+            let done = match event {
+                SinkEvent::NotReady => return Async::NotReady,
+                SinkEvent::Drained => false,
+                SinkEvent::Closed | SinkEvent::Error(_) => true,
+            };
+            if let Some(handler) = handler {
+                handler(act, ctx, event);
+            } else if let SinkEvent::Error(_) = event {
+                error!("Sink of {} failed, error is dropped. Use `Context::observe_sink()` \
+                        to handle sink errors", ctx.identity());
+            }
+            if done {
+                return Async::Ready(())
             }
         }
     }
@@ -0,0 +1,176 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use futures::{Async, Poll};
+
+use fut::ActorFuture;
+use actor::{Actor, MessageHandler, MessageResponse};
+use context::Context;
+use message::{handle_message, MessageFuture, ReplySlot};
+use trace::{self, TraceContext};
+
+impl<A> Context<A> where A: Actor {
+
+    /// Replay all stashed messages.
+    ///
+    /// Replay is asynchronous, stashed messages get handled in the same order
+    /// they were stashed during next iteration of the context's event loop.
+    /// Messages that are already queued in actor's mailbox could be handled
+    /// before or in between replayed messages, so actor has to be ready to
+    /// handle new messages once `unstash_all()` is called.
+    /// Response of the message handler is delivered to original sender.
+    pub fn unstash_all(&mut self, stash: &mut Stash<A>) {
+        if !stash.is_empty() {
+            let items = stash.items.drain(..).collect();
+            self.spawn(Unstash{items: items})
+        }
+    }
+}
+
+/// Stash of postponed messages
+///
+/// Actor can postpone message handling, for example until initialization is
+/// completed. Message handler returns result of `Stash::defer()` method,
+/// message is kept unprocessed until actor calls `Context::unstash_all()`.
+/// Result of `Stash::defer()` must not be polled or used in any other way,
+/// polling it panics.
+/// Caller receives response after message gets handled.
+/// If actor stops before stashed messages are replayed, callers receive `Canceled`.
+///
+/// # Examples
+///
+/// ```rust
+/// extern crate actix;
+///
+/// use actix::prelude::*;
+/// use actix::Stash;
+///
+/// struct Query;
+/// struct Connected;
+///
+/// struct DbActor {
+///     connected: bool,
+///     stash: Stash<DbActor>,
+/// }
+///
+/// impl Actor for DbActor {}
+///
+/// impl MessageResponse<Query> for DbActor {
+///     type Item = ();
+///     type Error = ();
+/// }
+///
+/// impl MessageHandler<Query> for DbActor {
+///     fn handle(&mut self, msg: Query, _: &mut Context<Self>) -> MessageFuture<Self, Query> {
+///         if !self.connected {
+///             // handle message after connection get established
+///             return self.stash.defer(msg)
+///         }
+///         Arbiter::system().send(actix::SystemExit(0));
+///         ().to_result()
+///     }
+/// }
+///
+/// impl MessageResponse<Connected> for DbActor {
+///     type Item = ();
+///     type Error = ();
+/// }
+///
+/// impl MessageHandler<Connected> for DbActor {
+///     fn handle(&mut self, _: Connected, ctx: &mut Context<Self>)
+///               -> MessageFuture<Self, Connected>
+///     {
+///         self.connected = true;
+///         ctx.unstash_all(&mut self.stash);
+///         ().to_result()
+///     }
+/// }
+///
+/// fn main() {
+///    let sys = System::new("test".to_owned());
+///
+///    let addr: Address<_> = DbActor{connected: false, stash: Stash::new()}.start();
+///    addr.send(Query);
+///    addr.send(Connected);
+///
+///    sys.run();
+/// }
+/// ```
+pub struct Stash<A: Actor> {
+    items: VecDeque<Box<Stashed<A>>>,
+}
+
+impl<A: Actor> Default for Stash<A> {
+    fn default() -> Self {
+        Stash::new()
+    }
+}
+
+impl<A: Actor> Stash<A> {
+
+    pub fn new() -> Stash<A> {
+        Stash{items: VecDeque::new()}
+    }
+
+    /// Number of stashed messages
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Check if stash is empty
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Postpone message handling. Message is kept with its response channel,
+    /// caller receives response after message get replayed and handled.
+    pub fn defer<M: 'static>(&mut self, msg: M) -> MessageFuture<A, M>
+        where A: MessageHandler<M>
+    {
+        let slot = Rc::new(RefCell::new(None));
+        self.items.push_back(
+            Box::new(StashedMessage{msg: msg, tx: Rc::clone(&slot), trace: trace::current()}));
+        MessageFuture::deferred(slot)
+    }
+}
+
+trait Stashed<A: Actor> {
+
+    /// handle stashed message
+    fn handle(self: Box<Self>, act: &mut A, ctx: &mut Context<A>);
+}
+
+struct StashedMessage<A, M> where A: Actor + MessageResponse<M> {
+    msg: M,
+    tx: ReplySlot<A::Item, A::Error>,
+    trace: Option<TraceContext>,
+}
+
+impl<A, M> Stashed<A> for StashedMessage<A, M>
+    where A: Actor + MessageHandler<M>, M: 'static
+{
+    fn handle(self: Box<Self>, act: &mut A, ctx: &mut Context<A>) {
+        let item = *self;
+        let tx = item.tx.borrow_mut().take();
+        handle_message(act, ctx, item.msg, tx, item.trace);
+    }
+}
+
+/// Replays stashed messages
+struct Unstash<A: Actor> {
+    items: VecDeque<Box<Stashed<A>>>,
+}
+
+impl<A: Actor> ActorFuture for Unstash<A> {
+    type Item = ();
+    type Error = ();
+    type Actor = A;
+
+    fn poll(&mut self, act: &mut A, ctx: &mut Context<A>) -> Poll<Self::Item, Self::Error>
+    {
+        while let Some(item) = self.items.pop_front() {
+            item.handle(act, ctx);
+        }
+        Ok(Async::Ready(()))
+    }
+}
@@ -0,0 +1,194 @@
+use std::marker::PhantomData;
+use futures::{Async, Poll, Stream};
+
+use fut::ActorFuture;
+use actor::{Actor, MessageHandler, MessageResponse, StreamHandler, StreamControl, StreamEnd};
+use context::Context;
+use message::MessageFuture;
+
+impl<A> Context<A> where A: Actor {
+
+    /// Register stream, each stream item get handled by actor's `MessageHandler`.
+    ///
+    /// Stream errors and stream end are reported to actor's `StreamHandler`.
+    pub fn add_stream<S>(&mut self, stream: S)
+        where S: Stream + 'static,
+              S::Item: 'static,
+              A: MessageHandler<S::Item, S::Error> + StreamHandler<S::Item, S::Error>,
+    {
+        self.spawn(ActorStreamCell::new(stream))
+    }
+
+    /// Register stream with concurrency limit.
+    ///
+    /// Next stream item is not polled until number of in-flight handler futures
+    /// for this stream is less than `limit`. This is useful if message handler
+    /// is asynchronous and stream produces items faster than actor can process them.
+    pub fn add_stream_with_limit<S>(&mut self, stream: S, limit: usize)
+        where S: Stream + 'static,
+              S::Item: 'static,
+              A: MessageHandler<S::Item, S::Error> + StreamHandler<S::Item, S::Error>,
+    {
+        assert!(limit > 0, "Limit has to be greater than 0");
+        self.spawn(ActorStreamCell::with_limit(stream, limit))
+    }
+}
+
+/// Stream processing future
+///
+/// `Context::add_stream()`, `Context::add_stream_with_limit()`,
+/// `ActorBuilder::start_with()` and `ActorBuilder::create_with()` spawn
+/// `ActorStreamCell` into actor's context.
+/// Each stream item get handled by `MessageHandler`, handler's future is spawned
+/// into context. Stream errors are handled by `StreamHandler::error` method.
+///
+/// If limit is set, handler futures are polled by `ActorStreamCell` itself and
+/// stream is not polled while number of in-flight futures reaches the limit.
+/// `StreamHandler::stream_finished` is called after all in-flight futures complete.
+pub(crate) struct ActorStreamCell<A, S>
+    where S: Stream, A: Actor + MessageResponse<S::Item>
+{
+    act: PhantomData<A>,
+    started: bool,
+    end: Option<StreamEnd>,
+    stream: S,
+    limit: Option<usize>,
+    inflight: Vec<StreamItemFuture<A, S::Item>>,
+}
+
+impl<A, S> ActorStreamCell<A, S>
+    where S: Stream + 'static,
+          S::Item: 'static,
+          A: Actor + MessageHandler<S::Item, S::Error> + StreamHandler<S::Item, S::Error>,
+{
+    pub(crate) fn new(stream: S) -> ActorStreamCell<A, S> {
+        ActorStreamCell {
+            act: PhantomData,
+            started: false,
+            end: None,
+            stream: stream,
+            limit: None,
+            inflight: Vec::new(),
+        }
+    }
+
+    pub(crate) fn with_limit(stream: S, limit: usize) -> ActorStreamCell<A, S> {
+        let mut cell = ActorStreamCell::new(stream);
+        cell.limit = Some(limit);
+        cell
+    }
+
+    fn handle(&mut self, msg: S::Item, act: &mut A, ctx: &mut Context<A>) {
+        let mut fut = <A as MessageHandler<S::Item, S::Error>>::handle(act, msg, ctx);
+        if fut.take_deferred().is_some() {
+            // item is stashed by actor
+            return
+        }
+        let mut fut = StreamItemFuture{fut: fut};
+
+        if self.limit.is_some() {
+            if let Ok(Async::NotReady) = fut.poll(act, ctx) {
+                self.inflight.push(fut);
+            }
+        } else {
+            ctx.spawn(fut);
+        }
+    }
+
+    fn poll_inflight(&mut self, act: &mut A, ctx: &mut Context<A>) {
+        let mut idx = 0;
+        while idx < self.inflight.len() {
+            match self.inflight[idx].poll(act, ctx) {
+                Ok(Async::NotReady) => idx += 1,
+                Ok(Async::Ready(_)) | Err(_) => {
+                    self.inflight.swap_remove(idx);
+                }
+            }
+        }
+    }
+
+}
+
+impl<A, S> ActorFuture for ActorStreamCell<A, S>
+    where S: Stream + 'static,
+          S::Item: 'static,
+          A: Actor + MessageHandler<S::Item, S::Error> + StreamHandler<S::Item, S::Error>,
+{
+    type Item = ();
+    type Error = ();
+    type Actor = A;
+
+    fn poll(&mut self, act: &mut A, ctx: &mut Context<A>) -> Poll<Self::Item, Self::Error>
+    {
+        if !self.started {
+            self.started = true;
+            <A as StreamHandler<S::Item, S::Error>>::started(act, ctx);
+        }
+
+        self.poll_inflight(act, ctx);
+
+        // stream items draw from actor's message budget
+        let budget = ctx.budget();
+
+        loop {
+            if let Some(reason) = self.end {
+                // wait for in-flight handlers, then report stream end
+                if self.inflight.is_empty() {
+                    <A as StreamHandler<S::Item, S::Error>>::stream_finished(act, reason, ctx);
+                    return Ok(Async::Ready(()))
+                } else {
+                    return Ok(Async::NotReady)
+                }
+            }
+
+            // apply backpressure
+            if let Some(limit) = self.limit {
+                if self.inflight.len() >= limit {
+                    return Ok(Async::NotReady)
+                }
+            }
+
+            if budget.available() == 0 {
+                return Ok(Async::NotReady)
+            }
+
+            match self.stream.poll() {
+                Ok(Async::Ready(Some(msg))) => {
+                    budget.consume(1);
+                    self.handle(msg, act, ctx)
+                }
+                Ok(Async::Ready(None)) => self.end = Some(StreamEnd::Completed),
+                Ok(Async::NotReady) => return Ok(Async::NotReady),
+                Err(err) => {
+                    match <A as StreamHandler<S::Item, S::Error>>::error(act, err, ctx) {
+                        StreamControl::Continue => continue,
+                        StreamControl::StopStream => (),
+                        StreamControl::StopActor => ctx.stop(),
+                    }
+                    self.end = Some(StreamEnd::Failed);
+                }
+            }
+        }
+    }
+}
+
+/// Stream item handler's future
+struct StreamItemFuture<A, M> where A: Actor + MessageResponse<M> {
+    fut: MessageFuture<A, M>,
+}
+
+impl<A, M> ActorFuture for StreamItemFuture<A, M> where A: Actor + MessageResponse<M>
+{
+    type Item = ();
+    type Error = ();
+    type Actor = A;
+
+    fn poll(&mut self, act: &mut A, ctx: &mut Context<A>) -> Poll<Self::Item, Self::Error>
+    {
+        match self.fut.poll(act, ctx) {
+            Ok(Async::Ready(_)) => Ok(Async::Ready(())),
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            Err(_) => Err(()),
+        }
+    }
+}
@@ -1,17 +1,24 @@
 use std;
+use std::sync::atomic::Ordering;
 use futures::{Future, Async, Poll, Stream};
 
 use actor::{Actor, SupervisedActor};
+use actors::dead_letters::{self, DeadLetterReason};
 use arbiter::{Arbiter, Execute};
 use address::{Address, SyncAddress, Proxy};
 use context::{Context, ContextProtocol};
 use queue::{sync, unsync};
 
+// Maximum number of remote messages received at once
+const SYNC_BATCH: usize = 32;
+
 /// Actor supervisor
 ///
 /// `Supervisor` can not garantee that actor successfully process incoming message.
 /// If actor fails during message processing, this message can not be recovered. But sender
-/// would receive `Err(Cancelled)` error in this situation.
+/// would receive `Err(Cancelled)` error in this situation. Use `call_retry` methods
+/// of the supervisor's addresses to resend such messages to the restarted actor,
+/// see [`Retry`](struct.Retry.html).
 ///
 /// ## Example
 ///
@@ -63,7 +70,7 @@ pub struct Supervisor<A: SupervisedActor> {
     msgs: unsync::UnboundedReceiver<ContextProtocol<A>>,
     sync_msgs: sync::UnboundedReceiver<Proxy<A>>,
     msg: Option<ContextProtocol<A>>,
-    sync_msg: Option<Proxy<A>>,
+    sync_msgs_batch: Vec<Proxy<A>>,
 }
 
 struct ActorCell<A: SupervisedActor> {
@@ -78,10 +85,15 @@ impl<A> Supervisor<A> where A: SupervisedActor
     pub fn start<F>(lazy: bool, f: F) -> (Address<A>, SyncAddress<A>)
         where F: FnOnce(&mut Context<A>) -> A + 'static
     {
-        // create actor
+        // supervisor only forwards messages, supervised actor's context
+        // draws from actor's message budget
+        let rx = unsync::unbounded().unbudgeted();
+
+        // create actor, actor shares identity with supervisor
         let (cell, factory) = if !lazy {
             let mut ctx = Context::new(unsafe{std::mem::uninitialized()});
-            let addr = ctx.address_cell().unsync_sender();
+            let mut addr = ctx.address_cell().unsync_sender();
+            addr.set_identity(rx.identity());
             let act = f(&mut ctx);
             let old = ctx.replace_actor(act);
             std::mem::forget(old);
@@ -92,16 +104,16 @@ impl<A> Supervisor<A> where A: SupervisedActor
         };
 
         // create supervisor
-        let rx = unsync::unbounded();
         let (stx, srx) = sync::unbounded();
         let mut supervisor = Supervisor {
             cell: cell,
             factory: factory,
             msgs: rx,
-            sync_msgs: srx,
+            sync_msgs: srx.unbudgeted(),
             msg: None,
-            sync_msg: None,
+            sync_msgs_batch: Vec::new(),
         };
+        supervisor.update_identity();
         let addr = Address::new(supervisor.msgs.sender());
         let saddr = SyncAddress::new(stx);
         Arbiter::handle().spawn(supervisor);
@@ -118,12 +130,19 @@ impl<A> Supervisor<A> where A: SupervisedActor
             None
         } else {
             let (tx, rx) = sync::unbounded();
+            // supervisor's mailboxes get identity of the sync mailbox,
+            // so returned address reports final identifier
+            let identity = rx.identity();
 
             addr.send(Execute::new(move || -> Result<(), ()> {
-                // create actor
+                let mut lrx = unsync::unbounded().unbudgeted();
+                lrx.set_identity(identity);
+
+                // create actor, actor shares identity with supervisor
                 let (cell, factory) = if lazy {
                     let mut ctx = Context::new(unsafe{std::mem::uninitialized()});
-                    let addr = ctx.address_cell().unsync_sender();
+                    let mut addr = ctx.address_cell().unsync_sender();
+                    addr.set_identity(lrx.identity());
                     let act = f(&mut ctx);
                     let old = ctx.replace_actor(act);
                     std::mem::forget(old);
@@ -133,15 +152,15 @@ impl<A> Supervisor<A> where A: SupervisedActor
                     (None, Some(f))
                 };
 
-                let lrx = unsync::unbounded();
-                let supervisor = Supervisor {
+                let mut supervisor = Supervisor {
                     cell: cell,
                     factory: factory,
                     msgs: lrx,
-                    sync_msgs: rx,
+                    sync_msgs: rx.unbudgeted(),
                     msg: None,
-                    sync_msg: None,
+                    sync_msgs_batch: Vec::new(),
                 };
+                supervisor.update_identity();
                 Arbiter::handle().spawn(supervisor);
                 Ok(())
             }));
@@ -159,12 +178,14 @@ impl<A> Supervisor<A> where A: SupervisedActor
             let f = self.factory.take().expect("Should be available");
             let mut ctx = Context::new(unsafe{std::mem::uninitialized()});
 
-            let addr = ctx.address_cell().unsync_sender();
+            let mut addr = ctx.address_cell().unsync_sender();
+            addr.set_identity(self.msgs.identity());
             let act = f.call(&mut ctx);
             let old = ctx.replace_actor(act);
             std::mem::forget(old);
 
             self.cell = Some(ActorCell {ctx: ctx, addr: addr});
+            self.update_identity();
         }
         self.cell.as_mut().unwrap()
     }
@@ -173,12 +194,49 @@ impl<A> Supervisor<A> where A: SupervisedActor
         let cell = self.cell.take().unwrap();
         let mut ctx = Context::new(unsafe{std::mem::uninitialized()});
 
-        let addr = ctx.address_cell().unsync_sender();
+        // restarted actor keeps its identity
+        let identity = cell.addr.identity();
+        info!("Restarting actor {}", identity);
+        identity.generation.fetch_add(1, Ordering::SeqCst);
+        identity.metrics.restarted();
+
+        let mut addr = ctx.address_cell().unsync_sender();
+        addr.set_identity(identity);
         let old = ctx.replace_actor(cell.ctx.into_inner());
         std::mem::forget(old);
         ctx.restarting();
 
         self.cell = Some(ActorCell {ctx: ctx, addr: addr});
+        self.update_identity();
+    }
+
+    /// Supervisor's mailboxes report identity of the supervised actor
+    fn update_identity(&mut self) {
+        if let Some(ref cell) = self.cell {
+            self.msgs.set_identity(cell.addr.identity());
+        }
+        self.sync_msgs.set_identity(self.msgs.identity());
+    }
+
+    /// Forward received remote messages to supervised actor, messages stay
+    /// in the batch if actor's message queue is dead
+    fn deliver_sync_msgs(&mut self) {
+        let mut batch = std::mem::replace(&mut self.sync_msgs_batch, Vec::new());
+        let mut rest = Vec::new();
+        for msg in batch.drain(..) {
+            if !rest.is_empty() {
+                rest.push(msg);
+                continue
+            }
+            if let Err(msg) = self.get_cell().addr.unbounded_send(
+                ContextProtocol::Envelope(msg))
+            {
+                if let ContextProtocol::Envelope(msg) = msg.into_inner() {
+                    rest.push(msg);
+                }
+            }
+        }
+        self.sync_msgs_batch = if rest.is_empty() { batch } else { rest };
     }
 }
 
@@ -236,27 +294,14 @@ impl<A> Future for Supervisor<A> where A: SupervisedActor
 
             // check remote messages. we still use local queue for remote message,
             // because actor runs in same context as supervisor
-            if let Some(msg) = self.sync_msg.take() {
-                // if Actor message queue is dead, store in temp
-                if let Err(msg) = self.get_cell().addr.unbounded_send(
-                    ContextProtocol::Envelope(msg))
-                {
-                    if let ContextProtocol::Envelope(msg) = msg.into_inner() {
-                        self.sync_msg = Some(msg);
-                    }
-                }
+            if !self.sync_msgs_batch.is_empty() {
+                self.deliver_sync_msgs();
             }
-            if self.sync_msg.is_none() {
-                match self.sync_msgs.poll() {
-                    Ok(Async::Ready(Some(msg))) => {
+            if self.sync_msgs_batch.is_empty() {
+                match self.sync_msgs.poll_batch(&mut self.sync_msgs_batch, SYNC_BATCH) {
+                    Ok(Async::Ready(Some(_))) => {
                         not_ready = false;
-                        if let Err(msg) = self.get_cell().addr.unbounded_send(
-                            ContextProtocol::Envelope(msg))
-                        {
-                            if let ContextProtocol::Envelope(msg) = msg.into_inner() {
-                                self.sync_msg = Some(msg);
-                            }
-                        }
+                        self.deliver_sync_msgs();
                     },
                     Ok(Async::NotReady) | Ok(Async::Ready(None)) | Err(_) => (),
                 }
@@ -278,6 +323,32 @@ impl<A> Future for Supervisor<A> where A: SupervisedActor
     }
 }
 
+impl<A> Drop for Supervisor<A> where A: SupervisedActor
+{
+    fn drop(&mut self) {
+        // messages held for supervised actor and messages queued
+        // in supervisor's mailboxes are undeliverable
+        let id = self.msgs.identity().id();
+        self.msgs.close();
+        self.sync_msgs.close();
+
+        let mut msg = self.msg.take();
+        while let Some(item) = msg.take().or_else(|| self.msgs.try_recv()) {
+            if let ContextProtocol::Envelope(proxy) = item {
+                dead_letters::report::<A>(
+                    id, proxy.0.message_type_id(), proxy.0.message_type(),
+                    DeadLetterReason::SupervisorStopped);
+            }
+        }
+        let mut batch = std::mem::replace(&mut self.sync_msgs_batch, Vec::new()).into_iter();
+        while let Some(proxy) = batch.next().or_else(|| self.sync_msgs.try_recv()) {
+            dead_letters::report::<A>(
+                id, proxy.0.message_type_id(), proxy.0.message_type(),
+                DeadLetterReason::SupervisorStopped);
+        }
+    }
+}
+
 trait FnFactory<A: Actor>: 'static {
     fn call(self: Box<Self>, &mut Context<A>) -> A;
 }
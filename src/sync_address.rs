@@ -1,45 +1,103 @@
+use std::fmt;
+use std::any::{Any, TypeId};
 use std::cell::Cell;
+use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
+use std::sync::Arc;
 
-use futures::{Async, Future, Poll};
+use futures::{Future, Poll};
 use futures::sync::oneshot::{channel, Canceled, Receiver, Sender};
 
 use fut::ActorFuture;
 use actor::{Actor, MessageHandler, MessageResponse};
+use actors::dead_letters::{self, DeadLetterReason};
 use address::{Subscriber, AsyncSubscriber, MessageProxy, Proxy, ActorAddress};
 use context::Context;
-use queue::sync;
-use message::MessageFuture;
+use identity::{ActorId, ActorInfo, Identity};
+use queue::{sync, Priority};
+use message::handle_message;
+use trace::{self, TraceContext};
+use retry::{Retry, RetryCall, SyncRetryCall};
+use utils;
 
 
 /// Address of the actor `A`. Actor can run in differend thread.
 pub struct SyncAddress<A> where A: Actor {
     tx: sync::UnboundedSender<Proxy<A>>,
     closed: Cell<bool>,
+    // identifier of the mailbox, available after receiver is gone
+    info: Arc<ActorInfo>,
 }
 
 impl<A> Clone for SyncAddress<A> where A: Actor {
     fn clone(&self) -> Self {
-        SyncAddress{tx: self.tx.clone(), closed: self.closed.clone()}
+        SyncAddress{tx: self.tx.clone(), closed: self.closed.clone(),
+                    info: Arc::clone(&self.info)}
+    }
+}
+
+impl<A> PartialEq for SyncAddress<A> where A: Actor {
+    fn eq(&self, other: &Self) -> bool {
+        self.id() == other.id()
+    }
+}
+
+impl<A> Eq for SyncAddress<A> where A: Actor {}
+
+impl<A> Hash for SyncAddress<A> where A: Actor {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id().hash(state)
+    }
+}
+
+impl<A> fmt::Debug for SyncAddress<A> where A: Actor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SyncAddress({})", self.tx.identity())
     }
 }
 
 impl<A> ActorAddress<A, SyncAddress<A>> for A where A: Actor {
 
     fn get(ctx: &mut Context<A>) -> SyncAddress<A> {
-        ctx.address_cell().sync_address()
+        // sync mailbox shares identity and metrics with context
+        ctx.metrics();
+        let identity = ctx.address_cell().unsync_sender().identity();
+        let mut addr = ctx.address_cell().sync_address();
+        addr.set_identity(identity);
+        addr
     }
 }
 
 impl<A> SyncAddress<A> where A: Actor {
 
     pub(crate) fn new(sender: sync::UnboundedSender<Proxy<A>>) -> SyncAddress<A> {
-        SyncAddress{tx: sender, closed: Cell::new(false)}
+        let info = Arc::clone(&sender.identity().info);
+        SyncAddress{tx: sender, closed: Cell::new(false), info: info}
     }
 
     /// Indicates if address is closed on other side.
     pub fn is_closed(&self) -> bool {
-        self.closed.get()
+        self.closed.get() || self.tx.is_closed()
+    }
+
+    /// Identifier of the actor
+    pub fn id(&self) -> ActorId {
+        self.info.id()
+    }
+
+    /// Name of the actor
+    pub fn name(&self) -> Option<String> {
+        self.tx.identity().name()
+    }
+
+    pub(crate) fn set_identity(&mut self, identity: Identity) {
+        self.info = Arc::clone(&identity.info);
+        self.tx.set_identity(identity)
+    }
+
+    /// Number of messages in actor's mailbox that are not processed yet.
+    pub fn mailbox_len(&self) -> usize {
+        self.tx.len()
     }
 
     /// Send message `M` to actor `A`. Message cold be sent to actor running in
@@ -49,11 +107,7 @@ impl<A> SyncAddress<A> where A: Actor {
               A::Item: Send,
               A::Error: Send,
     {
-        if self.tx.unbounded_send(
-            Proxy::new(SyncEnvelope::new(Some(msg), None))).is_err()
-        {
-            self.closed.set(true)
-        }
+        self.deliver(SyncEnvelope::new(Some(msg), None), None);
     }
 
     /// Send message to actor `A` and asyncronously wait for response.
@@ -63,11 +117,32 @@ impl<A> SyncAddress<A> where A: Actor {
               A::Error: Send,
     {
         let (tx, rx) = channel();
-        if self.tx.unbounded_send(
-            Proxy::new(SyncEnvelope::new(Some(msg), Some(tx)))).is_err()
-        {
-            self.closed.set(true)
-        }
+        self.deliver(SyncEnvelope::new(Some(msg), Some(tx)), None);
+
+        MessageResult::new(rx)
+    }
+
+    /// Send message `M` to actor `A` with specific priority.
+    ///
+    /// Messages with higher priority are handled first.
+    pub fn send_priority<M: 'static + Send>(&self, msg: M, priority: Priority)
+        where A: MessageHandler<M>,
+              A::Item: Send,
+              A::Error: Send,
+    {
+        self.deliver(SyncEnvelope::new(Some(msg), None), Some(priority));
+    }
+
+    /// Send message to actor `A` with specific priority and
+    /// asyncronously wait for response.
+    pub fn call_priority<B: Actor, M: 'static + Send>(&self, msg: M, priority: Priority)
+                                                      -> MessageResult<A, B, M>
+        where A: MessageHandler<M>,
+              A::Item: Send,
+              A::Error: Send,
+    {
+        let (tx, rx) = channel();
+        self.deliver(SyncEnvelope::new(Some(msg), Some(tx)), Some(priority));
 
         MessageResult::new(rx)
     }
@@ -78,15 +153,53 @@ impl<A> SyncAddress<A> where A: Actor {
               M: 'static
     {
         let (tx, rx) = channel();
-        if self.tx.unbounded_send(
-            Proxy::new(SyncEnvelope::new(Some(msg), Some(tx)))).is_err()
-        {
-            self.closed.set(true)
-        }
+        self.deliver(SyncEnvelope::new(Some(msg), Some(tx)), None);
 
         rx
     }
 
+    /// Send message to actor `A` and asyncronously wait for response,
+    /// retry according to the retry policy. Message is cloned for each attempt.
+    /// Returned future is `Send`.
+    pub fn call_retry<M>(&self, msg: M, retry: Retry<A::Item, A::Error>)
+                         -> SyncRetryCall<A::Item, A::Error>
+        where A: MessageHandler<M>,
+              A::Item: Send,
+              A::Error: Send,
+              M: Clone + Send + 'static
+    {
+        let addr = self.clone();
+        let generation = self.tx.identity().generation;
+        let call: Box<Fn() -> Receiver<Result<A::Item, A::Error>> + Send> =
+            Box::new(move || addr.call_fut(msg.clone()));
+        RetryCall::new(call, retry, generation)
+    }
+
+    /// Send raw proxy to actor's mailbox, returns `false` if context is stopped
+    pub(crate) fn send_proxy<P>(&self, proxy: P) -> bool
+        where P: MessageProxy<Actor=A> + 'static
+    {
+        self.tx.unbounded_send(Proxy::new(proxy)).is_ok()
+    }
+
+    /// Send envelope to actor's mailbox, undeliverable message is reported
+    /// to dead letters actor.
+    fn deliver<M: 'static>(&self, env: SyncEnvelope<A, M>, priority: Option<Priority>)
+        where A: MessageHandler<M>
+    {
+        let msg = Proxy::new(env);
+        let res = match priority {
+            Some(priority) => self.tx.unbounded_send_priority(msg, priority),
+            None => self.tx.unbounded_send(msg),
+        };
+        if res.is_err() {
+            self.closed.set(true);
+            dead_letters::report::<A>(
+                self.id(), TypeId::of::<M>(), utils::type_name::<M>(),
+                DeadLetterReason::Stopped);
+        }
+    }
+
     /// Get `Subscriber` for specific message type
     pub fn subscriber<M: 'static + Send>(&self) -> Box<Subscriber<M> + Send>
         where A: MessageHandler<M>,
@@ -121,6 +234,10 @@ impl<A, M> Subscriber<M> for SyncAddress<A>
         self.send(msg);
         Ok(())
     }
+
+    fn is_closed(&self) -> bool {
+        self.is_closed()
+    }
 }
 
 impl<A, M> AsyncSubscriber<M> for SyncAddress<A>
@@ -134,11 +251,7 @@ impl<A, M> AsyncSubscriber<M> for SyncAddress<A>
     fn call(&self, msg: M) -> Self::Future
     {
         let (tx, rx) = channel();
-        if self.tx.unbounded_send(
-            Proxy::new(SyncEnvelope::new(Some(msg), Some(tx)))).is_err()
-        {
-            self.closed.set(true)
-        }
+        self.deliver(SyncEnvelope::new(Some(msg), Some(tx)), None);
 
         CallResult::new(rx)
     }
@@ -149,11 +262,13 @@ impl<A, M> AsyncSubscriber<M> for SyncAddress<A>
     }
 }
 
-struct SyncEnvelope<A, M> where A: Actor + MessageHandler<M>
+pub(crate) struct SyncEnvelope<A, M> where A: Actor + MessageHandler<M>
 {
     msg: Option<M>,
     act: PhantomData<A>,
     tx: Option<Sender<Result<A::Item, A::Error>>>,
+    // trace context of the sender's thread
+    trace: Option<TraceContext>,
 }
 
 impl<A, M> SyncEnvelope<A, M> where A: Actor + MessageHandler<M>
@@ -161,7 +276,19 @@ impl<A, M> SyncEnvelope<A, M> where A: Actor + MessageHandler<M>
     fn new(msg: Option<M>,
            tx: Option<Sender<Result<A::Item, A::Error>>>) -> SyncEnvelope<A, M>
     {
-        SyncEnvelope{msg: msg, tx: tx, act: PhantomData}
+        SyncEnvelope{msg: msg, tx: tx, act: PhantomData, trace: trace::current()}
+    }
+
+    /// Take message without handling, used by mock addresses
+    pub(crate) fn take_message(&mut self) -> Option<M> {
+        self.msg.take()
+    }
+
+    /// Send response to the caller
+    pub(crate) fn respond(&mut self, res: Result<A::Item, A::Error>) {
+        if let Some(tx) = self.tx.take() {
+            let _ = tx.send(res);
+        }
     }
 }
 
@@ -173,45 +300,20 @@ impl<A, M> MessageProxy for SyncEnvelope<A, M>
     fn handle(&mut self, act: &mut Self::Actor, ctx: &mut Context<A>)
     {
         if let Some(msg) = self.msg.take() {
-            let fut = <Self::Actor as MessageHandler<M>>::handle(act, msg, ctx);
-            let f: EnvelopFuture<Self::Actor, _> = EnvelopFuture {msg: PhantomData,
-                                                                  fut: fut,
-                                                                  tx: self.tx.take()};
-            ctx.spawn(f);
+            handle_message(act, ctx, msg, self.tx.take(), self.trace.take());
         }
     }
-}
 
-struct EnvelopFuture<A, M> where A: Actor + MessageHandler<M>
-{
-    msg: PhantomData<M>,
-    fut: MessageFuture<A, M>,
-    tx: Option<Sender<Result<A::Item, A::Error>>>,
-}
+    fn message_type(&self) -> &'static str {
+        utils::type_name::<M>()
+    }
 
-impl<A, M> ActorFuture for EnvelopFuture<A, M> where A: Actor + MessageHandler<M>
-{
-    type Item = ();
-    type Error = ();
-    type Actor = A;
+    fn message_type_id(&self) -> TypeId {
+        TypeId::of::<M>()
+    }
 
-    fn poll(&mut self, act: &mut A, ctx: &mut Context<A>) -> Poll<Self::Item, Self::Error>
-    {
-        match self.fut.poll(act, ctx) {
-            Ok(Async::Ready(val)) => {
-                if let Some(tx) = self.tx.take() {
-                    let _ = tx.send(Ok(val));
-                }
-                Ok(Async::Ready(()))
-            },
-            Ok(Async::NotReady) => Ok(Async::NotReady),
-            Err(err) => {
-                if let Some(tx) = self.tx.take() {
-                    let _ = tx.send(Err(err));
-                }
-                Err(())
-            }
-        }
+    fn as_any(&mut self) -> &mut Any {
+        self
     }
 }
 
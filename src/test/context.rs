@@ -0,0 +1,134 @@
+use std::mem;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use futures::{future, executor, Async, Poll};
+use futures::executor::Notify;
+
+use actor::{Actor, MessageHandler};
+use context::Context;
+use message::MessageFuture;
+
+/// Actor context detached from event loop
+///
+/// `TestContext` owns actor and its context, handlers could be called directly
+/// and handler's `MessageFuture` could be stepped manually. Context is never
+/// polled, actor's lifecycle methods are not called, futures spawned with
+/// `ctx.spawn()` do not run.
+///
+/// # Examples
+///
+/// ```rust
+/// extern crate actix;
+///
+/// use actix::prelude::*;
+/// use actix::test::TestContext;
+///
+/// struct Sum(usize, usize);
+///
+/// struct Adder;
+///
+/// impl Actor for Adder {}
+///
+/// impl MessageResponse<Sum> for Adder {
+///     type Item = usize;
+///     type Error = ();
+/// }
+///
+/// impl MessageHandler<Sum> for Adder {
+///     fn handle(&mut self, msg: Sum, _: &mut Context<Self>) -> MessageFuture<Self, Sum> {
+///         (msg.0 + msg.1).to_result()
+///     }
+/// }
+///
+/// fn main() {
+///     let mut ctx = TestContext::new(Adder);
+///     assert_eq!(ctx.call(Sum(1, 2)), Ok(3));
+/// }
+/// ```
+pub struct TestContext<A: Actor> {
+    act: A,
+    ctx: Option<Context<A>>,
+    notify: Arc<Notified>,
+}
+
+impl<A: Actor> TestContext<A> {
+
+    /// Create detached context for the actor
+    pub fn new(act: A) -> TestContext<A> {
+        // actor is stored outside of the context
+        let ctx = Context::new(unsafe{mem::uninitialized()});
+        TestContext {
+            act: act,
+            ctx: Some(ctx),
+            notify: Arc::new(Notified(AtomicBool::new(false))),
+        }
+    }
+
+    /// Reference to the actor
+    pub fn actor(&mut self) -> &mut A {
+        &mut self.act
+    }
+
+    /// Reference to the actor's context
+    pub fn context(&mut self) -> &mut Context<A> {
+        self.ctx.as_mut().unwrap()
+    }
+
+    /// Call message handler, returns handler's response future.
+    pub fn handle<M>(&mut self, msg: M) -> MessageFuture<A, M>
+        where A: MessageHandler<M>
+    {
+        let ctx = self.ctx.as_mut().unwrap();
+        <A as MessageHandler<M>>::handle(&mut self.act, msg, ctx)
+    }
+
+    /// Poll handler's response future once.
+    pub fn step<M>(&mut self, fut: &mut MessageFuture<A, M>) -> Poll<A::Item, A::Error>
+        where A: MessageHandler<M>
+    {
+        self.notify.0.store(false, Ordering::SeqCst);
+
+        let act = &mut self.act;
+        let ctx = self.ctx.as_mut().unwrap();
+        executor::spawn(future::poll_fn(|| fut.poll(act, ctx)))
+            .poll_future_notify(&self.notify, 0)
+    }
+
+    /// Call message handler and step response future to completion.
+    ///
+    /// Panics if response future can not complete without event loop.
+    pub fn call<M>(&mut self, msg: M) -> Result<A::Item, A::Error>
+        where A: MessageHandler<M>
+    {
+        let mut fut = self.handle(msg);
+        loop {
+            match self.step(&mut fut) {
+                Ok(Async::Ready(item)) => return Ok(item),
+                Ok(Async::NotReady) =>
+                    if !self.notify.0.load(Ordering::SeqCst) {
+                        panic!("Message future can not complete without event loop")
+                    },
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+impl<A: Actor> Drop for TestContext<A> {
+    fn drop(&mut self) {
+        if let Some(ctx) = self.ctx.take() {
+            // context does not own actor
+            mem::forget(ctx.into_inner());
+        }
+    }
+}
+
+/// Records wake up of the stepped future
+struct Notified(AtomicBool);
+
+impl Notify for Notified {
+    fn notify(&self, _: usize) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
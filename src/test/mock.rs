@@ -0,0 +1,343 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+use std::sync::{Arc, Mutex};
+
+use futures::{future, Async, Future, Poll, Stream};
+use futures::unsync::oneshot::channel;
+
+use actor::{Actor, MessageHandler};
+use address::{Address, SyncAddress, Subscriber, AsyncSubscriber, Proxy};
+use arbiter::Arbiter;
+use context::ContextProtocol;
+use message::{CallResult, Envelope};
+use queue::{sync, unsync};
+use sync_address::SyncEnvelope;
+use super::Probe;
+
+/// Mock of the actor `A`
+///
+/// Messages sent to mock's addresses are not handled by actor, mock records
+/// them and replies with scripted responses. Message type has to be registered
+/// with `expect()` or `reply()` before message is sent, otherwise message
+/// is dropped and its type is recorded as unexpected. Caller of
+/// the unscripted message receives `Canceled` error.
+///
+/// Mock processes messages on `process()` call, or continuously in
+/// current arbiter after `start()` call.
+///
+/// # Examples
+///
+/// ```rust
+/// extern crate actix;
+/// extern crate futures;
+///
+/// use futures::Future;
+/// use actix::prelude::*;
+/// use actix::test::Mock;
+///
+/// struct Get(String);
+///
+/// struct Storage;
+///
+/// impl Actor for Storage {}
+///
+/// impl MessageResponse<Get> for Storage {
+///     type Item = String;
+///     type Error = ();
+/// }
+///
+/// impl MessageHandler<Get> for Storage {
+///     fn handle(&mut self, _: Get, _: &mut Context<Self>) -> MessageFuture<Self, Get> {
+///         // never called, messages are handled by mock
+///         String::new().to_result()
+///     }
+/// }
+///
+/// fn main() {
+///     let mock = Mock::<Storage>::new();
+///     mock.reply(|msg: &Get| Ok(format!("value of {}", msg.0)));
+///
+///     let addr = mock.address();
+///     let res = addr.call_fut(Get("key".to_owned()));
+///     mock.process();
+///
+///     assert_eq!(res.wait(), Ok(Ok("value of key".to_owned())));
+///     assert_eq!(mock.messages::<Get>().len(), 1);
+/// }
+/// ```
+pub struct Mock<A: Actor> {
+    inner: Rc<RefCell<MockInner<A>>>,
+}
+
+struct MockInner<A: Actor> {
+    rx: unsync::UnboundedReceiver<ContextProtocol<A>>,
+    srx: sync::UnboundedReceiver<Proxy<A>>,
+    stx: sync::UnboundedSender<Proxy<A>>,
+    scripts: Vec<Box<Script<A>>>,
+    unexpected: Vec<&'static str>,
+}
+
+impl<A: Actor> Default for Mock<A> {
+    fn default() -> Self {
+        Mock::new()
+    }
+}
+
+impl<A: Actor> Mock<A> {
+
+    /// Create new mock
+    pub fn new() -> Mock<A> {
+        let rx = unsync::unbounded();
+        let (stx, srx) = sync::unbounded();
+        // `process()` drains mailboxes completely
+        rx.identity().budget.set(Some(usize::MAX));
+        // mock addresses share identity
+        srx.set_identity(rx.identity());
+
+        Mock{inner: Rc::new(RefCell::new(MockInner {
+            rx: rx,
+            srx: srx,
+            stx: stx,
+            scripts: Vec::new(),
+            unexpected: Vec::new(),
+        }))}
+    }
+
+    /// Address of the mock
+    pub fn address(&self) -> Address<A> {
+        Address::new(self.inner.borrow_mut().rx.sender())
+    }
+
+    /// Sync address of the mock
+    pub fn sync_address(&self) -> SyncAddress<A> {
+        SyncAddress::new(self.inner.borrow().stx.clone())
+    }
+
+    /// Record messages of type `M`, callers do not receive response.
+    pub fn expect<M: 'static>(&self) where A: MessageHandler<M> {
+        self.with_script(|_: &mut MockScript<A, M>| ());
+    }
+
+    /// Record messages of type `M` and reply to callers with result of `f`
+    pub fn reply<M: 'static, F>(&self, f: F)
+        where A: MessageHandler<M>,
+              F: FnMut(&M) -> Result<A::Item, A::Error> + 'static
+    {
+        self.with_script(move |script: &mut MockScript<A, M>| script.reply = Some(Box::new(f)));
+    }
+
+    /// Copy of recorded messages of type `M`
+    pub fn messages<M>(&self) -> Vec<M> where A: MessageHandler<M>, M: Clone + 'static {
+        self.process();
+        self.with_script(|script: &mut MockScript<A, M>| script.messages.clone())
+    }
+
+    /// Take recorded messages of type `M`
+    pub fn take<M: 'static>(&self) -> Vec<M> where A: MessageHandler<M> {
+        self.process();
+        self.with_script(|script: &mut MockScript<A, M>| script.messages.drain(..).collect())
+    }
+
+    /// Type names of unscripted messages
+    pub fn unexpected(&self) -> Vec<&'static str> {
+        self.process();
+        self.inner.borrow().unexpected.clone()
+    }
+
+    /// Process pending messages, returns number of processed messages
+    pub fn process(&self) -> usize {
+        let inner = &self.inner;
+        future::lazy(|| Ok::<_, ()>(inner.borrow_mut().process())).wait().unwrap()
+    }
+
+    /// Process messages in current arbiter until mock is dropped
+    pub fn start(&self) {
+        Arbiter::handle().spawn(MockDriver{inner: Rc::downgrade(&self.inner)});
+    }
+
+    fn with_script<M, F, R>(&self, f: F) -> R
+        where A: MessageHandler<M>,
+              M: 'static,
+              F: FnOnce(&mut MockScript<A, M>) -> R
+    {
+        let mut inner = self.inner.borrow_mut();
+        let idx = match inner.scripts.iter_mut()
+            .position(|s| s.as_any().downcast_mut::<MockScript<A, M>>().is_some())
+        {
+            Some(idx) => idx,
+            None => {
+                inner.scripts.push(Box::new(MockScript::<A, M>{messages: Vec::new(), reply: None}));
+                inner.scripts.len() - 1
+            }
+        };
+        let script = inner.scripts[idx].as_any().downcast_mut::<MockScript<A, M>>().unwrap();
+        f(script)
+    }
+}
+
+impl<A: Actor> MockInner<A> {
+
+    fn process(&mut self) -> usize {
+        let mut count = 0;
+        loop {
+            let mut proxy = match self.rx.poll() {
+                Ok(Async::Ready(Some(ContextProtocol::SyncAddress(tx)))) => {
+                    let _ = tx.send(SyncAddress::new(self.stx.clone()));
+                    continue
+                }
+                Ok(Async::Ready(Some(msg))) => match msg {
+                    ContextProtocol::Envelope(proxy) => proxy,
+                    _ => continue,
+                },
+                Ok(Async::NotReady) | Ok(Async::Ready(None)) | Err(_) => match self.srx.poll() {
+                    Ok(Async::Ready(Some(proxy))) => proxy,
+                    Ok(Async::NotReady) | Ok(Async::Ready(None)) | Err(_) => return count,
+                },
+            };
+            count += 1;
+
+            let message_type = proxy.0.message_type();
+            let proxy = proxy.0.as_any();
+            if !self.scripts.iter_mut().any(|script| script.handle(proxy)) {
+                self.unexpected.push(message_type);
+            }
+        }
+    }
+}
+
+/// Drives mock in arbiter's event loop
+struct MockDriver<A: Actor> {
+    inner: Weak<RefCell<MockInner<A>>>,
+}
+
+impl<A: Actor> Future for MockDriver<A> {
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<(), ()> {
+        match self.inner.upgrade() {
+            Some(inner) => {
+                inner.borrow_mut().process();
+                Ok(Async::NotReady)
+            }
+            None => Ok(Async::Ready(())),
+        }
+    }
+}
+
+trait Script<A: Actor> {
+    /// Handle message proxy, returns `false` if message type does not match
+    fn handle(&mut self, proxy: &mut Any) -> bool;
+
+    fn as_any(&mut self) -> &mut Any;
+}
+
+struct MockScript<A: MessageHandler<M>, M> {
+    messages: Vec<M>,
+    reply: Option<Box<FnMut(&M) -> Result<A::Item, A::Error>>>,
+}
+
+impl<A: MessageHandler<M>, M> MockScript<A, M> {
+    fn record(&mut self, msg: M) -> Option<Result<A::Item, A::Error>> {
+        let res = self.reply.as_mut().map(|reply| reply(&msg));
+        self.messages.push(msg);
+        res
+    }
+}
+
+impl<A, M> Script<A> for MockScript<A, M> where A: MessageHandler<M>, M: 'static {
+
+    fn handle(&mut self, proxy: &mut Any) -> bool {
+        if let Some(env) = proxy.downcast_mut::<Envelope<A, M>>() {
+            if let Some(msg) = env.take_message() {
+                if let Some(res) = self.record(msg) {
+                    env.respond(res);
+                }
+            }
+            return true
+        }
+        if let Some(env) = proxy.downcast_mut::<SyncEnvelope<A, M>>() {
+            if let Some(msg) = env.take_message() {
+                if let Some(res) = self.record(msg) {
+                    env.respond(res);
+                }
+            }
+            return true
+        }
+        false
+    }
+
+    fn as_any(&mut self) -> &mut Any {
+        self
+    }
+}
+
+/// Mock subscriber, records messages like `Probe` and replies
+/// to `AsyncSubscriber` calls with scripted responses.
+pub struct MockSubscriber<M, I, E> {
+    probe: Probe<M>,
+    reply: Arc<Mutex<Box<FnMut(&M) -> Result<I, E> + Send>>>,
+}
+
+impl<M, I, E> Clone for MockSubscriber<M, I, E> {
+    fn clone(&self) -> Self {
+        MockSubscriber{probe: self.probe.clone(), reply: Arc::clone(&self.reply)}
+    }
+}
+
+impl<M: 'static, I: 'static, E: 'static> MockSubscriber<M, I, E> {
+
+    /// Create mock subscriber, calls are replied with result of `f`
+    pub fn new<F>(f: F) -> MockSubscriber<M, I, E>
+        where F: FnMut(&M) -> Result<I, E> + Send + 'static
+    {
+        MockSubscriber{probe: Probe::new(), reply: Arc::new(Mutex::new(Box::new(f)))}
+    }
+
+    /// Probe that records messages of this subscriber
+    pub fn probe(&self) -> &Probe<M> {
+        &self.probe
+    }
+}
+
+impl<M: 'static, I, E> Subscriber<M> for MockSubscriber<M, I, E> {
+
+    fn send(&self, msg: M) {
+        self.probe.send(msg)
+    }
+
+    fn unbuffered_send(&self, msg: M) -> Result<(), M> {
+        self.probe.unbuffered_send(msg)
+    }
+
+    fn is_closed(&self) -> bool {
+        self.probe.is_closed()
+    }
+}
+
+impl<M: 'static, I, E> AsyncSubscriber<M> for MockSubscriber<M, I, E> {
+    type Future = CallResult<I, E>;
+
+    fn call(&self, msg: M) -> CallResult<I, E> {
+        let (tx, rx) = channel();
+        if !self.probe.is_closed() {
+            let res = {
+                let mut reply = self.reply.lock().unwrap();
+                (&mut **reply)(&msg)
+            };
+            if self.probe.unbuffered_send(msg).is_ok() {
+                let _ = tx.send(res);
+            }
+        }
+        CallResult::new(rx)
+    }
+
+    fn unbuffered_call(&self, msg: M) -> Result<CallResult<I, E>, M> {
+        if self.probe.is_closed() {
+            Err(msg)
+        } else {
+            Ok(self.call(msg))
+        }
+    }
+}
@@ -0,0 +1,387 @@
+//! Deterministic test harness
+//!
+//! [`TestSystem`](struct.TestSystem.html) runs actors of the current thread
+//! with virtual clock. Actix timers (`ActorFuture::timeout()`, fsm state timeouts,
+//! retry and reconnect backoff) do not fire until clock is advanced with
+//! `TestSystem::advance()`, so tests do not depend on real time.
+//! `TestSystem::run_until_idle()` runs event loop until actors of the system
+//! stop processing messages. Event loop waits for IO between idle checks,
+//! IO that does not complete within idle interval is not awaited, see
+//! `TestSystem::set_idle_interval()`.
+//!
+//! [`Probe`](struct.Probe.html) is a fake subscriber, it records all received
+//! messages.
+//!
+//! [`TestContext`](struct.TestContext.html) is a context detached from event loop,
+//! it is used for calling message handlers directly. [`Mock`](struct.Mock.html)
+//! provides addresses that record sent messages and reply with scripted responses.
+//!
+//! Virtual clock is installed only in the thread of the `TestSystem`,
+//! actors started in other arbiters use real time.
+//!
+//! # Examples
+//!
+//! ```rust
+//! extern crate actix;
+//! extern crate futures;
+//!
+//! use std::time::Duration;
+//! use actix::prelude::*;
+//! use actix::test::{Probe, TestSystem};
+//!
+//! struct Tick;
+//!
+//! struct Ticker {
+//!     subscriber: Box<Subscriber<Tick>>,
+//! }
+//!
+//! impl Actor for Ticker {
+//!     fn started(&mut self, ctx: &mut Context<Self>) {
+//!         // never completes, interrupted by timeout
+//!         futures::future::empty::<(), ()>()
+//!             .actfuture()
+//!             .timeout(Duration::from_secs(5), ())
+//!             .then(|_, act: &mut Ticker, _: &mut Context<Ticker>| {
+//!                 act.subscriber.send(Tick);
+//!                 fut::ok(())
+//!             })
+//!             .spawn(ctx);
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let mut sys = TestSystem::new();
+//!
+//!     let probe = Probe::new();
+//!     let addr: Address<_> = Ticker{subscriber: Box::new(probe.clone())}.start();
+//!
+//!     sys.advance(Duration::from_secs(4));
+//!     assert!(probe.is_empty());
+//!
+//!     sys.advance(Duration::from_secs(1));
+//!     assert_eq!(probe.len(), 1);
+//!     assert_eq!(sys.state(&addr), ActorState::Running);
+//! }
+//! ```
+use std::mem;
+use std::any::{Any, TypeId};
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::{Async, Future, Poll};
+use tokio_core::reactor::Timeout;
+
+use actor::Actor;
+use address::{Address, SyncAddress, MessageProxy, Subscriber};
+use arbiter::Arbiter;
+use clock::VirtualClock;
+use context::{ActorState, Context};
+use system::{System, SystemRunner};
+use utils;
+
+mod context;
+mod mock;
+
+pub use self::context::TestContext;
+pub use self::mock::{Mock, MockSubscriber};
+
+/// Number of idle intervals without progress, after that system is idle
+const IDLE_TURNS: usize = 3;
+
+/// Default idle interval in milliseconds
+const IDLE_INTERVAL: u64 = 1;
+
+/// Progress of actors, number of received and handled messages.
+/// Message counters are maintained by actor's metrics, so message
+/// handling has no test specific overhead.
+fn activity() -> usize {
+    System::metrics().actors.iter()
+        .fold(0, |sum, m| sum.wrapping_add(m.received).wrapping_add(m.handled))
+}
+
+/// Actor system with virtual clock
+pub struct TestSystem {
+    runner: SystemRunner,
+    clock: Arc<VirtualClock>,
+    interval: Duration,
+}
+
+impl Default for TestSystem {
+    fn default() -> TestSystem {
+        TestSystem::new()
+    }
+}
+
+impl TestSystem {
+
+    /// Create new system and install virtual clock for current thread
+    pub fn new() -> TestSystem {
+        let runner = System::new("test".to_owned());
+        TestSystem{runner: runner,
+                   clock: VirtualClock::install(),
+                   interval: Duration::from_millis(IDLE_INTERVAL)}
+    }
+
+    /// Set real time interval event loop waits for IO between idle checks.
+    /// System is idle if actors do not make progress during several intervals.
+    /// By default interval is 1 millisecond.
+    pub fn set_idle_interval(&mut self, interval: Duration) {
+        self.interval = interval;
+    }
+
+    /// Virtual time passed since system is created
+    pub fn now(&self) -> Duration {
+        self.clock.now()
+    }
+
+    /// Run event loop until actors of the system stop processing
+    /// messages and expired timers are handled.
+    pub fn run_until_idle(&mut self) {
+        let _ = self.runner.run_until_complete(
+            Idle{clock: Arc::clone(&self.clock), interval: self.interval,
+                 last: None, turns: 0, timeout: None});
+    }
+
+    /// Advance virtual clock. Timers fire in order of deadlines,
+    /// system runs until idle after each timer.
+    pub fn advance(&mut self, dur: Duration) {
+        let target = self.clock.now() + dur;
+        self.run_until_idle();
+
+        while let Some(deadline) = self.clock.next_deadline() {
+            if deadline > target {
+                break
+            }
+            self.clock.set(deadline);
+            self.run_until_idle();
+        }
+        self.clock.set(target);
+        self.run_until_idle();
+    }
+
+    /// Run event loop until future completes
+    pub fn run_until_complete<F>(&mut self, fut: F) -> Result<F::Item, F::Error>
+        where F: Future
+    {
+        self.runner.run_until_complete(fut)
+    }
+
+    /// Lifecycle state of the actor of the current thread, works with
+    /// `Address` and `SyncAddress`. System runs until idle before state check.
+    /// Actor with closed address is reported as `ActorState::Stopped`,
+    /// actor that is alive but does not process messages (for example, it waits
+    /// for response future) is reported as `ActorState::Running`.
+    pub fn state<A: Actor, T: InspectAddress<A>>(&mut self, addr: &T) -> ActorState {
+        self.run_until_idle();
+
+        let state = Arc::new(Mutex::new(None));
+        if addr.inspect(Arc::clone(&state)) {
+            self.run_until_idle();
+        }
+        let res = state.lock().unwrap().take();
+        match res {
+            Some(state) => state,
+            None => if addr.is_closed() {
+                ActorState::Stopped
+            } else {
+                ActorState::Running
+            }
+        }
+    }
+}
+
+/// Address that can be inspected by `TestSystem::state()`
+#[doc(hidden)]
+pub trait InspectAddress<A: Actor> {
+
+    /// Send state probe, returns `false` if address is closed
+    fn inspect(&self, state: Arc<Mutex<Option<ActorState>>>) -> bool;
+
+    fn is_closed(&self) -> bool;
+}
+
+impl<A: Actor> InspectAddress<A> for Address<A> {
+
+    fn inspect(&self, state: Arc<Mutex<Option<ActorState>>>) -> bool {
+        self.send_proxy(StateProbe{state: state, act: PhantomData})
+    }
+
+    fn is_closed(&self) -> bool {
+        Address::is_closed(self)
+    }
+}
+
+impl<A: Actor> InspectAddress<A> for SyncAddress<A> {
+
+    fn inspect(&self, state: Arc<Mutex<Option<ActorState>>>) -> bool {
+        self.send_proxy(StateProbe{state: state, act: PhantomData})
+    }
+
+    fn is_closed(&self) -> bool {
+        SyncAddress::is_closed(self)
+    }
+}
+
+impl Drop for TestSystem {
+    fn drop(&mut self) {
+        VirtualClock::uninstall();
+    }
+}
+
+/// Future resolves after actors do not make progress for several idle intervals.
+/// Event loop is blocked on IO during idle interval.
+struct Idle {
+    clock: Arc<VirtualClock>,
+    interval: Duration,
+    last: Option<usize>,
+    turns: usize,
+    timeout: Option<Timeout>,
+}
+
+impl Future for Idle {
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<(), ()> {
+        loop {
+            if let Some(ref mut timeout) = self.timeout {
+                match timeout.poll() {
+                    Ok(Async::NotReady) => return Ok(Async::NotReady),
+                    Ok(Async::Ready(_)) => (),
+                    Err(err) => {
+                        error!("Can not poll idle timeout: {}", err);
+                        return Err(())
+                    }
+                }
+            }
+
+            // handled messages and expired timers
+            let progress = activity().wrapping_add(self.clock.fired());
+            if self.last == Some(progress) {
+                self.turns += 1;
+            } else {
+                self.last = Some(progress);
+                self.turns = 0;
+            }
+            if self.turns >= IDLE_TURNS {
+                return Ok(Async::Ready(()))
+            }
+
+            match Timeout::new(self.interval, Arbiter::handle()) {
+                Ok(timeout) => self.timeout = Some(timeout),
+                Err(err) => {
+                    error!("Can not start idle timeout: {}", err);
+                    return Err(())
+                }
+            }
+        }
+    }
+}
+
+struct StateProbe<A: Actor> {
+    state: Arc<Mutex<Option<ActorState>>>,
+    act: PhantomData<A>,
+}
+
+impl<A: Actor> MessageProxy for StateProbe<A> {
+    type Actor = A;
+
+    fn handle(&mut self, _: &mut A, ctx: &mut Context<A>) {
+        *self.state.lock().unwrap() = Some(ctx.state());
+    }
+
+    fn message_type(&self) -> &'static str {
+        utils::type_name::<Self>()
+    }
+
+    fn message_type_id(&self) -> TypeId {
+        TypeId::of::<Self>()
+    }
+
+    fn as_any(&mut self) -> &mut Any {
+        self
+    }
+}
+
+/// Fake subscriber, records received messages
+pub struct Probe<M> {
+    inner: Arc<Mutex<ProbeInner<M>>>,
+}
+
+struct ProbeInner<M> {
+    messages: Vec<M>,
+    closed: bool,
+}
+
+impl<M> Clone for Probe<M> {
+    fn clone(&self) -> Self {
+        Probe{inner: Arc::clone(&self.inner)}
+    }
+}
+
+impl<M> Default for Probe<M> {
+    fn default() -> Self {
+        Probe{inner: Arc::new(Mutex::new(ProbeInner{messages: Vec::new(), closed: false}))}
+    }
+}
+
+impl<M: 'static> Probe<M> {
+
+    /// Create new probe
+    pub fn new() -> Probe<M> {
+        Probe::default()
+    }
+
+    /// Boxed subscriber that records messages to this probe
+    pub fn subscriber(&self) -> Box<Subscriber<M> + Send> where M: Send {
+        Box::new(self.clone())
+    }
+
+    /// Number of recorded messages
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().messages.len()
+    }
+
+    /// Returns `true` if probe has not recorded any messages
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Copy of recorded messages
+    pub fn messages(&self) -> Vec<M> where M: Clone {
+        self.inner.lock().unwrap().messages.clone()
+    }
+
+    /// Take recorded messages
+    pub fn take(&self) -> Vec<M> {
+        mem::replace(&mut self.inner.lock().unwrap().messages, Vec::new())
+    }
+
+    /// Close subscriber, messages sent to closed probe are not recorded
+    /// and `is_closed()` returns `true`.
+    pub fn close(&self) {
+        self.inner.lock().unwrap().closed = true;
+    }
+}
+
+impl<M: 'static> Subscriber<M> for Probe<M> {
+
+    fn send(&self, msg: M) {
+        let _ = self.unbuffered_send(msg);
+    }
+
+    fn unbuffered_send(&self, msg: M) -> Result<(), M> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.closed {
+            Err(msg)
+        } else {
+            inner.messages.push(msg);
+            Ok(())
+        }
+    }
+
+    fn is_closed(&self) -> bool {
+        self.inner.lock().unwrap().closed
+    }
+}
@@ -0,0 +1,254 @@
+//! Message tracing
+//!
+//! Each thread has current [`TraceContext`](struct.TraceContext.html).
+//! Messages sent with `Address` or `SyncAddress` capture current trace
+//! context of the sender's thread. During message handling, and during polling
+//! of handler's response future, current trace context is set to
+//! the child span of the captured context. So messages sent by the handler
+//! belong to the same trace, even if they cross thread boundaries.
+//!
+//! [`Interceptor`](trait.Interceptor.html) registered with
+//! `System::add_interceptor()` sees every message before handling and after
+//! response completion, it could be used for logging or for exporting spans
+//! to tracing system.
+//!
+//! # Examples
+//!
+//! ```rust
+//! extern crate actix;
+//!
+//! use actix::prelude::*;
+//! use actix::trace::{self, Interceptor, MessageInfo, TraceContext};
+//!
+//! struct Logger;
+//!
+//! impl Interceptor for Logger {
+//!     fn before(&self, info: &MessageInfo) {
+//!         println!("{} -> {}: {:?}", info.message_type, info.actor_type, info.trace);
+//!     }
+//! }
+//!
+//! struct Ping;
+//!
+//! struct MyActor;
+//!
+//! impl Actor for MyActor {}
+//!
+//! impl MessageResponse<Ping> for MyActor {
+//!     type Item = ();
+//!     type Error = ();
+//! }
+//!
+//! impl MessageHandler<Ping> for MyActor {
+//!     fn handle(&mut self, _: Ping, _: &mut Context<Self>) -> MessageFuture<Self, Ping> {
+//!         // handler runs within child span of the sender's span
+//!         println!("trace: {:?}", trace::current());
+//!         Arbiter::system().send(actix::SystemExit(0));
+//!         ().to_result()
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let sys = System::new("test".to_owned());
+//!     System::add_interceptor(Logger);
+//!
+//!     let addr: Address<_> = MyActor.start();
+//!     trace::with_context(TraceContext::new(), || addr.send(Ping));
+//!
+//!     sys.run();
+//! }
+//! ```
+use std::fmt;
+use std::cell::RefCell;
+use std::sync::Arc;
+use rand;
+
+use actor::Actor;
+use arbiter::Arbiter;
+use context::Context;
+use identity::ActorId;
+use system::System;
+use utils;
+
+thread_local!(static CURRENT: RefCell<Option<TraceContext>> = RefCell::new(None));
+
+/// Trace context, identifies trace and span within trace
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct TraceContext {
+    /// Trace identifier, shared by all spans of the trace
+    pub trace_id: u64,
+    /// Span identifier
+    pub span_id: u64,
+    /// Identifier of the parent span
+    pub parent_id: Option<u64>,
+}
+
+impl TraceContext {
+
+    /// Start new trace
+    pub fn new() -> TraceContext {
+        TraceContext{trace_id: rand::random(), span_id: rand::random(), parent_id: None}
+    }
+
+    /// Create child span of this span
+    pub fn child(&self) -> TraceContext {
+        TraceContext{trace_id: self.trace_id,
+                     span_id: rand::random(),
+                     parent_id: Some(self.span_id)}
+    }
+}
+
+impl Default for TraceContext {
+    fn default() -> TraceContext {
+        TraceContext::new()
+    }
+}
+
+impl fmt::Debug for TraceContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.parent_id {
+            Some(parent) => write!(f, "TraceContext({:016x}:{:016x}:{:016x})",
+                                   self.trace_id, self.span_id, parent),
+            None => write!(f, "TraceContext({:016x}:{:016x})", self.trace_id, self.span_id),
+        }
+    }
+}
+
+/// Current trace context of the thread
+pub fn current() -> Option<TraceContext> {
+    CURRENT.with(|cur| cur.borrow().clone())
+}
+
+/// Set current trace context of the thread, returns previous context
+pub fn set_current(ctx: Option<TraceContext>) -> Option<TraceContext> {
+    CURRENT.with(|cur| ::std::mem::replace(&mut *cur.borrow_mut(), ctx))
+}
+
+/// Run `f` with specific current trace context. Previous context
+/// is restored even if `f` panics.
+pub fn with_context<F, R>(ctx: TraceContext, f: F) -> R where F: FnOnce() -> R {
+    let _guard = Restore(Some(set_current(Some(ctx))));
+    f()
+}
+
+/// Restores previous trace context on drop
+pub(crate) struct Restore(Option<Option<TraceContext>>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        if let Some(prev) = self.0.take() {
+            set_current(prev);
+        }
+    }
+}
+
+/// Message information available to interceptors
+pub struct MessageInfo<'a> {
+    /// Identifier of the actor that handles message
+    pub actor_id: ActorId,
+    /// Type name of the actor
+    pub actor_type: &'static str,
+    /// Type name of the message
+    pub message_type: &'static str,
+    /// Span of message handling, if message is sent within trace
+    pub trace: Option<&'a TraceContext>,
+}
+
+/// Message interceptor
+///
+/// Interceptors are system wide, interceptor's methods are called from
+/// arbiter's threads for every message sent with actor's addresses.
+pub trait Interceptor: Send + Sync + 'static {
+
+    /// Method is called before message handling
+    #[allow(unused_variables)]
+    fn before(&self, info: &MessageInfo) {}
+
+    /// Method is called after message is handled, for asynchronous handlers
+    /// method is called after handler's response future completes.
+    #[allow(unused_variables)]
+    fn after(&self, info: &MessageInfo) {}
+}
+
+pub(crate) type Interceptors = Arc<Vec<Arc<Interceptor>>>;
+
+impl System {
+
+    /// Register system wide message interceptor
+    pub fn add_interceptor<I: Interceptor>(interceptor: I) {
+        Arbiter::system_registry().add_interceptor(Arc::new(interceptor))
+    }
+}
+
+/// Message handling span, used by envelopes. Span is empty if message
+/// is sent outside of trace and there are no registered interceptors.
+pub(crate) struct MessageSpan(Option<SpanInfo>);
+
+struct SpanInfo {
+    actor_id: ActorId,
+    actor_type: &'static str,
+    message_type: &'static str,
+    trace: Option<TraceContext>,
+    interceptors: Option<Interceptors>,
+}
+
+impl MessageSpan {
+
+    pub fn new<A: Actor, M>(trace: Option<TraceContext>, interceptors: Option<Interceptors>,
+                            ctx: &mut Context<A>) -> MessageSpan {
+        if trace.is_none() && interceptors.is_none() {
+            return MessageSpan(None)
+        }
+        MessageSpan(Some(SpanInfo {
+            actor_id: ctx.id(),
+            actor_type: utils::type_name::<A>(),
+            message_type: utils::type_name::<M>(),
+            trace: trace.map(|trace| trace.child()),
+            interceptors: interceptors,
+        }))
+    }
+
+    /// Make span current trace context. Previous context is restored
+    /// when returned guard is dropped, even if handler panics.
+    /// Empty span does not touch current context, it is empty
+    /// in the event loop.
+    pub fn enter(&self) -> Restore {
+        match self.0 {
+            Some(ref span) => Restore(Some(set_current(span.trace.clone()))),
+            None => Restore(None),
+        }
+    }
+
+    pub fn before(&self) {
+        if let Some(ref span) = self.0 {
+            if let Some(ref interceptors) = span.interceptors {
+                let info = span.info();
+                for interceptor in interceptors.iter() {
+                    interceptor.before(&info);
+                }
+            }
+        }
+    }
+
+    pub fn after(&self) {
+        if let Some(ref span) = self.0 {
+            if let Some(ref interceptors) = span.interceptors {
+                let info = span.info();
+                for interceptor in interceptors.iter() {
+                    interceptor.after(&info);
+                }
+            }
+        }
+    }
+}
+
+impl SpanInfo {
+    fn info(&self) -> MessageInfo {
+        MessageInfo {
+            actor_id: self.actor_id,
+            actor_type: self.actor_type,
+            message_type: self.message_type,
+            trace: self.trace.as_ref(),
+        }
+    }
+}
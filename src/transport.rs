@@ -0,0 +1,217 @@
+//! Ready to use transports for framed actors
+//!
+//! This module provides process standard input/output transport and,
+//! with `uds` feature enabled, unix domain socket transports.
+//! All transports implement `AsyncRead` and `AsyncWrite` traits, so they could be
+//! used with [`ActixFramed`](../framed/trait.ActixFramed.html) helper trait.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! extern crate actix;
+//! extern crate futures;
+//! extern crate tokio_io;
+//!
+//! use futures::Sink;
+//! use actix::prelude::*;
+//! use actix::transport::{self, Stdio};
+//! use tokio_io::codec::LinesCodec;
+//!
+//! struct Cli {
+//!     writer: ActixFramedWrite<tokio_io::io::WriteHalf<Stdio>, LinesCodec>,
+//! }
+//!
+//! impl Actor for Cli {}
+//!
+//! impl MessageResponse<String> for Cli {
+//!     type Item = ();
+//!     type Error = ();
+//! }
+//!
+//! impl StreamHandler<String, std::io::Error> for Cli {}
+//!
+//! impl MessageHandler<String, std::io::Error> for Cli {
+//!     // echo lines back to standard output
+//!     fn handle(&mut self, line: String, _: &mut Context<Self>) -> MessageFuture<Self, String> {
+//!         // stdout writes are blocking, flush completes immediately
+//!         let _ = self.writer.start_send(line);
+//!         let _ = self.writer.poll_complete();
+//!         ().to_result()
+//!     }
+//! }
+//!
+//! fn main() {
+//!    // initialize system
+//!    let sys = System::new("test".to_owned());
+//!
+//!    let _: () = transport::start_stdio(|io, ctx| {
+//!        let (reader, writer) = io.actix_framed(LinesCodec::new(), LinesCodec::new());
+//!        ctx.add_stream(reader);
+//!        Cli{writer: writer}
+//!    }).unwrap();
+//!
+//!    sys.run();
+//! }
+//! ```
+use std::io;
+use std::io::{Read, Write};
+use std::thread;
+
+use bytes::Bytes;
+use futures::{Async, Future, Poll, Sink, Stream};
+use futures::sync::mpsc;
+use tokio_io::{AsyncRead, AsyncWrite};
+
+use actor::Actor;
+use address::ActorAddress;
+use builder::ActorBuilder;
+use context::Context;
+
+#[cfg(all(unix, feature="uds"))]
+pub use self::uds::{start_unix_listener, start_unix_stream, UnixListener, UnixStream};
+
+const STDIN_BUFFER_SIZE: usize = 8 * 1024;
+
+/// Process standard input/output transport
+///
+/// Standard input is read in separate thread, each chunk of data
+/// get transferred to the arbiter's thread. Write operations are blocking.
+/// Only one `Stdio` object should be created per process.
+pub struct Stdio {
+    rx: mpsc::Receiver<io::Result<Bytes>>,
+    buf: Bytes,
+    eof: bool,
+}
+
+impl Stdio {
+
+    /// Create new `Stdio` transport, this method starts stdin reader thread.
+    pub fn new() -> io::Result<Stdio> {
+        let (tx, rx) = mpsc::channel(1);
+
+        thread::Builder::new().name("actix-stdin".to_owned()).spawn(move || {
+            let stdin = io::stdin();
+            let mut stdin = stdin.lock();
+            let mut buf = [0u8; STDIN_BUFFER_SIZE];
+            let mut tx = tx;
+
+            loop {
+                let item = match stdin.read(&mut buf) {
+                    Ok(0) => break,
+                    Ok(n) => Ok(Bytes::from(&buf[..n])),
+                    Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(err) => Err(err),
+                };
+                let is_err = item.is_err();
+
+                // blocking send, apply backpressure to reader thread
+                tx = match tx.send(item).wait() {
+                    Ok(tx) => tx,
+                    Err(_) => break,
+                };
+                if is_err {
+                    break
+                }
+            }
+        })?;
+
+        Ok(Stdio{rx: rx, buf: Bytes::new(), eof: false})
+    }
+}
+
+impl Read for Stdio {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if !self.buf.is_empty() {
+                let n = ::std::cmp::min(buf.len(), self.buf.len());
+                buf[..n].copy_from_slice(&self.buf.split_to(n));
+                return Ok(n)
+            }
+            if self.eof {
+                return Ok(0)
+            }
+
+            match self.rx.poll() {
+                Ok(Async::Ready(Some(Ok(chunk)))) => self.buf = chunk,
+                Ok(Async::Ready(Some(Err(err)))) => return Err(err),
+                Ok(Async::Ready(None)) | Err(_) => self.eof = true,
+                Ok(Async::NotReady) => return Err(io::ErrorKind::WouldBlock.into()),
+            }
+        }
+    }
+}
+
+impl AsyncRead for Stdio {}
+
+impl Write for Stdio {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        io::stdout().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stdout().flush()
+    }
+}
+
+impl AsyncWrite for Stdio {
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        self.flush()?;
+        Ok(Async::Ready(()))
+    }
+}
+
+/// Start actor over process standard input/output.
+pub fn start_stdio<A, Addr, F>(f: F) -> io::Result<Addr>
+    where A: Actor + ActorAddress<A, Addr>,
+          F: FnOnce(Stdio, &mut Context<A>) -> A + 'static
+{
+    let io = Stdio::new()?;
+    Ok(A::create(move |ctx| f(io, ctx)))
+}
+
+#[cfg(all(unix, feature="uds"))]
+mod uds {
+    use std::io;
+    use std::rc::Rc;
+    use std::path::Path;
+
+    use futures::Stream;
+    pub use tokio_uds::{UnixListener, UnixStream};
+
+    use actor::Actor;
+    use address::ActorAddress;
+    use arbiter::Arbiter;
+    use builder::ActorBuilder;
+    use context::Context;
+
+    /// Bind unix listener to the `path` and start new actor for each incoming
+    /// connection.
+    pub fn start_unix_listener<A, P, F>(path: P, f: F) -> io::Result<()>
+        where A: Actor,
+              P: AsRef<Path>,
+              F: Fn(UnixStream, &mut Context<A>) -> A + 'static
+    {
+        let lst = UnixListener::bind(path, Arbiter::handle())?;
+        let f = Rc::new(f);
+
+        Arbiter::handle().spawn(
+            lst.incoming()
+                .map_err(|err| error!("Error during accepting unix connection: {}", err))
+                .for_each(move |(stream, _)| {
+                    let f = Rc::clone(&f);
+                    let _: () = A::create(move |ctx| f(stream, ctx));
+                    Ok(())
+                }));
+        Ok(())
+    }
+
+    /// Connect to unix socket at `path` and start actor over this connection.
+    pub fn start_unix_stream<A, Addr, P, F>(path: P, f: F) -> io::Result<Addr>
+        where A: Actor + ActorAddress<A, Addr>,
+              P: AsRef<Path>,
+              F: FnOnce(UnixStream, &mut Context<A>) -> A + 'static
+    {
+        let stream = UnixStream::connect(path, Arbiter::handle())?;
+        Ok(A::create(move |ctx| f(stream, ctx)))
+    }
+}
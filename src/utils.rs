@@ -25,3 +25,17 @@ impl<T> Default for Condition<T> where T: Clone {
         Condition { waiters: Vec::new() }
     }
 }
+
+/// Type name of `T`, used for diagnostics only.
+///
+/// Names are available on rust 1.38 or later, older compilers get
+/// a placeholder, so type names must not be used to identify types.
+#[cfg(actix_type_name)]
+pub(crate) fn type_name<T: ?Sized>() -> &'static str {
+    ::std::any::type_name::<T>()
+}
+
+#[cfg(not(actix_type_name))]
+pub(crate) fn type_name<T: ?Sized>() -> &'static str {
+    "<unknown>"
+}
@@ -11,21 +11,43 @@ use futures::{future, Future};
 use futures::unsync::oneshot::{channel, Sender};
 use tokio_core::reactor::Timeout;
 use actix::prelude::*;
+use actix::test::TestSystem;
+
+#[derive(Default)]
+struct Flags {
+    started: AtomicBool,
+    stopping: AtomicBool,
+    stopped: AtomicBool,
+}
+
+impl Flags {
+    fn check(&self, started: bool, stopping: bool, stopped: bool) {
+        assert_eq!(self.started.load(Ordering::Relaxed), started, "started");
+        assert_eq!(self.stopping.load(Ordering::Relaxed), stopping, "stopping");
+        assert_eq!(self.stopped.load(Ordering::Relaxed), stopped, "stopped");
+    }
+}
 
 struct MyActor{
-    started: Arc<AtomicBool>,
-    stopping: Arc<AtomicBool>,
-    stopped: Arc<AtomicBool>,
+    flags: Arc<Flags>,
     temp: Option<Sender<()>>,
     restore_after_stop: bool,
 }
 
+impl MyActor {
+    fn new(restore_after_stop: bool) -> (MyActor, Arc<Flags>) {
+        let flags = Arc::new(Flags::default());
+        (MyActor{flags: Arc::clone(&flags), temp: None, restore_after_stop: restore_after_stop},
+         flags)
+    }
+}
+
 impl Actor for MyActor {
     fn started(&mut self, _: &mut Context<MyActor>) {
-        self.started.store(true, Ordering::Relaxed);
+        self.flags.started.store(true, Ordering::Relaxed);
     }
     fn stopping(&mut self, ctx: &mut Context<MyActor>) {
-        self.stopping.store(true, Ordering::Relaxed);
+        self.flags.stopping.store(true, Ordering::Relaxed);
 
         if self.restore_after_stop {
             let (tx, rx) = channel();
@@ -36,198 +58,106 @@ impl Actor for MyActor {
         }
     }
     fn stopped(&mut self, _: &mut Context<MyActor>) {
-        self.stopped.store(true, Ordering::Relaxed);
+        self.flags.stopped.store(true, Ordering::Relaxed);
     }
 }
 
 #[test]
 fn test_active_address() {
-    let sys = System::new("test".to_owned());
-
-    let started = Arc::new(AtomicBool::new(false));
-    let stopping = Arc::new(AtomicBool::new(false));
-    let stopped = Arc::new(AtomicBool::new(false));
+    let mut sys = TestSystem::new();
 
-    let _addr: Address<_> = MyActor{
-        started: Arc::clone(&started),
-        stopping: Arc::clone(&stopping),
-        stopped: Arc::clone(&stopped),
-        temp: None, restore_after_stop: false,
-    }.start();
-
-    Arbiter::handle().spawn(
-        Timeout::new(Duration::new(0, 100), Arbiter::handle()).unwrap()
-            .then(|_| {
-                Arbiter::system().send(actix::SystemExit(0));
-                future::result(Ok(()))
-            })
-    );
+    let (act, flags) = MyActor::new(false);
+    let addr: Address<_> = act.start();
 
-    sys.run();
-    assert!(started.load(Ordering::Relaxed), "Not started");
-    assert!(!stopping.load(Ordering::Relaxed), "Stopping");
-    assert!(!stopped.load(Ordering::Relaxed), "Stopped");
+    sys.run_until_idle();
+    flags.check(true, false, false);
+    assert_eq!(sys.state(&addr), ActorState::Running);
 }
 
 #[test]
 fn test_active_sync_address() {
-    let sys = System::new("test".to_owned());
-
-    let started = Arc::new(AtomicBool::new(false));
-    let stopping = Arc::new(AtomicBool::new(false));
-    let stopped = Arc::new(AtomicBool::new(false));
-
-    let _addr: SyncAddress<_> = MyActor{
-        started: Arc::clone(&started),
-        stopping: Arc::clone(&stopping),
-        stopped: Arc::clone(&stopped),
-        temp: None, restore_after_stop: false,
-    }.start();
+    let mut sys = TestSystem::new();
 
-    Arbiter::handle().spawn(
-        Timeout::new(Duration::new(0, 100), Arbiter::handle()).unwrap()
-            .then(|_| {
-                Arbiter::system().send(actix::SystemExit(0));
-                future::result(Ok(()))
-            })
-    );
+    let (act, flags) = MyActor::new(false);
+    let addr: SyncAddress<_> = act.start();
 
-    sys.run();
-    assert!(started.load(Ordering::Relaxed), "Not started");
-    assert!(!stopping.load(Ordering::Relaxed), "Stopping");
-    assert!(!stopped.load(Ordering::Relaxed), "Stopped");
+    sys.run_until_idle();
+    flags.check(true, false, false);
+    assert_eq!(sys.state(&addr), ActorState::Running);
 }
 
 #[test]
 fn test_stop_after_drop_address() {
-    let sys = System::new("test".to_owned());
-
-    let started = Arc::new(AtomicBool::new(false));
-    let stopping = Arc::new(AtomicBool::new(false));
-    let stopped = Arc::new(AtomicBool::new(false));
+    let mut sys = TestSystem::new();
 
-    let addr: Address<_> = MyActor{
-        started: Arc::clone(&started),
-        stopping: Arc::clone(&stopping),
-        stopped: Arc::clone(&stopped),
-        temp: None, restore_after_stop: false,
-    }.start();
+    let (act, flags) = MyActor::new(false);
+    let addr: Address<_> = act.start();
 
-    let started2 = Arc::clone(&started);
-    let stopping2 = Arc::clone(&stopping);
-    let stopped2 = Arc::clone(&stopped);
+    sys.run_until_idle();
+    flags.check(true, false, false);
 
-    Arbiter::handle().spawn_fn(move || {
-        assert!(started2.load(Ordering::Relaxed), "Not started");
-        assert!(!stopping2.load(Ordering::Relaxed), "Stopping");
-        assert!(!stopped2.load(Ordering::Relaxed), "Stopped");
-
-        Timeout::new(Duration::new(0, 100), Arbiter::handle()).unwrap()
-            .then(move |_| {
-                drop(addr);
-                Arbiter::system().send(actix::SystemExit(0));
-                future::result(Ok(()))
-            })
-    });
-
-    sys.run();
-    assert!(started.load(Ordering::Relaxed), "Not started");
-    assert!(stopping.load(Ordering::Relaxed), "Not stopping");
-    assert!(stopped.load(Ordering::Relaxed), "Not stopped");
+    drop(addr);
+    sys.run_until_idle();
+    flags.check(true, true, true);
 }
 
 #[test]
 fn test_stop_after_drop_sync_address() {
-    let sys = System::new("test".to_owned());
-
-    let started = Arc::new(AtomicBool::new(false));
-    let stopping = Arc::new(AtomicBool::new(false));
-    let stopped = Arc::new(AtomicBool::new(false));
-
-    let addr: SyncAddress<_> = MyActor{
-        started: Arc::clone(&started),
-        stopping: Arc::clone(&stopping),
-        stopped: Arc::clone(&stopped),
-        temp: None, restore_after_stop: false,
-    }.start();
-
-    let started2 = Arc::clone(&started);
-    let stopping2 = Arc::clone(&stopping);
-    let stopped2 = Arc::clone(&stopped);
+    let mut sys = TestSystem::new();
 
-    Arbiter::handle().spawn_fn(move || {
-        assert!(started2.load(Ordering::Relaxed), "Not started");
-        assert!(!stopping2.load(Ordering::Relaxed), "Stopping");
-        assert!(!stopped2.load(Ordering::Relaxed), "Stopped");
+    let (act, flags) = MyActor::new(false);
+    let addr: SyncAddress<_> = act.start();
 
-        Timeout::new(Duration::new(0, 100), Arbiter::handle()).unwrap()
-            .then(move |_| {
-                drop(addr);
-                Arbiter::system().send(actix::SystemExit(0));
-                future::result(Ok(()))
-            })
-    });
+    sys.run_until_idle();
+    flags.check(true, false, false);
 
-    sys.run();
-    assert!(started.load(Ordering::Relaxed), "Not started");
-    assert!(stopping.load(Ordering::Relaxed), "Not stopping");
-    assert!(stopped.load(Ordering::Relaxed), "Not stopped");
+    drop(addr);
+    sys.run_until_idle();
+    flags.check(true, true, true);
 }
 
 #[test]
 fn test_stop() {
-    let sys = System::new("test".to_owned());
+    let mut sys = TestSystem::new();
 
-    let started = Arc::new(AtomicBool::new(false));
-    let stopping = Arc::new(AtomicBool::new(false));
-    let stopped = Arc::new(AtomicBool::new(false));
+    let (act, flags) = MyActor::new(false);
+    let _: () = act.start();
 
-    let _: () = MyActor{
-        started: Arc::clone(&started),
-        stopping: Arc::clone(&stopping),
-        stopped: Arc::clone(&stopped),
-        temp: None, restore_after_stop: false,
-    }.start();
+    sys.run_until_idle();
+    flags.check(true, true, true);
+}
 
-    Arbiter::handle().spawn(
-        Timeout::new(Duration::new(0, 100), Arbiter::handle()).unwrap()
-            .then(|_| {
-                Arbiter::system().send(actix::SystemExit(0));
-                future::result(Ok(()))
-            })
-    );
+#[test]
+fn test_stop_restore_after_stopping() {
+    let mut sys = TestSystem::new();
 
-    sys.run();
-    assert!(started.load(Ordering::Relaxed), "Not started");
-    assert!(stopping.load(Ordering::Relaxed), "Not stopping");
-    assert!(stopped.load(Ordering::Relaxed), "Not stopped");
+    let (act, flags) = MyActor::new(true);
+    let _: () = act.start();
+
+    sys.run_until_idle();
+    flags.check(true, true, false);
 }
 
 #[test]
-fn test_stop_restore_after_stopping() {
+fn test_stop_after_drop_address_real_system() {
     let sys = System::new("test".to_owned());
 
-    let started = Arc::new(AtomicBool::new(false));
-    let stopping = Arc::new(AtomicBool::new(false));
-    let stopped = Arc::new(AtomicBool::new(false));
-
-    let _: () = MyActor{
-        started: Arc::clone(&started),
-        stopping: Arc::clone(&stopping),
-        stopped: Arc::clone(&stopped),
-        temp: None, restore_after_stop: true,
-    }.start();
+    let (act, flags) = MyActor::new(false);
+    let addr: Address<_> = act.start();
 
+    let flags2 = Arc::clone(&flags);
     Arbiter::handle().spawn(
-        Timeout::new(Duration::new(0, 100), Arbiter::handle()).unwrap()
+        Timeout::new(Duration::from_millis(10), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                flags2.check(true, false, false);
+                drop(addr);
+                Timeout::new(Duration::from_millis(10), Arbiter::handle()).unwrap()
+            })
             .then(|_| {
                 Arbiter::system().send(actix::SystemExit(0));
                 future::result(Ok(()))
-            })
-    );
+            }));
 
     sys.run();
-    assert!(started.load(Ordering::Relaxed), "Not started");
-    assert!(stopping.load(Ordering::Relaxed), "Not stopping");
-    assert!(!stopped.load(Ordering::Relaxed), "Stopped");
+    flags.check(true, true, true);
 }
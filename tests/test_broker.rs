@@ -0,0 +1,172 @@
+extern crate actix;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use actix::prelude::*;
+use actix::actors::broker::{Broker, Publish, Subscribe, SubscribeLocal, Unsubscribe};
+
+#[derive(Clone)]
+struct Event(usize);
+
+struct Listener {
+    received: Arc<AtomicUsize>,
+}
+
+impl Actor for Listener {}
+
+impl MessageResponse<Event> for Listener {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<Event> for Listener {
+    fn handle(&mut self, msg: Event, _: &mut Context<Self>) -> MessageFuture<Self, Event> {
+        self.received.fetch_add(msg.0, Ordering::Relaxed);
+        if msg.0 == 100 {
+            Arbiter::system().send(actix::SystemExit(0));
+        }
+        ().to_result()
+    }
+}
+
+struct Stop;
+
+impl MessageResponse<Stop> for Listener {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<Stop> for Listener {
+    fn handle(&mut self, _: Stop, ctx: &mut Context<Self>) -> MessageFuture<Self, Stop> {
+        ctx.stop();
+        ().to_result()
+    }
+}
+
+/// Subscriber counts delivered messages, could be closed from the test
+#[derive(Clone)]
+struct Probe {
+    sent: Arc<AtomicUsize>,
+    closed: Arc<AtomicBool>,
+}
+
+impl Subscriber<Event> for Probe {
+    fn send(&self, _: Event) {
+        self.sent.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn unbuffered_send(&self, msg: Event) -> Result<(), Event> {
+        self.send(msg);
+        Ok(())
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Relaxed)
+    }
+}
+
+#[test]
+fn test_broker_topics() {
+    let sys = System::new("test".to_owned());
+
+    let all = Arc::new(AtomicUsize::new(0));
+    let topic = Arc::new(AtomicUsize::new(0));
+
+    let broker = Broker::<Event>::from_registry();
+
+    let addr: SyncAddress<_> = Listener{received: Arc::clone(&all)}.start();
+    broker.send(Subscribe::all(addr.subscriber()));
+    let addr: SyncAddress<_> = Listener{received: Arc::clone(&topic)}.start();
+    broker.send(Subscribe::topic("a", addr.subscriber()));
+
+    broker.send(Publish::topic("a", Event(1)));
+    broker.send(Publish::topic("b", Event(10)));
+    broker.send(Publish::new(Event(100)));
+
+    sys.run();
+    assert_eq!(all.load(Ordering::Relaxed), 111);
+    assert_eq!(topic.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn test_broker_unsubscribe() {
+    let mut sys = System::new("test".to_owned());
+
+    let first = Arc::new(AtomicUsize::new(0));
+    let second = Arc::new(AtomicUsize::new(0));
+
+    let broker = Broker::<Event>::from_registry();
+
+    let addr: SyncAddress<_> = Listener{received: Arc::clone(&first)}.start();
+    let id = sys.run_until_complete(
+        broker.call_fut(Subscribe::all(addr.subscriber()))).unwrap().unwrap();
+    let addr: SyncAddress<_> = Listener{received: Arc::clone(&second)}.start();
+    broker.send(Subscribe::all(addr.subscriber()));
+
+    broker.send(Unsubscribe(id));
+    broker.send(Publish::new(Event(1)));
+    broker.send(Publish::new(Event(100)));
+
+    sys.run();
+    assert_eq!(first.load(Ordering::Relaxed), 0);
+    assert_eq!(second.load(Ordering::Relaxed), 101);
+}
+
+#[test]
+fn test_broker_prune_closed() {
+    let mut sys = System::new("test".to_owned());
+
+    let probe = Probe{sent: Arc::new(AtomicUsize::new(0)),
+                      closed: Arc::new(AtomicBool::new(false))};
+    let broker = Broker::<Event>::from_registry();
+    broker.send(Subscribe::all(Box::new(probe.clone())));
+
+    sys.run_until_complete(broker.call_fut(Publish::new(Event(1)))).unwrap().unwrap();
+    assert_eq!(probe.sent.load(Ordering::Relaxed), 1);
+
+    // closed subscriber does not receive messages and get removed
+    probe.closed.store(true, Ordering::Relaxed);
+    sys.run_until_complete(broker.call_fut(Publish::new(Event(2)))).unwrap().unwrap();
+    probe.closed.store(false, Ordering::Relaxed);
+    sys.run_until_complete(broker.call_fut(Publish::new(Event(3)))).unwrap().unwrap();
+    assert_eq!(probe.sent.load(Ordering::Relaxed), 1);
+
+    // sync address of stopped actor is closed without failed send
+    let received = Arc::new(AtomicUsize::new(0));
+    let addr: SyncAddress<_> = Listener{received: Arc::clone(&received)}.start();
+    broker.send(Subscribe::all(addr.subscriber()));
+    sys.run_until_complete(addr.call_fut(Stop)).unwrap().unwrap();
+    assert!(addr.is_closed());
+}
+
+#[test]
+fn test_broker_local_subscriber() {
+    let sys = System::new("test".to_owned());
+
+    let received = Arc::new(AtomicUsize::new(0));
+
+    let broker = Broker::<Event>::from_registry();
+    let addr: Address<_> = Listener{received: Arc::clone(&received)}.start();
+    broker.send(SubscribeLocal::topic("a", addr.subscriber()));
+    broker.send(Publish::topic("a", Event(100)));
+
+    sys.run();
+    assert_eq!(received.load(Ordering::Relaxed), 100);
+}
+
+#[test]
+fn test_broker_system_registry() {
+    let sys = System::new("test".to_owned());
+
+    let received = Arc::new(AtomicUsize::new(0));
+
+    let broker = Broker::<Event>::from_system_registry();
+    assert_eq!(broker, Broker::<Event>::from_system_registry());
+
+    let addr: SyncAddress<_> = Listener{received: Arc::clone(&received)}.start();
+    broker.send(Subscribe::all(addr.subscriber()));
+    Broker::<Event>::from_system_registry().send(Publish::new(Event(100)));
+
+    sys.run();
+    assert_eq!(received.load(Ordering::Relaxed), 100);
+}
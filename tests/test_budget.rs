@@ -0,0 +1,158 @@
+extern crate actix;
+extern crate futures;
+
+use std::sync::{Arc, Mutex};
+use futures::stream;
+use actix::prelude::*;
+
+struct Msg(usize);
+struct AsyncMsg(usize);
+
+struct Worker {
+    name: &'static str,
+    budget: Option<usize>,
+    handled: usize,
+    log: Arc<Mutex<Vec<(&'static str, usize)>>>,
+}
+
+impl Worker {
+    fn new(name: &'static str, budget: Option<usize>,
+           log: &Arc<Mutex<Vec<(&'static str, usize)>>>) -> Worker {
+        Worker{name: name, budget: budget, handled: 0, log: Arc::clone(log)}
+    }
+}
+
+impl Actor for Worker {
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.set_message_budget(self.budget);
+    }
+}
+
+impl StreamHandler<Msg> for Worker {}
+
+impl MessageResponse<Msg> for Worker {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<Msg> for Worker {
+    fn handle(&mut self, msg: Msg, _: &mut Context<Self>) -> MessageFuture<Self, Msg> {
+        self.log.lock().unwrap().push((self.name, msg.0));
+        self.handled += 1;
+        if self.name == "busy" && self.handled == 100 {
+            Arbiter::system().send(actix::SystemExit(0));
+        }
+        ().to_result()
+    }
+}
+
+impl MessageResponse<AsyncMsg> for Worker {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<AsyncMsg> for Worker {
+    fn handle(&mut self, msg: AsyncMsg, ctx: &mut Context<Self>)
+              -> MessageFuture<Self, AsyncMsg>
+    {
+        let _ = <Worker as MessageHandler<Msg>>::handle(self, Msg(msg.0), ctx);
+        fut::ok(()).into()
+    }
+}
+
+fn position(log: &Arc<Mutex<Vec<(&'static str, usize)>>>) -> usize {
+    let log = log.lock().unwrap();
+    assert_eq!(log.len(), 101);
+    log.iter().position(|&(name, _)| name == "other").unwrap()
+}
+
+#[test]
+fn test_actor_budget() {
+    let sys = System::new("test".to_owned());
+
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let busy: Address<_> = Worker::new("busy", Some(10), &log).start();
+    let other: Address<_> = Worker::new("other", None, &log).start();
+
+    for i in 0..100 {
+        busy.send(Msg(i));
+    }
+    other.send(Msg(0));
+    sys.run();
+
+    // busy actor yields after 10 messages
+    assert_eq!(position(&log), 10);
+}
+
+#[test]
+fn test_arbiter_budget() {
+    let sys = System::new("test".to_owned());
+    assert_eq!(Arbiter::message_budget(), 256);
+
+    Arbiter::set_message_budget(5);
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let busy: Address<_> = Worker::new("busy", None, &log).start();
+    let other: Address<_> = Worker::new("other", None, &log).start();
+
+    for i in 0..100 {
+        busy.send(Msg(i));
+    }
+    other.send(Msg(0));
+    sys.run();
+
+    assert_eq!(position(&log), 5);
+}
+
+#[test]
+fn test_budget_shared_by_mailboxes() {
+    let sys = System::new("test".to_owned());
+
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let (busy, sbusy): (Address<_>, SyncAddress<_>) =
+        Worker::new("busy", Some(10), &log).start();
+    let other: Address<_> = Worker::new("other", None, &log).start();
+
+    for i in 0..50 {
+        busy.send(Msg(i));
+        sbusy.send(Msg(50 + i));
+    }
+    other.send(Msg(0));
+    sys.run();
+
+    // both mailboxes draw from one budget
+    assert_eq!(position(&log), 10);
+}
+
+#[test]
+fn test_stream_budget() {
+    let sys = System::new("test".to_owned());
+
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let items = stream::iter_ok::<_, ()>((0..100).map(Msg));
+    let _: () = Worker::new("busy", Some(10), &log).start_with(items);
+    let other: Address<_> = Worker::new("other", None, &log).start();
+
+    other.send(Msg(0));
+    sys.run();
+
+    // stream items draw from actor's budget
+    assert_eq!(position(&log), 10);
+}
+
+#[test]
+fn test_async_response_counted_once() {
+    let sys = System::new("test".to_owned());
+
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let busy: Address<_> = Worker::new("busy", Some(10), &log).start();
+    let other: Address<_> = Worker::new("other", None, &log).start();
+
+    for i in 0..100 {
+        busy.send(AsyncMsg(i));
+    }
+    other.send(Msg(0));
+    sys.run();
+
+    // response futures do not draw from the budget again
+    assert_eq!(position(&log), 10);
+}
@@ -0,0 +1,143 @@
+extern crate actix;
+extern crate futures;
+
+use std::net;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+use futures::{future, Future};
+use actix::prelude::*;
+use actix::actors::connector::{Connect, Connector, ConnectorError,
+                               Resolver, ResolverFuture};
+use actix::test::TestSystem;
+
+// Counts resolve attempts, every attempt fails
+struct CountingResolver(Arc<AtomicUsize>);
+
+impl Resolver for CountingResolver {
+    fn resolve(&mut self, _: &str, _: u16) -> ResolverFuture {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        Box::new(future::err(ConnectorError::NoRecords))
+    }
+}
+
+
+#[test]
+fn test_connect() {
+    let sys = System::new("test".to_owned());
+
+    let srv = net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = srv.local_addr().unwrap();
+
+    let connected = Arc::new(AtomicBool::new(false));
+    let connected2 = Arc::clone(&connected);
+
+    let connector: SyncAddress<_> = Connector::default().start();
+    Arbiter::handle().spawn(
+        connector.call_fut(Connect::host(format!("127.0.0.1:{}", addr.port())))
+            .then(move |res| {
+                if let Ok(Ok(_)) = res {
+                    connected2.store(true, Ordering::Relaxed);
+                }
+                Arbiter::system().send(actix::SystemExit(0));
+                future::result(Ok(()))
+            })
+    );
+
+    sys.run();
+    assert!(connected.load(Ordering::Relaxed), "Not connected");
+}
+
+#[test]
+fn test_connect_invalid_input() {
+    let sys = System::new("test".to_owned());
+
+    let invalid = Arc::new(AtomicBool::new(false));
+    let invalid2 = Arc::clone(&invalid);
+
+    let connector: SyncAddress<_> = Connector::default().start();
+    Arbiter::handle().spawn(
+        connector.call_fut(Connect::host("127.0.0.1"))
+            .then(move |res| {
+                if let Ok(Err(ConnectorError::InvalidInput)) = res {
+                    invalid2.store(true, Ordering::Relaxed);
+                }
+                Arbiter::system().send(actix::SystemExit(0));
+                future::result(Ok(()))
+            })
+    );
+
+    sys.run();
+    assert!(invalid.load(Ordering::Relaxed), "Address is valid");
+}
+
+#[test]
+fn test_reconnect() {
+    let sys = System::new("test".to_owned());
+
+    // reserve port and close listener
+    let addr = net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+
+    let failed = Arc::new(AtomicBool::new(false));
+    let failed2 = Arc::clone(&failed);
+
+    let connector: SyncAddress<_> = Connector::default()
+        .retries(2)
+        .backoff(Duration::from_millis(1), Duration::from_millis(5))
+        .start();
+    Arbiter::handle().spawn(
+        connector.call_fut(Connect::host(format!("127.0.0.1:{}", addr.port())))
+            .then(move |res| {
+                if let Ok(Err(_)) = res {
+                    failed2.store(true, Ordering::Relaxed);
+                }
+                Arbiter::system().send(actix::SystemExit(0));
+                future::result(Ok(()))
+            })
+    );
+
+    sys.run();
+    assert!(failed.load(Ordering::Relaxed), "Connected");
+}
+
+#[test]
+fn test_reconnect_backoff() {
+    let mut sys = TestSystem::new();
+
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let failed = Arc::new(AtomicBool::new(false));
+    let failed2 = Arc::clone(&failed);
+
+    let connector: SyncAddress<_> = Connector::new(CountingResolver(Arc::clone(&attempts)))
+        .retries(3)
+        .backoff(Duration::from_millis(100), Duration::from_millis(250))
+        .start();
+    Arbiter::handle().spawn(
+        connector.call_fut(Connect::host("localhost:8080"))
+            .then(move |res| {
+                if let Ok(Err(ConnectorError::NoRecords)) = res {
+                    failed2.store(true, Ordering::Relaxed);
+                }
+                future::result(Ok(()))
+            })
+    );
+
+    // initial attempt
+    sys.run_until_idle();
+    assert_eq!(attempts.load(Ordering::Relaxed), 1);
+
+    // backoff doubles after each attempt, but does not exceed max backoff
+    for &(delay, attempt) in &[(100, 2), (200, 3), (250, 4)] {
+        sys.advance(Duration::from_millis(delay - 1));
+        assert_eq!(attempts.load(Ordering::Relaxed), attempt - 1);
+        assert!(!failed.load(Ordering::Relaxed));
+
+        sys.advance(Duration::from_millis(1));
+        assert_eq!(attempts.load(Ordering::Relaxed), attempt);
+    }
+
+    // retries are exhausted
+    assert!(failed.load(Ordering::Relaxed), "Connected");
+    sys.advance(Duration::from_secs(10));
+    assert_eq!(attempts.load(Ordering::Relaxed), 4);
+}
@@ -0,0 +1,174 @@
+extern crate actix;
+extern crate futures;
+extern crate tokio_core;
+
+use std::thread;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use futures::Future;
+use tokio_core::reactor::Timeout;
+use actix::prelude::*;
+use actix::actors::dead_letters::{DeadLetter, DeadLetterReason, DeadLetters, Subscribe};
+
+struct Ping;
+
+struct MyActor;
+
+impl Actor for MyActor {
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.stop();
+    }
+}
+
+impl MessageResponse<Ping> for MyActor {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<Ping> for MyActor {
+    fn handle(&mut self, _: Ping, _: &mut Context<Self>) -> MessageFuture<Self, Ping> {
+        ().to_result()
+    }
+}
+
+/// Collects dead letters, stops system after `expected` letters
+struct Collector(Arc<Mutex<Vec<DeadLetter>>>, usize);
+
+impl Actor for Collector {}
+
+impl MessageResponse<DeadLetter> for Collector {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<DeadLetter> for Collector {
+    fn handle(&mut self, msg: DeadLetter, _: &mut Context<Self>)
+              -> MessageFuture<Self, DeadLetter>
+    {
+        let mut letters = self.0.lock().unwrap();
+        letters.push(msg);
+        if letters.len() >= self.1 {
+            Arbiter::system().send(actix::SystemExit(0));
+        }
+        ().to_result()
+    }
+}
+
+fn subscribe(expected: usize) -> Arc<Mutex<Vec<DeadLetter>>> {
+    let letters = Arc::new(Mutex::new(Vec::new()));
+    let collector: SyncAddress<_> = Collector(Arc::clone(&letters), expected).start();
+    DeadLetters::from_registry().send(Subscribe(collector.subscriber()));
+    letters
+}
+
+/// run `f` after started actors are stopped
+fn later<F>(f: F) where F: FnOnce() + 'static {
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(50), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                f();
+                Ok(())
+            }));
+}
+
+#[test]
+fn test_address_dead_letter() {
+    let sys = System::new("test".to_owned());
+    let letters = subscribe(1);
+
+    let addr: Address<_> = MyActor.start();
+    let id = addr.id();
+    later(move || addr.send(Ping));
+
+    sys.run();
+
+    let letters = letters.lock().unwrap();
+    assert_eq!(letters.len(), 1);
+    assert_eq!(letters[0].target_id, id);
+    // type names are placeholders on rust older than 1.38
+    if cfg!(actix_type_name) {
+        assert_eq!(letters[0].target_type, "test_dead_letters::MyActor");
+        assert_eq!(letters[0].message_type, "test_dead_letters::Ping");
+    }
+    assert_eq!(letters[0].reason, DeadLetterReason::Stopped);
+}
+
+#[test]
+fn test_sync_address_dead_letter() {
+    let sys = System::new("test".to_owned());
+    let letters = subscribe(1);
+
+    let addr: SyncAddress<_> = MyActor.start();
+    let id = addr.id();
+    later(move || {
+        assert!(!addr.is_closed());
+        addr.send(Ping);
+        assert!(addr.is_closed());
+    });
+
+    sys.run();
+
+    let letters = letters.lock().unwrap();
+    assert_eq!(letters.len(), 1);
+    assert_eq!(letters[0].target_id, id);
+    assert_eq!(letters[0].reason, DeadLetterReason::Stopped);
+}
+
+struct Block;
+
+struct Supervised;
+
+impl Actor for Supervised {}
+
+impl SupervisedActor for Supervised {}
+
+impl MessageResponse<Block> for Supervised {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<Block> for Supervised {
+    fn handle(&mut self, _: Block, _: &mut Context<Self>) -> MessageFuture<Self, Block> {
+        // messages sent meanwhile are queued in supervisor's mailbox,
+        // arbiter's event loop and supervisor get dropped during unwinding
+        thread::sleep(Duration::from_millis(100));
+        panic!("arbiter failed");
+    }
+}
+
+impl MessageResponse<Ping> for Supervised {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<Ping> for Supervised {
+    fn handle(&mut self, _: Ping, _: &mut Context<Self>) -> MessageFuture<Self, Ping> {
+        ().to_result()
+    }
+}
+
+#[test]
+fn test_supervisor_dead_letters() {
+    let sys = System::new("test".to_owned());
+    let letters = subscribe(2);
+
+    let arbiter = Arbiter::new(Some("supervisor".to_owned()));
+    let addr = Supervisor::start_in(arbiter, false, |_| Supervised).unwrap();
+    let id = addr.id();
+    addr.send(Block);
+    addr.send(Ping);
+    addr.send(Ping);
+
+    sys.run();
+
+    let letters = letters.lock().unwrap();
+    assert_eq!(letters.len(), 2);
+    for letter in letters.iter() {
+        assert_eq!(letter.target_id, id);
+        if cfg!(actix_type_name) {
+            assert_eq!(letter.target_type, "test_dead_letters::Supervised");
+            assert_eq!(letter.message_type, "test_dead_letters::Ping");
+        }
+        assert_eq!(letter.reason, DeadLetterReason::SupervisorStopped);
+    }
+}
@@ -0,0 +1,157 @@
+extern crate actix;
+extern crate futures;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use futures::Future;
+use actix::prelude::*;
+use actix::test::{Mock, TestSystem};
+
+struct Small(u8);
+struct Large([u64; 64]);
+
+struct Tracked(Arc<AtomicUsize>);
+struct Die;
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+struct MyActor;
+
+impl Actor for MyActor {}
+
+impl MessageResponse<Small> for MyActor {
+    type Item = u64;
+    type Error = ();
+}
+
+impl MessageHandler<Small> for MyActor {
+    fn handle(&mut self, msg: Small, _: &mut Context<Self>) -> MessageFuture<Self, Small> {
+        u64::from(msg.0).to_result()
+    }
+}
+
+impl MessageResponse<Large> for MyActor {
+    type Item = u64;
+    type Error = ();
+}
+
+impl MessageHandler<Large> for MyActor {
+    fn handle(&mut self, msg: Large, _: &mut Context<Self>) -> MessageFuture<Self, Large> {
+        msg.0.iter().sum::<u64>().to_result()
+    }
+}
+
+impl MessageResponse<Tracked> for MyActor {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<Tracked> for MyActor {
+    fn handle(&mut self, _: Tracked, _: &mut Context<Self>) -> MessageFuture<Self, Tracked> {
+        ().to_result()
+    }
+}
+
+impl MessageResponse<Die> for MyActor {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<Die> for MyActor {
+    fn handle(&mut self, _: Die, ctx: &mut Context<Self>) -> MessageFuture<Self, Die> {
+        ctx.stop();
+        ().to_result()
+    }
+}
+
+fn counters(num: usize) -> Vec<Arc<AtomicUsize>> {
+    (0..num).map(|_| Arc::new(AtomicUsize::new(0))).collect()
+}
+
+fn assert_dropped_once(counters: &[Arc<AtomicUsize>]) {
+    for (idx, counter) in counters.iter().enumerate() {
+        assert_eq!(counter.load(Ordering::SeqCst), 1, "message {}", idx);
+    }
+}
+
+#[test]
+fn test_small_and_large_messages() {
+    let mut sys = TestSystem::new();
+
+    // small envelopes are stored inline, large ones are boxed
+    let (addr, saddr): (Address<_>, SyncAddress<_>) = MyActor.start();
+    let small = addr.call_fut(Small(1));
+    let large = addr.call_fut(Large([2; 64]));
+    let res = sys.run_until_complete(small.join(large));
+    assert_eq!(res.unwrap(), (Ok(1), Ok(128)));
+
+    let small = saddr.call_fut(Small(1));
+    let large = saddr.call_fut(Large([2; 64]));
+    let res = sys.run_until_complete(small.join(large));
+    assert_eq!(res.unwrap(), (Ok(1), Ok(128)));
+}
+
+#[test]
+fn test_undelivered_messages_dropped() {
+    let dropped = Arc::new(AtomicUsize::new(0));
+
+    let mock = Mock::<MyActor>::new();
+    mock.address().send(Tracked(Arc::clone(&dropped)));
+    mock.sync_address().send(Tracked(Arc::clone(&dropped)));
+    assert_eq!(dropped.load(Ordering::SeqCst), 0);
+
+    drop(mock);
+    assert_eq!(dropped.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_handled_envelopes_dropped_once() {
+    let mut sys = TestSystem::new();
+    let dropped = counters(12);
+
+    let (addr, saddr): (Address<_>, SyncAddress<_>) = MyActor.start();
+    for (idx, counter) in dropped[..10].iter().enumerate() {
+        if idx % 2 == 0 {
+            addr.send(Tracked(Arc::clone(counter)));
+        } else {
+            saddr.send(Tracked(Arc::clone(counter)));
+        }
+    }
+
+    // envelopes of calls also hold response channel
+    let call = addr.call_fut(Tracked(Arc::clone(&dropped[10])));
+    let sync_call = saddr.call_fut(Tracked(Arc::clone(&dropped[11])));
+    let res = sys.run_until_complete(call.join(sync_call));
+    assert_eq!(res.unwrap(), (Ok(()), Ok(())));
+
+    sys.run_until_idle();
+    assert_dropped_once(&dropped);
+}
+
+#[test]
+fn test_queued_envelopes_dropped_once() {
+    let mut sys = TestSystem::new();
+    let dropped = counters(10);
+
+    // actor stops before queued messages are handled,
+    // envelopes are dropped with actor's mailboxes
+    let (addr, saddr): (Address<_>, SyncAddress<_>) = MyActor.start();
+    addr.send(Die);
+    for (idx, counter) in dropped.iter().enumerate() {
+        if idx % 2 == 0 {
+            addr.send(Tracked(Arc::clone(counter)));
+        } else {
+            saddr.send(Tracked(Arc::clone(counter)));
+        }
+    }
+    drop(addr);
+    drop(saddr);
+
+    sys.run_until_idle();
+    drop(sys);
+    assert_dropped_once(&dropped);
+}
@@ -0,0 +1,154 @@
+extern crate actix;
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use actix::prelude::*;
+use actix::fsm::{Fsm, FsmActor, FsmHandler};
+use actix::test::TestSystem;
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+enum State {
+    Idle,
+    Connecting,
+    Connected,
+}
+
+struct Connect;
+
+struct Protocol {
+    fsm: Fsm<Protocol>,
+    events: Arc<Mutex<Vec<String>>>,
+    // leave `Connecting` state right after entering it
+    bounce: bool,
+}
+
+impl Protocol {
+    fn event(&self, event: String) {
+        self.events.lock().unwrap().push(event);
+    }
+}
+
+impl Actor for Protocol {}
+
+impl FsmActor for Protocol {
+    type State = State;
+
+    fn fsm(&mut self) -> &mut Fsm<Self> {
+        &mut self.fsm
+    }
+
+    fn on_enter(&mut self, state: &State, ctx: &mut Context<Self>) {
+        self.event(format!("enter {:?}", state));
+        if self.bounce && *state == State::Connecting {
+            self.transition(State::Idle, ctx);
+        }
+    }
+
+    fn on_exit(&mut self, state: &State, _: &mut Context<Self>) {
+        self.event(format!("exit {:?}", state));
+    }
+
+    fn on_timeout(&mut self, state: &State, ctx: &mut Context<Self>) {
+        self.event(format!("timeout {:?}", state));
+        if *state == State::Connecting {
+            self.transition(State::Idle, ctx);
+        }
+        Arbiter::system().send(actix::SystemExit(0));
+    }
+}
+
+impl MessageResponse<Connect> for Protocol {
+    type Item = ();
+    type Error = ();
+}
+
+impl FsmHandler<Connect> for Protocol {
+    fn handle_state(&mut self, state: &State, msg: Connect, ctx: &mut Context<Self>)
+                    -> Result<MessageFuture<Self, Connect>, Connect>
+    {
+        match *state {
+            State::Idle => {
+                self.transition_with_timeout(State::Connecting, Duration::from_millis(10), ctx);
+                Ok(().to_result())
+            }
+            _ => Err(msg),
+        }
+    }
+
+    fn unhandled(&mut self, state: &State, _: Connect, _: &mut Context<Self>)
+                 -> MessageFuture<Self, Connect>
+    {
+        self.event(format!("unhandled {:?}", state));
+        ().to_error()
+    }
+}
+
+impl MessageHandler<Connect> for Protocol {
+    fn handle(&mut self, msg: Connect, ctx: &mut Context<Self>) -> MessageFuture<Self, Connect> {
+        self.fsm_handle(msg, ctx)
+    }
+}
+
+struct Done;
+
+impl MessageResponse<Done> for Protocol {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<Done> for Protocol {
+    fn handle(&mut self, _: Done, ctx: &mut Context<Self>) -> MessageFuture<Self, Done> {
+        // stale timeout of `Connecting` state must not fire
+        self.transition_with_timeout(State::Connected, Duration::from_millis(50), ctx);
+        ().to_result()
+    }
+}
+
+#[test]
+fn test_fsm() {
+    let sys = System::new("test".to_owned());
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let addr: Address<_> = Protocol{
+        fsm: Fsm::new(State::Idle), events: Arc::clone(&events), bounce: false}.start();
+
+    addr.send(Connect);
+    addr.send(Connect);
+
+    sys.run();
+    assert_eq!(*events.lock().unwrap(),
+               vec!["exit Idle", "enter Connecting", "unhandled Connecting",
+                    "timeout Connecting", "exit Connecting", "enter Idle"]);
+}
+
+#[test]
+fn test_fsm_stale_timeout() {
+    let sys = System::new("test".to_owned());
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let addr: Address<_> = Protocol{
+        fsm: Fsm::new(State::Idle), events: Arc::clone(&events), bounce: false}.start();
+
+    addr.send(Connect);
+    addr.send(Done);
+
+    sys.run();
+    assert_eq!(*events.lock().unwrap(),
+               vec!["exit Idle", "enter Connecting",
+                    "exit Connecting", "enter Connected", "timeout Connected"]);
+}
+
+#[test]
+fn test_fsm_transition_on_enter() {
+    let mut sys = TestSystem::new();
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let addr: Address<_> = Protocol{
+        fsm: Fsm::new(State::Idle), events: Arc::clone(&events), bounce: true}.start();
+
+    // transition made by `on_enter` cancels timeout of the entered state
+    addr.send(Connect);
+    sys.advance(Duration::from_millis(100));
+    assert_eq!(*events.lock().unwrap(),
+               vec!["exit Idle", "enter Connecting", "exit Connecting", "enter Idle"]);
+}
@@ -0,0 +1,163 @@
+extern crate actix;
+extern crate futures;
+extern crate tokio_core;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use futures::{future, stream};
+use tokio_core::reactor::Timeout;
+use actix::prelude::*;
+
+struct MyActor {
+    joined: Arc<AtomicBool>,
+    selected: Arc<AtomicBool>,
+    timeout: Arc<AtomicBool>,
+}
+
+impl Actor for MyActor {
+    fn started(&mut self, ctx: &mut Context<MyActor>) {
+        fut::ok::<u32, (), MyActor>(1)
+            .join(fut::ok(2))
+            .map(|res, act: &mut MyActor, _: &mut Context<MyActor>| {
+                act.joined.store(res == (1, 2), Ordering::Relaxed);
+            })
+            .spawn(ctx);
+
+        future::empty::<u32, ()>()
+            .actfuture()
+            .select(fut::ok(3))
+            .map(|(res, _), act: &mut MyActor, _: &mut Context<MyActor>| {
+                act.selected.store(res == 3, Ordering::Relaxed);
+            })
+            .map_err(|_, _: &mut MyActor, _: &mut Context<MyActor>| ())
+            .spawn(ctx);
+
+        Timeout::new(Duration::new(10, 0), Arbiter::handle()).unwrap()
+            .actfuture()
+            .map_err(|_, _: &mut MyActor, _: &mut Context<MyActor>| ())
+            .timeout(Duration::new(0, 1000), ())
+            .or_else(|_, act: &mut MyActor, _: &mut Context<MyActor>| {
+                act.timeout.store(true, Ordering::Relaxed);
+                Arbiter::system().send(actix::SystemExit(0));
+                fut::ok(())
+            })
+            .boxed()
+            .spawn(ctx);
+    }
+}
+
+#[test]
+fn test_combinators() {
+    let sys = System::new("test".to_owned());
+
+    let joined = Arc::new(AtomicBool::new(false));
+    let selected = Arc::new(AtomicBool::new(false));
+    let timeout = Arc::new(AtomicBool::new(false));
+
+    let _: () = MyActor{
+        joined: Arc::clone(&joined),
+        selected: Arc::clone(&selected),
+        timeout: Arc::clone(&timeout),
+    }.start();
+
+    sys.run();
+    assert!(joined.load(Ordering::Relaxed), "Not joined");
+    assert!(selected.load(Ordering::Relaxed), "Not selected");
+    assert!(timeout.load(Ordering::Relaxed), "Not timed out");
+}
+
+struct StreamActor {
+    sum: Arc<AtomicBool>,
+}
+
+impl Actor for StreamActor {
+    fn started(&mut self, ctx: &mut Context<StreamActor>) {
+        stream::iter_ok::<_, ()>(vec![1, 2, 3, 4, 5, 6])
+            .actstream()
+            .filter(|item, _: &mut StreamActor, _: &mut Context<StreamActor>| item % 2 == 0)
+            .map(|item, _: &mut StreamActor, _: &mut Context<StreamActor>| item * 10)
+            .take_while(|item, _: &mut StreamActor, _: &mut Context<StreamActor>| {
+                fut::ok(*item < 60)
+            })
+            .fold(0, |sum, item, _: &mut StreamActor, _: &mut Context<StreamActor>| {
+                fut::ok::<_, (), StreamActor>(sum + item)
+            })
+            .map(|sum, act: &mut StreamActor, _: &mut Context<StreamActor>| {
+                act.sum.store(sum == 60, Ordering::Relaxed);
+                Arbiter::system().send(actix::SystemExit(0));
+            })
+            .spawn(ctx);
+    }
+}
+
+#[test]
+fn test_stream_combinators() {
+    let sys = System::new("test".to_owned());
+
+    let sum = Arc::new(AtomicBool::new(false));
+    let _: () = StreamActor{sum: Arc::clone(&sum)}.start();
+
+    sys.run();
+    assert!(sum.load(Ordering::Relaxed), "Wrong sum");
+}
+
+#[derive(PartialEq, Debug)]
+struct MyError(u32);
+
+impl From<u32> for MyError {
+    fn from(err: u32) -> MyError {
+        MyError(err)
+    }
+}
+
+struct CollectActor {
+    joined: Arc<AtomicBool>,
+    failed: Arc<AtomicBool>,
+    timeout: Option<Box<ActorFuture<Item=(), Error=(), Actor=CollectActor>>>,
+}
+
+impl Actor for CollectActor {
+    fn started(&mut self, ctx: &mut Context<CollectActor>) {
+        // future is created outside of arbiter
+        self.timeout.take().unwrap().spawn(ctx);
+
+        fut::join_all(vec![fut::ok::<u32, u32, CollectActor>(1), fut::ok(2), fut::ok(3)])
+            .map(|res, act: &mut CollectActor, _: &mut Context<CollectActor>| {
+                act.joined.store(res == vec![1, 2, 3], Ordering::Relaxed);
+            })
+            .map_err(|_, _: &mut CollectActor, _: &mut Context<CollectActor>| ())
+            .spawn(ctx);
+
+        fut::join_all(vec![fut::ok::<u32, u32, CollectActor>(1), fut::err(2)])
+            .from_err::<MyError>()
+            .map(|_, _: &mut CollectActor, _: &mut Context<CollectActor>| ())
+            .map_err(|err, act: &mut CollectActor, _: &mut Context<CollectActor>| {
+                act.failed.store(err == MyError(2), Ordering::Relaxed);
+                Arbiter::system().send(actix::SystemExit(0));
+            })
+            .spawn(ctx);
+    }
+}
+
+#[test]
+fn test_join_all_from_err() {
+    let timeout = fut::ok::<(), (), CollectActor>(())
+        .timeout(Duration::new(1, 0), ())
+        .boxed();
+
+    let sys = System::new("test".to_owned());
+
+    let joined = Arc::new(AtomicBool::new(false));
+    let failed = Arc::new(AtomicBool::new(false));
+
+    let _: () = CollectActor{
+        joined: Arc::clone(&joined),
+        failed: Arc::clone(&failed),
+        timeout: Some(timeout),
+    }.start();
+
+    sys.run();
+    assert!(joined.load(Ordering::Relaxed), "Not joined");
+    assert!(failed.load(Ordering::Relaxed), "Error is not converted");
+}
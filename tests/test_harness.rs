@@ -0,0 +1,120 @@
+extern crate actix;
+extern crate futures;
+
+use std::time::Duration;
+use actix::prelude::*;
+use actix::test::{Probe, TestSystem};
+
+struct Count(usize);
+struct Die;
+
+struct MyActor {
+    subscriber: Box<Subscriber<usize>>,
+}
+
+impl Actor for MyActor {}
+
+impl MessageResponse<Count> for MyActor {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<Count> for MyActor {
+    fn handle(&mut self, msg: Count, ctx: &mut Context<Self>) -> MessageFuture<Self, Count> {
+        self.subscriber.send(msg.0);
+        if msg.0 > 0 {
+            let addr: Address<_> = ctx.address();
+            addr.send(Count(msg.0 - 1));
+        }
+        ().to_result()
+    }
+}
+
+impl MessageResponse<Die> for MyActor {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<Die> for MyActor {
+    fn handle(&mut self, _: Die, ctx: &mut Context<Self>) -> MessageFuture<Self, Die> {
+        ctx.stop();
+        ().to_result()
+    }
+}
+
+struct Delayed {
+    subscriber: Box<Subscriber<Duration>>,
+}
+
+impl Actor for Delayed {
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        for secs in &[3, 1, 2] {
+            let dur = Duration::from_secs(*secs);
+            futures::future::empty::<(), ()>()
+                .actfuture()
+                .timeout(dur, ())
+                .then(move |_, act: &mut Delayed, _: &mut Context<Delayed>| {
+                    act.subscriber.send(dur);
+                    fut::ok(())
+                })
+                .spawn(ctx);
+        }
+    }
+}
+
+#[test]
+fn test_run_until_idle() {
+    let mut sys = TestSystem::new();
+
+    let probe = Probe::new();
+    let addr: Address<_> = MyActor{subscriber: Box::new(probe.clone())}.start();
+    addr.send(Count(3));
+
+    sys.run_until_idle();
+    assert_eq!(probe.take(), vec![3, 2, 1, 0]);
+    assert!(probe.is_empty());
+}
+
+#[test]
+fn test_lifecycle_state() {
+    let mut sys = TestSystem::new();
+
+    let addr: Address<_> = MyActor{subscriber: Box::new(Probe::<usize>::new())}.start();
+    assert_eq!(sys.state(&addr), ActorState::Running);
+
+    addr.send(Die);
+    assert_eq!(sys.state(&addr), ActorState::Stopped);
+}
+
+#[test]
+fn test_virtual_clock() {
+    let mut sys = TestSystem::new();
+
+    let probe = Probe::new();
+    let _: Address<_> = Delayed{subscriber: Box::new(probe.clone())}.start();
+
+    sys.run_until_idle();
+    assert!(probe.is_empty());
+
+    sys.advance(Duration::from_millis(1500));
+    assert_eq!(sys.now(), Duration::from_millis(1500));
+    assert_eq!(probe.take(), vec![Duration::from_secs(1)]);
+
+    // timers fire in order of deadlines
+    sys.advance(Duration::from_secs(5));
+    assert_eq!(probe.take(), vec![Duration::from_secs(2), Duration::from_secs(3)]);
+}
+
+#[test]
+fn test_closed_probe() {
+    let probe = Probe::new();
+    let subscriber = probe.subscriber();
+
+    subscriber.send(1);
+    assert!(!subscriber.is_closed());
+
+    probe.close();
+    assert!(subscriber.is_closed());
+    assert_eq!(subscriber.unbuffered_send(2), Err(2));
+    assert_eq!(probe.messages(), vec![1]);
+}
@@ -0,0 +1,104 @@
+extern crate actix;
+extern crate futures;
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use futures::Future;
+use actix::prelude::*;
+use actix::ActorId;
+
+struct Id;
+
+struct MyActor {
+    id: Arc<Mutex<Option<ActorId>>>,
+}
+
+impl Actor for MyActor {
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.set_name("worker");
+        *self.id.lock().unwrap() = Some(ctx.id());
+    }
+}
+
+impl SupervisedActor for MyActor {}
+
+impl MessageResponse<Id> for MyActor {
+    type Item = ActorId;
+    type Error = ();
+}
+
+impl MessageHandler<Id> for MyActor {
+    fn handle(&mut self, _: Id, ctx: &mut Context<Self>) -> MessageFuture<Self, Id> {
+        Arbiter::system().send(actix::SystemExit(0));
+        ctx.id().to_result()
+    }
+}
+
+#[test]
+fn test_address_identity() {
+    let sys = System::new("test".to_owned());
+
+    let id = Arc::new(Mutex::new(None));
+    let (addr, saddr): (Address<_>, SyncAddress<_>) = MyActor{id: Arc::clone(&id)}.start();
+    let addr2: Address<_> = MyActor{id: Arc::new(Mutex::new(None))}.start();
+
+    assert_eq!(addr, addr.clone());
+    assert_ne!(addr, addr2);
+    assert_eq!(addr.id(), saddr.id());
+
+    let mut set = HashSet::new();
+    set.insert(addr.clone());
+    set.insert(addr.clone());
+    set.insert(addr2);
+    assert_eq!(set.len(), 2);
+
+    addr.send(Id);
+    sys.run();
+
+    assert_eq!(*id.lock().unwrap(), Some(addr.id()));
+    assert_eq!(saddr.name(), Some("worker".to_owned()));
+    assert!(format!("{:?}", saddr).starts_with("SyncAddress(worker("));
+}
+
+#[test]
+fn test_supervisor_identity() {
+    let sys = System::new("test".to_owned());
+
+    let id = Arc::new(Mutex::new(None));
+    let id2 = Arc::clone(&id);
+    let (addr, saddr) = Supervisor::start(false, move |_| MyActor{id: Arc::clone(&id2)});
+    assert_eq!(addr.id(), saddr.id());
+
+    let result = Arc::new(Mutex::new(None));
+    let result2 = Arc::clone(&result);
+    Arbiter::handle().spawn(
+        saddr.call_fut(Id).then(move |res| {
+            *result2.lock().unwrap() = res.ok().and_then(|res| res.ok());
+            Ok(())
+        }));
+
+    sys.run();
+    assert_eq!(*result.lock().unwrap(), Some(addr.id()));
+    assert_eq!(*id.lock().unwrap(), Some(addr.id()));
+}
+
+#[test]
+fn test_upgrade_identity() {
+    let sys = System::new("test".to_owned());
+
+    let addr: Address<_> = MyActor{id: Arc::new(Mutex::new(None))}.start();
+
+    let result = Arc::new(Mutex::new(None));
+    let result2 = Arc::clone(&result);
+    Arbiter::handle().spawn(
+        addr.upgrade().then(move |res| {
+            let saddr: SyncAddress<_> = res.unwrap();
+            *result2.lock().unwrap() = Some((saddr.id(), saddr.name()));
+            Arbiter::system().send(actix::SystemExit(0));
+            Ok(())
+        }));
+
+    sys.run();
+    // upgraded address shares identity with the context
+    assert_eq!(*result.lock().unwrap(), Some((addr.id(), Some("worker".to_owned()))));
+}
@@ -0,0 +1,155 @@
+extern crate actix;
+
+use std::sync::{Arc, Mutex};
+use actix::prelude::*;
+use actix::metrics::ActorMetrics;
+
+struct Ping;
+struct Report;
+struct Die;
+
+struct MyActor {
+    result: Arc<Mutex<Option<ActorMetrics>>>,
+}
+
+impl Actor for MyActor {}
+
+impl SupervisedActor for MyActor {
+    fn restarting(&mut self, ctx: &mut Context<Self>) {
+        let addr: Address<_> = ctx.address();
+        addr.send(Report);
+    }
+}
+
+impl MessageResponse<Ping> for MyActor {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<Ping> for MyActor {
+    fn handle(&mut self, _: Ping, _: &mut Context<Self>) -> MessageFuture<Self, Ping> {
+        ().to_result()
+    }
+}
+
+impl MessageResponse<Die> for MyActor {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<Die> for MyActor {
+    fn handle(&mut self, _: Die, ctx: &mut Context<Self>) -> MessageFuture<Self, Die> {
+        ctx.stop();
+        ().to_result()
+    }
+}
+
+impl MessageResponse<Report> for MyActor {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<Report> for MyActor {
+    fn handle(&mut self, _: Report, ctx: &mut Context<Self>) -> MessageFuture<Self, Report> {
+        let metrics = System::metrics();
+        *self.result.lock().unwrap() = metrics.actor(ctx.id()).cloned();
+        Arbiter::system().send(actix::SystemExit(0));
+        ().to_result()
+    }
+}
+
+#[test]
+fn test_metrics() {
+    let sys = System::new("test".to_owned());
+    System::enable_timings();
+
+    let result = Arc::new(Mutex::new(None));
+    let (addr, saddr): (Address<_>, SyncAddress<_>) =
+        MyActor{result: Arc::clone(&result)}.start();
+
+    addr.send(Ping);
+    addr.send(Ping);
+    addr.send(Report);
+    addr.send(Ping);
+
+    sys.run();
+
+    // `Report` message is in progress during snapshot
+    let metrics = result.lock().unwrap().take().unwrap();
+    assert_eq!(metrics.id, saddr.id());
+    // type names are placeholders on rust older than 1.38
+    if cfg!(actix_type_name) {
+        assert!(metrics.actor_type.ends_with("MyActor"));
+    }
+    assert_eq!(metrics.received, 3);
+    assert_eq!(metrics.handled, 2);
+    // immediate results are not spawned
+    assert_eq!(metrics.spawned, 0);
+    assert_eq!(metrics.mailbox, 1);
+    assert_eq!(metrics.sync_mailbox, 0);
+    assert_eq!(metrics.restarts, 0);
+    assert_eq!(metrics.handler_time.count, 2);
+    assert_eq!(metrics.handler_time.buckets.len(), 7);
+    // response time is observed only for spawned response futures
+    assert_eq!(metrics.future_time.count, 0);
+}
+
+#[test]
+fn test_timings_disabled() {
+    let sys = System::new("test".to_owned());
+
+    let result = Arc::new(Mutex::new(None));
+    let addr: Address<_> = MyActor{result: Arc::clone(&result)}.start();
+
+    addr.send(Ping);
+    addr.send(Ping);
+    addr.send(Report);
+
+    sys.run();
+
+    // messages are counted, handler time is not measured
+    let metrics = result.lock().unwrap().take().unwrap();
+    assert_eq!(metrics.received, 3);
+    assert_eq!(metrics.handled, 2);
+    assert_eq!(metrics.handler_time.count, 0);
+}
+
+#[test]
+fn test_sync_mailbox() {
+    let sys = System::new("test".to_owned());
+
+    let result = Arc::new(Mutex::new(None));
+    let saddr: SyncAddress<_> = MyActor{result: Arc::clone(&result)}.start();
+
+    saddr.send(Ping);
+    saddr.send(Ping);
+    saddr.send(Report);
+    saddr.send(Ping);
+    saddr.send(Ping);
+
+    sys.run();
+
+    // received messages are not counted, even if channel state
+    // is not updated yet
+    let metrics = result.lock().unwrap().take().unwrap();
+    assert_eq!(metrics.received, 3);
+    assert_eq!(metrics.sync_mailbox, 2);
+}
+
+#[test]
+fn test_supervisor_restarts() {
+    let sys = System::new("test".to_owned());
+
+    let result = Arc::new(Mutex::new(None));
+    let result2 = Arc::clone(&result);
+    let (addr, _) = Supervisor::start(false, move |_| MyActor{result: Arc::clone(&result2)});
+
+    // restarted actor sends `Report` to itself
+    addr.send(Die);
+
+    sys.run();
+
+    let metrics = result.lock().unwrap().take().unwrap();
+    assert_eq!(metrics.id, addr.id());
+    assert_eq!(metrics.restarts, 1);
+}
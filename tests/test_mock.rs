@@ -0,0 +1,131 @@
+extern crate actix;
+extern crate futures;
+
+use futures::{Async, Future};
+use actix::prelude::*;
+use actix::test::{Mock, MockSubscriber, TestContext};
+
+#[derive(Clone, Debug, PartialEq)]
+struct Get(String);
+
+#[derive(Clone, Debug, PartialEq)]
+struct Set(String, String);
+
+struct Storage;
+
+impl Actor for Storage {}
+
+impl MessageResponse<Get> for Storage {
+    type Item = String;
+    type Error = ();
+}
+
+impl MessageHandler<Get> for Storage {
+    fn handle(&mut self, _: Get, _: &mut Context<Self>) -> MessageFuture<Self, Get> {
+        unreachable!("handled by mock")
+    }
+}
+
+impl MessageResponse<Set> for Storage {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<Set> for Storage {
+    fn handle(&mut self, _: Set, _: &mut Context<Self>) -> MessageFuture<Self, Set> {
+        unreachable!("handled by mock")
+    }
+}
+
+struct Lookup(&'static str);
+
+struct Frontend {
+    storage: Address<Storage>,
+    lookups: usize,
+}
+
+impl Actor for Frontend {}
+
+impl MessageResponse<Lookup> for Frontend {
+    type Item = String;
+    type Error = ();
+}
+
+impl MessageHandler<Lookup> for Frontend {
+    fn handle(&mut self, msg: Lookup, _: &mut Context<Self>) -> MessageFuture<Self, Lookup> {
+        self.lookups += 1;
+        if msg.0.is_empty() {
+            return ().to_error()
+        }
+        self.storage.call(Get(msg.0.to_owned()))
+            .then(|res, _: &mut Frontend, _: &mut Context<Frontend>| match res {
+                Ok(Ok(val)) => fut::ok(val),
+                _ => fut::err(()),
+            })
+            .into()
+    }
+}
+
+#[test]
+fn test_detached_context() {
+    let mock = Mock::<Storage>::new();
+    mock.reply(|msg: &Get| Ok(format!("value of {}", msg.0)));
+
+    let mut ctx = TestContext::new(Frontend{storage: mock.address(), lookups: 0});
+    assert_eq!(ctx.call(Lookup("")), Err(()));
+    assert_eq!(ctx.actor().lookups, 1);
+
+    // response future waits for mock
+    let mut fut = ctx.handle(Lookup("key"));
+    assert_eq!(ctx.step(&mut fut), Ok(Async::NotReady));
+    assert_eq!(mock.process(), 1);
+    assert_eq!(ctx.step(&mut fut), Ok(Async::Ready("value of key".to_owned())));
+
+    assert_eq!(ctx.actor().lookups, 2);
+    assert_eq!(mock.messages::<Get>(), vec![Get("key".to_owned())]);
+}
+
+#[test]
+fn test_mock_expect() {
+    let mock = Mock::<Storage>::new();
+    mock.expect::<Set>();
+
+    let addr = mock.address();
+    addr.send(Set("key".to_owned(), "value".to_owned()));
+    addr.send(Get("key".to_owned()));
+    let res = addr.call_fut(Get("key".to_owned()));
+
+    assert_eq!(mock.take::<Set>(), vec![Set("key".to_owned(), "value".to_owned())]);
+    assert!(mock.take::<Set>().is_empty());
+
+    // unscripted messages
+    assert_eq!(mock.unexpected(), vec!["test_mock::Get", "test_mock::Get"]);
+    assert!(res.wait().is_err());
+}
+
+#[test]
+fn test_mock_sync_address() {
+    let mock = Mock::<Storage>::new();
+    mock.reply(|msg: &Get| Ok(msg.0.clone()));
+
+    let addr = mock.sync_address();
+    assert_eq!(addr.id(), mock.address().id());
+
+    let res = addr.call_fut(Get("key".to_owned()));
+    mock.process();
+    assert_eq!(res.wait(), Ok(Ok("key".to_owned())));
+}
+
+#[test]
+fn test_mock_subscriber() {
+    let subscriber = MockSubscriber::new(|msg: &Get| -> Result<usize, ()> { Ok(msg.0.len()) });
+
+    let res = subscriber.call(Get("key".to_owned()));
+    assert_eq!(res.wait(), Ok(Ok(3)));
+    subscriber.send(Get("value".to_owned()));
+    assert_eq!(subscriber.probe().len(), 2);
+
+    subscriber.probe().close();
+    assert!(subscriber.is_closed());
+    assert!(subscriber.unbuffered_call(Get("key".to_owned())).is_err());
+}
@@ -0,0 +1,94 @@
+extern crate actix;
+
+use std::sync::{Arc, Mutex};
+use actix::prelude::*;
+
+struct Msg(Priority);
+
+struct MyActor {
+    received: Arc<Mutex<Vec<Priority>>>,
+    total: usize,
+}
+
+impl Actor for MyActor {}
+
+impl MessageResponse<Msg> for MyActor {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<Msg> for MyActor {
+    fn handle(&mut self, msg: Msg, _: &mut Context<MyActor>) -> MessageFuture<Self, Msg> {
+        let mut received = self.received.lock().unwrap();
+        received.push(msg.0);
+        if received.len() == self.total {
+            Arbiter::system().send(actix::SystemExit(0));
+        }
+        ().to_result()
+    }
+}
+
+const EXPECTED: [Priority; 4] = [Priority::High, Priority::Normal, Priority::Low, Priority::Low];
+
+#[test]
+fn test_address_priority() {
+    let sys = System::new("test".to_owned());
+
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let addr: Address<_> = MyActor{received: Arc::clone(&received), total: 4}.start();
+
+    addr.send_priority(Msg(Priority::Low), Priority::Low);
+    addr.send(Msg(Priority::Normal));
+    addr.send_priority(Msg(Priority::Low), Priority::Low);
+    addr.send_priority(Msg(Priority::High), Priority::High);
+
+    sys.run();
+    assert_eq!(*received.lock().unwrap(), EXPECTED);
+}
+
+#[test]
+fn test_sync_address_priority() {
+    let sys = System::new("test".to_owned());
+
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let addr: SyncAddress<_> = MyActor{received: Arc::clone(&received), total: 4}.start();
+
+    addr.send_priority(Msg(Priority::Low), Priority::Low);
+    addr.send(Msg(Priority::Normal));
+    addr.send_priority(Msg(Priority::Low), Priority::Low);
+    addr.send_priority(Msg(Priority::High), Priority::High);
+
+    sys.run();
+    assert_eq!(*received.lock().unwrap(), EXPECTED);
+}
+
+fn count(received: &[Priority], lane: Priority) -> usize {
+    received.iter().filter(|p| **p == lane).count()
+}
+
+#[test]
+fn test_lanes_make_progress() {
+    let sys = System::new("test".to_owned());
+
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let addr: Address<_> = MyActor{received: Arc::clone(&received), total: 300}.start();
+
+    for _ in 0..100 {
+        addr.send_priority(Msg(Priority::High), Priority::High);
+        addr.send(Msg(Priority::Normal));
+        addr.send_priority(Msg(Priority::Low), Priority::Low);
+    }
+
+    sys.run();
+    let received = received.lock().unwrap();
+
+    // after 16 high priority messages each lower lane gets one message
+    assert_eq!(count(&received[..16], Priority::High), 16);
+    assert_eq!(received[16], Priority::Normal);
+    assert_eq!(received[17], Priority::Low);
+    for chunk in received[..90].chunks(18) {
+        assert_eq!(count(chunk, Priority::High), 16);
+        assert_eq!(count(chunk, Priority::Normal), 1);
+        assert_eq!(count(chunk, Priority::Low), 1);
+    }
+}
@@ -0,0 +1,104 @@
+#![cfg(feature="prometheus")]
+
+extern crate actix;
+extern crate futures;
+extern crate tokio_core;
+extern crate tokio_io;
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use futures::Future;
+use tokio_core::net::TcpStream;
+use actix::prelude::*;
+use actix::actors::prometheus::PrometheusExporter;
+use actix::test::TestSystem;
+
+struct MyActor;
+
+impl Actor for MyActor {
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.set_name("test-actor");
+    }
+}
+
+fn request(req: &'static str) -> String {
+    let sys = System::new("test".to_owned());
+
+    let result = Arc::new(Mutex::new(String::new()));
+    let result2 = Arc::clone(&result);
+
+    let _: () = MyActor.start();
+    let (addr, _): (_, Address<_>) =
+        PrometheusExporter::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+
+    Arbiter::handle().spawn(
+        TcpStream::connect(&addr, Arbiter::handle())
+            .and_then(move |stream| tokio_io::io::write_all(stream, req.as_bytes()))
+            .and_then(|(stream, _)| tokio_io::io::read_to_end(stream, Vec::new()))
+            .then(move |res| {
+                if let Ok((_, body)) = res {
+                    *result2.lock().unwrap() = String::from_utf8_lossy(&body).into_owned();
+                }
+                Arbiter::system().send(actix::SystemExit(0));
+                Ok(())
+            }));
+
+    sys.run();
+    let res = result.lock().unwrap().clone();
+    res
+}
+
+#[test]
+fn test_metrics_endpoint() {
+    let resp = request("GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+    assert!(resp.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(resp.contains("# TYPE actix_actor_messages_received_total counter\n"));
+    assert!(resp.contains("name=\"test-actor\""));
+    // type names are placeholders on rust older than 1.38
+    if cfg!(actix_type_name) {
+        assert!(resp.contains("actor_type=\"test_prometheus::MyActor\""));
+    }
+    assert!(resp.contains("actix_actor_handler_seconds_bucket{"));
+    assert!(resp.contains("\nactix_system_actors "));
+}
+
+#[test]
+fn test_not_found() {
+    let resp = request("GET / HTTP/1.1\r\nHost: localhost\r\n\r\n");
+    assert!(resp.starts_with("HTTP/1.1 404 Not Found\r\n"));
+
+    let resp = request("POST /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n");
+    assert!(resp.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+}
+
+#[test]
+fn test_query_string() {
+    let resp = request("GET /metrics?name=test HTTP/1.1\r\nHost: localhost\r\n\r\n");
+    assert!(resp.starts_with("HTTP/1.1 200 OK\r\n"));
+}
+
+#[test]
+fn test_request_timeout() {
+    let mut sys = TestSystem::new();
+    sys.set_idle_interval(Duration::from_millis(10));
+
+    let (addr, _): (_, Address<_>) =
+        PrometheusExporter::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let stream = sys.run_until_complete(TcpStream::connect(&addr, Arbiter::handle())).unwrap();
+
+    // client does not send request
+    let closed = Arc::new(Mutex::new(None));
+    let closed2 = Arc::clone(&closed);
+    Arbiter::handle().spawn(
+        tokio_io::io::read_to_end(stream, Vec::new()).then(move |res| {
+            *closed2.lock().unwrap() = Some(res.map(|(_, body)| body.len()).ok());
+            Ok(())
+        }));
+
+    sys.advance(Duration::from_millis(4900));
+    assert_eq!(*closed.lock().unwrap(), None);
+
+    sys.advance(Duration::from_millis(100));
+    assert_eq!(*closed.lock().unwrap(), Some(Some(0)));
+}
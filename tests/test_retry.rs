@@ -0,0 +1,113 @@
+extern crate actix;
+extern crate futures;
+
+use std::thread;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use futures::{future, Future};
+use actix::prelude::*;
+use actix::Retry;
+
+struct Query;
+
+struct MyActor {
+    attempts: usize,
+    fail: usize,
+}
+
+impl Actor for MyActor {}
+
+impl SupervisedActor for MyActor {}
+
+impl MessageResponse<Query> for MyActor {
+    type Item = usize;
+    type Error = usize;
+}
+
+impl MessageHandler<Query> for MyActor {
+    fn handle(&mut self, _: Query, ctx: &mut Context<Self>) -> MessageFuture<Self, Query> {
+        self.attempts += 1;
+        if self.attempts <= self.fail {
+            // actor fails in the middle of message processing
+            ctx.stop();
+            MessageFuture::from(fut::wrap_future::<_, Self>(future::empty()))
+        } else if self.attempts < 3 {
+            self.attempts.to_error()
+        } else {
+            self.attempts.to_result()
+        }
+    }
+}
+
+fn run(fail: usize, retry: Retry<usize, usize>) -> Option<Result<usize, usize>> {
+    let sys = System::new("test".to_owned());
+
+    let result = Arc::new(Mutex::new(None));
+    let result2 = Arc::clone(&result);
+
+    let (addr, _) = Supervisor::start(false, move |_| MyActor{attempts: 0, fail: fail});
+    Arbiter::handle().spawn(
+        addr.call_retry(Query, retry)
+            .then(move |res| {
+                if let Ok(res) = res {
+                    *result2.lock().unwrap() = Some(res);
+                }
+                Arbiter::system().send(actix::SystemExit(0));
+                future::result(Ok(()))
+            })
+    );
+
+    sys.run();
+    let res = result.lock().unwrap().take();
+    res
+}
+
+#[test]
+fn test_retry_canceled() {
+    assert_eq!(run(1, Retry::new(3).backoff(Duration::from_millis(10))), Some(Err(2)));
+}
+
+#[test]
+fn test_retry_restarted() {
+    // message dropped by restarting actor is resent without backoff delay
+    let start = Instant::now();
+    assert_eq!(run(1, Retry::new(3).backoff(Duration::from_secs(30))), Some(Err(2)));
+    assert!(start.elapsed() < Duration::from_secs(30));
+}
+
+#[test]
+fn test_retry_predicate() {
+    assert_eq!(run(0, Retry::new(3).backoff(Duration::from_millis(10)).retry_if(|res| res.is_err())), Some(Ok(3)));
+}
+
+#[test]
+fn test_retry_exhausted() {
+    assert_eq!(run(0, Retry::new(2).backoff(Duration::from_millis(10)).retry_if(|res| res.is_err())), Some(Err(2)));
+}
+
+#[test]
+fn test_sync_retry_restarted() {
+    let sys = System::new("test".to_owned());
+
+    let result = Arc::new(Mutex::new(None));
+    let result2 = Arc::clone(&result);
+
+    let (_, addr) = Supervisor::start(false, move |_| MyActor{attempts: 0, fail: 1});
+
+    // retrying call of `SyncAddress` is `Send`, it is started in other thread
+    let call = thread::spawn(
+        move || addr.call_retry(Query, Retry::new(3).backoff(Duration::from_secs(30))))
+        .join().unwrap();
+    Arbiter::handle().spawn(
+        call.then(move |res| {
+            if let Ok(res) = res {
+                *result2.lock().unwrap() = Some(res);
+            }
+            Arbiter::system().send(actix::SystemExit(0));
+            future::result(Ok(()))
+        })
+    );
+
+    sys.run();
+    assert_eq!(*result.lock().unwrap(), Some(Err(2)));
+}
@@ -0,0 +1,164 @@
+extern crate actix;
+
+use std::cell::Cell;
+use std::sync::{Arc, Mutex};
+use actix::prelude::*;
+use actix::actors::router::{Router, Strategy};
+
+#[derive(Clone)]
+struct Job(usize);
+
+struct Stop;
+
+struct Worker {
+    idx: usize,
+    jobs: Arc<Mutex<Vec<(usize, usize)>>>,
+    exit: usize,
+}
+
+impl Actor for Worker {}
+
+impl MessageResponse<Job> for Worker {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<Job> for Worker {
+    fn handle(&mut self, msg: Job, _: &mut Context<Self>) -> MessageFuture<Self, Job> {
+        let mut jobs = self.jobs.lock().unwrap();
+        jobs.push((msg.0, self.idx));
+        if jobs.len() == self.exit {
+            Arbiter::system().send(actix::SystemExit(0));
+        }
+        ().to_result()
+    }
+}
+
+impl MessageResponse<Stop> for Worker {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<Stop> for Worker {
+    fn handle(&mut self, _: Stop, ctx: &mut Context<Self>) -> MessageFuture<Self, Stop> {
+        ctx.stop();
+        ().to_result()
+    }
+}
+
+fn run<F>(strategy: Strategy, jobs: usize, f: F) -> Vec<(usize, usize)>
+    where F: FnOnce(Router<Worker>) -> Router<Worker>
+{
+    let sys = System::new("test".to_owned());
+
+    let result = Arc::new(Mutex::new(Vec::new()));
+    let mut routees = Vec::new();
+    for idx in 0..3 {
+        let addr: SyncAddress<_> =
+            Worker{idx: idx, jobs: Arc::clone(&result), exit: 6}.start();
+        routees.push(addr);
+    }
+    let addr: Address<_> = f(Router::new(strategy, routees)).start();
+
+    for job in 0..jobs {
+        addr.send(Job(job));
+    }
+
+    sys.run();
+    let mut result = result.lock().unwrap().clone();
+    result.sort();
+    result
+}
+
+#[test]
+fn test_round_robin() {
+    let jobs = run(Strategy::RoundRobin, 6, |router| router);
+    assert_eq!(jobs, vec![(0, 0), (1, 1), (2, 2), (3, 0), (4, 1), (5, 2)]);
+}
+
+#[test]
+fn test_random() {
+    let jobs = run(Strategy::Random, 6, |router| router);
+
+    // every job is handled exactly once
+    assert_eq!(jobs.iter().map(|job| job.0).collect::<Vec<_>>(), vec![0, 1, 2, 3, 4, 5]);
+    assert!(jobs.iter().all(|job| job.1 < 3));
+}
+
+#[test]
+fn test_smallest_mailbox() {
+    // router handles all jobs before routees get polled,
+    // so every job goes to routee with shortest queue
+    let jobs = run(Strategy::SmallestMailbox, 6, |router| router);
+    assert_eq!(jobs, vec![(0, 0), (1, 1), (2, 2), (3, 0), (4, 1), (5, 2)]);
+}
+
+#[test]
+fn test_consistent_hash() {
+    let jobs = run(Strategy::ConsistentHash, 6,
+                   |router| router.hash_key(|job: &Job| job.0 % 2));
+    assert_eq!(jobs[0].1, jobs[2].1);
+    assert_eq!(jobs[0].1, jobs[4].1);
+    assert_eq!(jobs[1].1, jobs[3].1);
+    assert_eq!(jobs[1].1, jobs[5].1);
+}
+
+#[test]
+fn test_broadcast() {
+    let jobs = run(Strategy::Broadcast, 2, |router| router.broadcast::<Job>());
+    assert_eq!(jobs, vec![(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2)]);
+}
+
+#[test]
+fn test_broadcast_stopped_routee() {
+    let mut sys = System::new("test".to_owned());
+
+    let jobs = Arc::new(Mutex::new(Vec::new()));
+    let mut routees = Vec::new();
+    for idx in 0..3 {
+        let addr: SyncAddress<_> =
+            Worker{idx: idx, jobs: Arc::clone(&jobs), exit: 0}.start();
+        routees.push(addr);
+    }
+    sys.run_until_complete(routees[2].call_fut(Stop)).unwrap().unwrap();
+
+    // stopped routee is removed, response of last live routee is returned
+    let addr: Address<_> = Router::new(Strategy::Broadcast, routees)
+        .broadcast::<Job>().start();
+    let res = sys.run_until_complete(addr.call_fut(Job(0))).unwrap();
+    assert!(res.is_ok());
+
+    let mut jobs = jobs.lock().unwrap().clone();
+    jobs.sort();
+    assert_eq!(jobs, vec![(0, 0), (0, 1)]);
+}
+
+#[test]
+fn test_pool_resize() {
+    let sys = System::new("test".to_owned());
+
+    let jobs = Arc::new(Mutex::new(Vec::new()));
+    let jobs2 = Arc::clone(&jobs);
+    let next = Cell::new(0);
+    let router = Router::<Worker>::from_factory(Strategy::RoundRobin, 2, move || {
+        let idx = next.get();
+        next.set(idx + 1);
+        Worker{idx: idx, jobs: Arc::clone(&jobs2), exit: 6}.start()
+    });
+    let pool = router.pool();
+    assert_eq!(pool.len(), 2);
+    assert_eq!(pool.resize(4), 4);
+    assert_eq!(pool.resize(1), 1);
+    // pool grows with new routee
+    assert_eq!(pool.resize(2), 2);
+    let addr: Address<_> = router.start();
+
+    for job in 0..6 {
+        addr.send(Job(job));
+    }
+
+    sys.run();
+    let mut jobs = jobs.lock().unwrap().clone();
+    jobs.sort();
+    assert_eq!(jobs, vec![(0, 0), (1, 4), (2, 0), (3, 4), (4, 0), (5, 4)]);
+}
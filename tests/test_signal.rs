@@ -0,0 +1,108 @@
+#![cfg(all(unix, feature="signal"))]
+extern crate actix;
+extern crate futures;
+extern crate libc;
+extern crate tokio_core;
+
+use std::io;
+use std::time::Duration;
+use futures::Future;
+use tokio_core::reactor::Timeout;
+use actix::prelude::*;
+use actix::actors::signal::{
+    ProcessSignals, Signal, SignalType, Subscribe, SubscribeSignals, Unsubscribe};
+use actix::test::{Probe, TestSystem};
+
+// Stops system once both raised signals are observed
+struct Listener(Vec<SignalType>);
+
+impl Actor for Listener {}
+
+impl MessageResponse<Signal> for Listener {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<Signal> for Listener {
+    fn handle(&mut self, msg: Signal, _: &mut Context<Self>) -> MessageFuture<Self, Signal> {
+        self.0.push(msg.0);
+        if self.0.contains(&SignalType::Usr2) && self.0.contains(&SignalType::Winch) {
+            Arbiter::system().send(actix::SystemExit(0));
+        }
+        ().to_result()
+    }
+}
+
+fn raise(sig: libc::c_int) {
+    assert_eq!(unsafe { libc::kill(libc::getpid(), sig) }, 0);
+}
+
+fn signals(probe: &Probe<Signal>) -> Vec<SignalType> {
+    probe.take().into_iter().map(|sig| sig.0).collect()
+}
+
+#[test]
+fn test_signal_subscriptions() {
+    let sys = System::new("test".to_owned());
+
+    let addr: SyncAddress<_> = ProcessSignals::run();
+    let listener: SyncAddress<_> = Listener(Vec::new()).start();
+    let usr2 = Probe::new();
+    let winch = Probe::new();
+    let unsubscribed = Probe::new();
+    let default = Probe::new();
+
+    // subscription result is available once handlers are installed,
+    // signals are raised after unsubscribe is processed
+    let both = [SignalType::Usr2, SignalType::Winch];
+    let addr2 = addr.clone();
+    let subscribed = addr.call_fut(SubscribeSignals::new(&[SignalType::Usr2], usr2.subscriber()))
+        .join(addr.call_fut(SubscribeSignals::new(&[SignalType::Winch], winch.subscriber())))
+        .join(addr.call_fut(SubscribeSignals::new(&both, listener.subscriber())))
+        .join(addr.call_fut(SubscribeSignals::new(&both, unsubscribed.subscriber())))
+        .join(addr.call_fut(Subscribe(default.subscriber())))
+        .and_then(move |((((usr2, winch), listener), id), default)| {
+            usr2.unwrap();
+            winch.unwrap();
+            listener.unwrap();
+            // subscriptions get distinct identifiers
+            assert!(default.unwrap() != *id.as_ref().unwrap());
+            addr2.call_fut(Unsubscribe(id.unwrap()))
+        })
+        .map(|_| {
+            raise(libc::SIGUSR2);
+            raise(libc::SIGWINCH);
+        })
+        .map_err(|_| panic!("ProcessSignals is stopped"));
+    Arbiter::handle().spawn(subscribed);
+
+    // fail instead of waiting forever
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_secs(10), Arbiter::handle()).unwrap()
+            .then(|_| {
+                Arbiter::system().send(actix::SystemExit(1));
+                Ok(())
+            }));
+
+    assert_eq!(sys.run(), 0);
+
+    // each subscriber gets selected signals only
+    assert_eq!(signals(&usr2), vec![SignalType::Usr2]);
+    let winch = signals(&winch);
+    assert!(!winch.is_empty());
+    assert!(winch.iter().all(|sig| *sig == SignalType::Winch));
+    assert!(unsubscribed.is_empty());
+    // selected signals are not delivered to subscribers of default signals
+    assert!(default.is_empty());
+}
+
+#[test]
+fn test_invalid_realtime_signal() {
+    let mut sys = TestSystem::new();
+
+    let addr: SyncAddress<_> = ProcessSignals::run();
+    let probe = Probe::new();
+    let res = sys.run_until_complete(
+        addr.call_fut(SubscribeSignals::new(&[SignalType::RealTime(0)], probe.subscriber())));
+    assert_eq!(res.unwrap().unwrap_err().kind(), io::ErrorKind::InvalidInput);
+}
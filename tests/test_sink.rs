@@ -0,0 +1,243 @@
+extern crate actix;
+extern crate futures;
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use futures::{Async, AsyncSink, Future, Poll, StartSend};
+use actix::prelude::*;
+
+#[derive(PartialEq, Debug)]
+enum Event {
+    Item(u32),
+    SinkClosed,
+    Sent,
+    Canceled,
+    Error(u32),
+    Drained,
+    Closed,
+}
+
+type Events = Rc<RefCell<Vec<Event>>>;
+
+/// Sink records items, fails on `fail` item
+struct TestSink {
+    events: Events,
+    fail: Option<u32>,
+}
+
+impl futures::Sink for TestSink {
+    type SinkItem = u32;
+    type SinkError = u32;
+
+    fn start_send(&mut self, item: u32) -> StartSend<u32, u32> {
+        if Some(item) == self.fail {
+            return Err(item)
+        }
+        self.events.borrow_mut().push(Event::Item(item));
+        Ok(AsyncSink::Ready)
+    }
+
+    fn poll_complete(&mut self) -> Poll<(), u32> {
+        Ok(Async::Ready(()))
+    }
+
+    fn close(&mut self) -> Poll<(), u32> {
+        self.events.borrow_mut().push(Event::SinkClosed);
+        Ok(Async::Ready(()))
+    }
+}
+
+struct SinkActor {
+    events: Events,
+    fail: Option<u32>,
+    observe: bool,
+    sink: Option<actix::Sink<u32, u32>>,
+}
+
+impl SinkActor {
+    fn new(fail: Option<u32>, observe: bool) -> (SinkActor, Events) {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        (SinkActor{events: Rc::clone(&events), fail: fail, observe: observe, sink: None},
+         events)
+    }
+}
+
+impl Actor for SinkActor {
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        let sink = ctx.add_sink(TestSink{events: Rc::clone(&self.events), fail: self.fail});
+        if self.observe {
+            ctx.observe_sink(&sink);
+        }
+
+        sink.send(1);
+        sink.call(2).actfuture()
+            .map(|res, act: &mut SinkActor, _| {
+                act.events.borrow_mut().push(
+                    if res.is_ok() { Event::Sent } else { Event::Error(2) });
+                // all items are sent, close sink
+                act.sink.as_ref().unwrap().close();
+            })
+            .map_err(|_, act: &mut SinkActor, _| {
+                act.events.borrow_mut().push(Event::Canceled);
+                Arbiter::system().send(actix::SystemExit(0));
+            })
+            .spawn(ctx);
+        self.sink = Some(sink);
+    }
+}
+
+impl SinkHandler<u32> for SinkActor {
+
+    fn error(&mut self, err: u32, _: &mut Context<Self>) {
+        let sink = self.sink.as_ref().unwrap();
+        assert!(sink.is_closed());
+        assert_eq!(sink.buffered(), 0);
+        self.events.borrow_mut().push(Event::Error(err));
+
+        // failed sink does not accept items and could not be closed again
+        assert_eq!(sink.unbuffered_send(3), Err(3));
+        sink.close();
+    }
+
+    fn drained(&mut self, _: &mut Context<Self>) {
+        self.events.borrow_mut().push(Event::Drained);
+    }
+
+    fn closed(&mut self, _: &mut Context<Self>) {
+        assert!(self.sink.as_ref().unwrap().is_closed());
+        self.events.borrow_mut().push(Event::Closed);
+        Arbiter::system().send(actix::SystemExit(0));
+    }
+}
+
+#[test]
+fn test_sink_error() {
+    let sys = System::new("test".to_owned());
+
+    let (act, events) = SinkActor::new(Some(2), true);
+    let _: () = act.start();
+    sys.run();
+
+    // error get reported to the actor, caller receives `Canceled`
+    assert_eq!(*events.borrow(), vec![Event::Item(1), Event::Error(2), Event::Canceled]);
+}
+
+#[test]
+fn test_sink_error_not_observed() {
+    let sys = System::new("test".to_owned());
+
+    let (act, events) = SinkActor::new(Some(2), false);
+    let _: () = act.start();
+    sys.run();
+
+    assert_eq!(*events.borrow(), vec![Event::Item(1), Event::Canceled]);
+}
+
+#[test]
+fn test_sink_close() {
+    let sys = System::new("test".to_owned());
+
+    let (act, events) = SinkActor::new(None, true);
+    let _: () = act.start();
+    sys.run();
+
+    let events: Vec<_> = events.borrow_mut().drain(..)
+        .filter(|ev| *ev != Event::Drained).collect();
+    assert_eq!(events, vec![Event::Item(1), Event::Item(2), Event::Sent,
+                            Event::SinkClosed, Event::Closed]);
+}
+
+#[test]
+fn test_sink_error_then_close() {
+    let sys = System::new("test".to_owned());
+
+    let (act, events) = SinkActor::new(Some(1), true);
+    let _: () = act.start();
+    sys.run();
+
+    // pending items get dropped, `closed` is not called for failed sink
+    assert_eq!(*events.borrow(), vec![Event::Error(1), Event::Canceled]);
+}
+
+struct CapacityActor {
+    events: Events,
+}
+
+impl Actor for CapacityActor {
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        let sink = ctx.add_sink(TestSink{events: Rc::clone(&self.events), fail: None});
+
+        // by default unbuffered operations succeed only if buffer is empty
+        assert_eq!(sink.unbuffered_send(1), Ok(()));
+        assert_eq!(sink.unbuffered_send(2), Err(2));
+        assert_eq!(sink.unbuffered_call(2).err(), Some(2));
+
+        sink.set_capacity(3);
+        assert_eq!(sink.unbuffered_send(2), Ok(()));
+        let call = match sink.unbuffered_call(3) {
+            Ok(call) => call,
+            Err(_) => panic!("sink buffer is not full"),
+        };
+        assert_eq!(sink.unbuffered_send(4), Err(4));
+
+        // buffered operations are not limited
+        sink.send(4);
+        assert_eq!(sink.buffered(), 4);
+
+        call.actfuture()
+            .map(|_, _: &mut CapacityActor, _| Arbiter::system().send(actix::SystemExit(0)))
+            .map_err(|_, _, _| panic!("sink call is canceled"))
+            .spawn(ctx);
+    }
+}
+
+#[test]
+fn test_sink_capacity() {
+    let sys = System::new("test".to_owned());
+
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let _: () = CapacityActor{events: Rc::clone(&events)}.start();
+    sys.run();
+
+    assert_eq!(*events.borrow(),
+               vec![Event::Item(1), Event::Item(2), Event::Item(3), Event::Item(4)]);
+}
+
+struct StopActor {
+    events: Events,
+    sink: Rc<RefCell<Option<actix::Sink<u32, u32>>>>,
+}
+
+impl Actor for StopActor {
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        let sink = ctx.add_sink(TestSink{events: Rc::clone(&self.events), fail: None});
+        *self.sink.borrow_mut() = Some(sink);
+        ctx.stop();
+    }
+
+    fn stopped(&mut self, _: &mut Context<Self>) {
+        Arbiter::system().send(actix::SystemExit(0));
+    }
+}
+
+#[test]
+fn test_sink_context_dropped() {
+    let sys = System::new("test".to_owned());
+
+    let sink = Rc::new(RefCell::new(None));
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let _: () = StopActor{events: Rc::clone(&events), sink: Rc::clone(&sink)}.start();
+    sys.run();
+
+    // sink context get dropped with actor's context
+    let sink = sink.borrow_mut().take().unwrap();
+    assert!(sink.is_closed());
+    sink.send(1);
+    assert_eq!(sink.buffered(), 0);
+    assert_eq!(sink.unbuffered_send(2), Err(2));
+    assert!(sink.call(3).wait().is_err());
+    assert!(events.borrow().is_empty());
+}
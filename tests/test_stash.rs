@@ -0,0 +1,143 @@
+extern crate actix;
+extern crate futures;
+
+use std::sync::{Arc, Mutex};
+use futures::{future, Future};
+use actix::prelude::*;
+use actix::Stash;
+use actix::test::{TestContext, TestSystem};
+
+struct Query(usize);
+struct Connected;
+struct Reset;
+
+struct DbActor {
+    connected: bool,
+    stash: Stash<DbActor>,
+    handled: Arc<Mutex<Vec<usize>>>,
+}
+
+impl Actor for DbActor {}
+
+impl MessageResponse<Query> for DbActor {
+    type Item = usize;
+    type Error = ();
+}
+
+impl MessageHandler<Query> for DbActor {
+    fn handle(&mut self, msg: Query, _: &mut Context<Self>) -> MessageFuture<Self, Query> {
+        if !self.connected {
+            return self.stash.defer(msg)
+        }
+        self.handled.lock().unwrap().push(msg.0);
+        (msg.0 * 10).to_result()
+    }
+}
+
+impl MessageResponse<Connected> for DbActor {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<Connected> for DbActor {
+    fn handle(&mut self, _: Connected, ctx: &mut Context<Self>)
+              -> MessageFuture<Self, Connected>
+    {
+        assert_eq!(self.stash.len(), 2);
+        self.connected = true;
+        ctx.unstash_all(&mut self.stash);
+        assert!(self.stash.is_empty());
+        ().to_result()
+    }
+}
+
+impl MessageResponse<Reset> for DbActor {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<Reset> for DbActor {
+    fn handle(&mut self, _: Reset, _: &mut Context<Self>) -> MessageFuture<Self, Reset> {
+        // drop stashed messages
+        self.stash = Stash::new();
+        ().to_result()
+    }
+}
+
+#[test]
+fn test_stash() {
+    let sys = System::new("test".to_owned());
+
+    let handled = Arc::new(Mutex::new(Vec::new()));
+    let response = Arc::new(Mutex::new(None));
+    let response2 = Arc::clone(&response);
+
+    let addr: SyncAddress<_> = DbActor{
+        connected: false, stash: Stash::new(), handled: Arc::clone(&handled)}.start();
+
+    addr.send(Query(1));
+    Arbiter::handle().spawn(
+        addr.call_fut(Query(2))
+            .then(move |res| {
+                if let Ok(Ok(res)) = res {
+                    *response2.lock().unwrap() = Some(res);
+                }
+                Arbiter::system().send(actix::SystemExit(0));
+                future::result(Ok(()))
+            })
+    );
+    addr.send(Connected);
+
+    sys.run();
+    assert_eq!(*handled.lock().unwrap(), vec![1, 2]);
+    assert_eq!(*response.lock().unwrap(), Some(20));
+}
+
+#[test]
+fn test_stash_dropped() {
+    let mut sys = System::new("test".to_owned());
+
+    let handled = Arc::new(Mutex::new(Vec::new()));
+    let addr: Address<_> = DbActor{
+        connected: false, stash: Stash::new(), handled: Arc::clone(&handled)}.start();
+
+    // caller receives `Canceled` if stashed message get dropped
+    let res = addr.call_fut(Query(1));
+    addr.send(Reset);
+    assert!(sys.run_until_complete(res).is_err());
+    assert!(handled.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_unstash_with_queued_messages() {
+    let mut sys = TestSystem::new();
+
+    let handled = Arc::new(Mutex::new(Vec::new()));
+    let addr: Address<_> = DbActor{
+        connected: false, stash: Stash::new(), handled: Arc::clone(&handled)}.start();
+
+    // messages 3 and 4 are queued before stashed messages are replayed
+    addr.send(Query(1));
+    addr.send(Query(2));
+    addr.send(Connected);
+    addr.send(Query(3));
+    addr.send(Query(4));
+    sys.run_until_idle();
+
+    // replayed and queued messages could interleave,
+    // but each group keeps its order
+    let handled = handled.lock().unwrap();
+    assert_eq!(handled.len(), 4);
+    let stashed: Vec<_> = handled.iter().filter(|n| **n <= 2).cloned().collect();
+    let queued: Vec<_> = handled.iter().filter(|n| **n > 2).cloned().collect();
+    assert_eq!(stashed, vec![1, 2]);
+    assert_eq!(queued, vec![3, 4]);
+}
+
+#[test]
+#[should_panic(expected = "Future of stashed message is polled")]
+fn test_poll_deferred() {
+    let mut ctx = TestContext::new(DbActor{
+        connected: false, stash: Stash::new(), handled: Arc::new(Mutex::new(Vec::new()))});
+    let _ = ctx.call(Query(1));
+}
@@ -0,0 +1,63 @@
+extern crate actix;
+extern crate futures;
+extern crate tokio_io;
+
+use std::env;
+use std::io::{Read, Write};
+use std::process::{Command, Stdio as ProcessStdio};
+use futures::Future;
+use actix::prelude::*;
+use actix::transport::{self, Stdio};
+
+const CHILD_ENV: &str = "ACTIX_TEST_STDIO_CHILD";
+
+struct Echo {
+    io: Option<Stdio>,
+}
+
+impl Actor for Echo {
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        let io = self.io.take().unwrap();
+        tokio_io::io::read_to_end(io, Vec::new())
+            .and_then(|(io, buf)| {
+                let upper = String::from_utf8_lossy(&buf).to_uppercase();
+                tokio_io::io::write_all(io, upper.into_bytes())
+            })
+            .then(|_| {
+                Arbiter::system().send(actix::SystemExit(0));
+                Ok(())
+            })
+            .actfuture()
+            .spawn(ctx);
+    }
+}
+
+/// Echo actor over stdio, runs in child process only
+#[test]
+fn stdio_echo_child() {
+    if env::var(CHILD_ENV).is_err() {
+        return
+    }
+    let sys = System::new("test".to_owned());
+    let _: () = transport::start_stdio(|io, _| Echo{io: Some(io)}).unwrap();
+    sys.run();
+}
+
+#[test]
+fn test_stdio() {
+    let mut child = Command::new(env::current_exe().unwrap())
+        .args(&["stdio_echo_child", "--exact", "--nocapture", "--test-threads=1"])
+        .env(CHILD_ENV, "1")
+        .stdin(ProcessStdio::piped())
+        .stdout(ProcessStdio::piped())
+        .spawn()
+        .unwrap();
+
+    // closing stdin sends EOF to the reader thread
+    child.stdin.take().unwrap().write_all(b"hello stdio\n").unwrap();
+
+    let mut out = String::new();
+    child.stdout.take().unwrap().read_to_string(&mut out).unwrap();
+    assert!(child.wait().unwrap().success());
+    assert!(out.contains("HELLO STDIO\n"));
+}
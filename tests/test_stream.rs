@@ -0,0 +1,176 @@
+extern crate actix;
+extern crate futures;
+extern crate tokio_core;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+use futures::stream;
+use tokio_core::reactor::Timeout;
+use actix::prelude::*;
+
+struct Num(usize);
+
+struct MyActor {
+    items: Arc<AtomicUsize>,
+    errors: Arc<AtomicUsize>,
+    stopped: Arc<AtomicBool>,
+    control: StreamControl,
+    end: Option<StreamEnd>,
+}
+
+impl Actor for MyActor {
+    fn stopped(&mut self, _: &mut Context<MyActor>) {
+        self.stopped.store(true, Ordering::Relaxed);
+        Arbiter::system().send(actix::SystemExit(0));
+    }
+}
+
+impl MessageResponse<Num> for MyActor {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<Num, ()> for MyActor {
+    fn handle(&mut self, msg: Num, _: &mut Context<MyActor>) -> MessageFuture<Self, Num> {
+        self.items.fetch_add(msg.0, Ordering::Relaxed);
+        ().to_result()
+    }
+}
+
+impl StreamHandler<Num, ()> for MyActor {
+
+    fn error(&mut self, _: (), _: &mut Context<MyActor>) -> StreamControl {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+        self.control
+    }
+
+    fn stream_finished(&mut self, reason: StreamEnd, _: &mut Context<MyActor>) {
+        if Some(reason) == self.end && self.control != StreamControl::StopActor {
+            Arbiter::system().send(actix::SystemExit(0));
+        }
+    }
+}
+
+fn run(control: StreamControl, end: StreamEnd) -> (usize, usize, bool) {
+    run_stream(control, end, false)
+}
+
+// Register stream with `start_with()` or with `Context::add_stream()`
+fn run_stream(control: StreamControl, end: StreamEnd, add_stream: bool)
+              -> (usize, usize, bool)
+{
+    let sys = System::new("test".to_owned());
+
+    let items = Arc::new(AtomicUsize::new(0));
+    let errors = Arc::new(AtomicUsize::new(0));
+    let stopped = Arc::new(AtomicBool::new(false));
+
+    let act = MyActor{items: Arc::clone(&items),
+                      errors: Arc::clone(&errors),
+                      stopped: Arc::clone(&stopped),
+                      control: control,
+                      end: Some(end)};
+    let items_stream = stream::iter_result(vec![Ok(Num(1)), Err(()), Ok(Num(2))]);
+    // address keeps actor alive after stream completion
+    let _addr: Address<_> = if add_stream {
+        MyActor::create(move |ctx| {
+            ctx.add_stream(items_stream);
+            act
+        })
+    } else {
+        act.start_with(items_stream)
+    };
+
+    sys.run();
+    (items.load(Ordering::Relaxed), errors.load(Ordering::Relaxed),
+     stopped.load(Ordering::Relaxed))
+}
+
+#[test]
+fn test_stream_error_continue() {
+    assert_eq!(run(StreamControl::Continue, StreamEnd::Completed), (3, 1, false));
+}
+
+#[test]
+fn test_stream_error_stop() {
+    assert_eq!(run(StreamControl::StopStream, StreamEnd::Failed), (1, 1, false));
+}
+
+#[test]
+fn test_stream_error_stop_actor() {
+    assert_eq!(run(StreamControl::StopActor, StreamEnd::Failed), (1, 1, true));
+}
+
+#[test]
+fn test_add_stream_error_continue() {
+    assert_eq!(run_stream(StreamControl::Continue, StreamEnd::Completed, true), (3, 1, false));
+}
+
+#[test]
+fn test_add_stream_error_stop() {
+    assert_eq!(run_stream(StreamControl::StopStream, StreamEnd::Failed, true), (1, 1, false));
+}
+
+struct Limited {
+    inflight: usize,
+    max_inflight: Arc<AtomicUsize>,
+    handled: usize,
+    finished: Arc<AtomicUsize>,
+}
+
+impl Actor for Limited {}
+
+impl MessageResponse<Num> for Limited {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<Num, ()> for Limited {
+    fn handle(&mut self, _: Num, _: &mut Context<Limited>) -> MessageFuture<Self, Num> {
+        self.inflight += 1;
+        if self.inflight > self.max_inflight.load(Ordering::Relaxed) {
+            self.max_inflight.store(self.inflight, Ordering::Relaxed);
+        }
+
+        // slow handler
+        Timeout::new(Duration::from_millis(5), Arbiter::handle()).unwrap()
+            .actfuture()
+            .map(|_, act: &mut Limited, _: &mut Context<Limited>| {
+                act.inflight -= 1;
+                act.handled += 1;
+            })
+            .map_err(|_, _: &mut Limited, _: &mut Context<Limited>| ())
+            .into()
+    }
+}
+
+impl StreamHandler<Num, ()> for Limited {
+
+    fn stream_finished(&mut self, _: StreamEnd, _: &mut Context<Limited>) {
+        // all in-flight handlers are completed
+        assert_eq!(self.inflight, 0);
+        self.finished.store(self.handled, Ordering::Relaxed);
+        Arbiter::system().send(actix::SystemExit(0));
+    }
+}
+
+#[test]
+fn test_stream_limit() {
+    let sys = System::new("test".to_owned());
+
+    let max_inflight = Arc::new(AtomicUsize::new(0));
+    let max = Arc::clone(&max_inflight);
+    let finished = Arc::new(AtomicUsize::new(0));
+    let fin = Arc::clone(&finished);
+
+    let _: () = Limited::create(move |ctx| {
+        ctx.add_stream_with_limit(
+            stream::iter_ok::<_, ()>((0..10).map(Num)), 2);
+        Limited{inflight: 0, max_inflight: max, handled: 0, finished: fin}
+    });
+
+    sys.run();
+    assert_eq!(max_inflight.load(Ordering::Relaxed), 2);
+    assert_eq!(finished.load(Ordering::Relaxed), 10);
+}
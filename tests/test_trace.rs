@@ -0,0 +1,151 @@
+extern crate actix;
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+use actix::prelude::*;
+use actix::test::TestSystem;
+use actix::trace::{self, Interceptor, MessageInfo, TraceContext};
+
+struct Recorder(Arc<Mutex<Vec<(String, &'static str, Option<TraceContext>)>>>);
+
+impl Interceptor for Recorder {
+    fn before(&self, info: &MessageInfo) {
+        self.0.lock().unwrap().push(
+            ("before".to_owned(), info.message_type, info.trace.cloned()));
+    }
+
+    fn after(&self, info: &MessageInfo) {
+        self.0.lock().unwrap().push(
+            ("after".to_owned(), info.message_type, info.trace.cloned()));
+    }
+}
+
+struct Ping;
+struct Pong;
+
+struct First {
+    second: SyncAddress<Second>,
+}
+
+impl Actor for First {}
+
+impl MessageResponse<Ping> for First {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<Ping> for First {
+    fn handle(&mut self, _: Ping, _: &mut Context<Self>) -> MessageFuture<Self, Ping> {
+        self.second.send(Pong);
+        ().to_result()
+    }
+}
+
+struct Second(Arc<Mutex<Option<TraceContext>>>);
+
+impl Actor for Second {}
+
+impl MessageResponse<Pong> for Second {
+    type Item = ();
+    type Error = ();
+}
+
+impl MessageHandler<Pong> for Second {
+    fn handle(&mut self, _: Pong, _: &mut Context<Self>) -> MessageFuture<Self, Pong> {
+        *self.0.lock().unwrap() = trace::current();
+        Arbiter::system().send(actix::SystemExit(0));
+        ().to_result()
+    }
+}
+
+#[test]
+// events are matched by message type name, names require rust 1.38
+#[cfg_attr(not(actix_type_name), ignore)]
+fn test_trace_propagation() {
+    let sys = System::new("test".to_owned());
+
+    let records = Arc::new(Mutex::new(Vec::new()));
+    System::add_interceptor(Recorder(Arc::clone(&records)));
+
+    let current = Arc::new(Mutex::new(None));
+    let second: SyncAddress<_> = Second(Arc::clone(&current)).start();
+    let first: Address<_> = First{second: second}.start();
+
+    let root = TraceContext::new();
+    trace::with_context(root.clone(), || first.send(Ping));
+    assert_eq!(trace::current(), None);
+
+    sys.run();
+
+    let records = records.lock().unwrap();
+    let event = |ev: &str, tp: &str| records.iter()
+        .find(|&&(ref e, t, _)| e == ev && t == tp).map(|r| r.2.clone().unwrap());
+    assert_eq!(records[0].1, "test_trace::Ping");
+    assert_eq!(event("before", "test_trace::Ping"), event("after", "test_trace::Ping"));
+
+    // first actor handles message in child span of the root span
+    let first_span = event("before", "test_trace::Ping").unwrap();
+    assert_eq!(first_span.trace_id, root.trace_id);
+    assert_eq!(first_span.parent_id, Some(root.span_id));
+
+    // span is propagated across sync address
+    let second_span = current.lock().unwrap().clone().unwrap();
+    assert_eq!(second_span.trace_id, root.trace_id);
+    assert_eq!(second_span.parent_id, Some(first_span.span_id));
+    assert_eq!(event("before", "test_trace::Pong"), Some(second_span));
+}
+
+#[test]
+fn test_no_trace() {
+    let sys = System::new("test".to_owned());
+
+    let current = Arc::new(Mutex::new(Some(TraceContext::new())));
+    let second: Address<_> = Second(Arc::clone(&current)).start();
+    second.send(Pong);
+
+    sys.run();
+    assert_eq!(*current.lock().unwrap(), None);
+}
+
+#[test]
+fn test_with_context_panic() {
+    let res = ::std::panic::catch_unwind(|| {
+        trace::with_context(TraceContext::new(), || panic!("handler failed"))
+    });
+    assert!(res.is_err());
+    // previous context is restored after panic
+    assert_eq!(trace::current(), None);
+}
+
+#[test]
+fn test_handler_panic() {
+    let sys = System::new("test".to_owned());
+
+    let addr: Address<_> = Quiet(true).start();
+    trace::with_context(TraceContext::new(), || addr.send(Ping));
+
+    let res = panic::catch_unwind(AssertUnwindSafe(move || sys.run()));
+    assert!(res.is_err());
+    // span of the message is not current after panic
+    assert_eq!(trace::current(), None);
+}
+
+#[test]
+fn test_interceptor_added_later() {
+    let mut sys = TestSystem::new();
+
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let addr: Address<_> = Quiet(false).start();
+    addr.send(Ping);
+    sys.run_until_idle();
+
+    // arbiter's interceptors are refreshed after registration
+    System::add_interceptor(Recorder(Arc::clone(&records)));
+    addr.send(Ping);
+    sys.run_until_idle();
+
+    let records = records.lock().unwrap();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].0, "before");
+    assert_eq!(records[1].0, "after");
+}
@@ -0,0 +1,53 @@
+#![cfg(all(unix, feature="uds"))]
+
+extern crate actix;
+extern crate futures;
+
+use std::env;
+use std::fs;
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use actix::prelude::*;
+use actix::transport::{self, UnixStream};
+
+struct Conn {
+    _stream: UnixStream,
+    started: Arc<AtomicUsize>,
+}
+
+impl Actor for Conn {
+    fn started(&mut self, _: &mut Context<Conn>) {
+        if self.started.fetch_add(1, Ordering::Relaxed) == 1 {
+            Arbiter::system().send(actix::SystemExit(0));
+        }
+    }
+}
+
+#[test]
+fn test_unix_listener() {
+    let sys = System::new("test".to_owned());
+
+    // unique path, parallel test runs do not collide
+    let path = env::temp_dir().join(
+        format!("actix-test-uds-{}-{}.sock", process::id(),
+                SystemTime::now().duration_since(UNIX_EPOCH).unwrap().subsec_nanos()));
+    let _ = fs::remove_file(&path);
+
+    let started = Arc::new(AtomicUsize::new(0));
+
+    let started2 = Arc::clone(&started);
+    transport::start_unix_listener(&path, move |stream, _| {
+        Conn{_stream: stream, started: Arc::clone(&started2)}
+    }).unwrap();
+
+    let started2 = Arc::clone(&started);
+    let _: () = transport::start_unix_stream(&path, move |stream, _| {
+        Conn{_stream: stream, started: started2}
+    }).unwrap();
+
+    sys.run();
+    let _ = fs::remove_file(&path);
+    assert_eq!(started.load(Ordering::Relaxed), 2);
+}